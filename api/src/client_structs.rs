@@ -1,8 +1,10 @@
 #![allow(non_snake_case)] // backwards compatibility
 use cribbage_library::{
-    cards::{Card, Rank, Suit},
+    cards::{parse_hand, Card, Rank, Suit},
     cribbage_errors::{CribbageError, CribbageErrorKind},
+    discard_analysis::DiscardOption,
     scoring::{Combination, CombinationName, Score},
+    variant::Variant,
 };
 use serde::{Deserialize, Serialize};
 
@@ -14,10 +16,10 @@ pub struct CutCards {
 impl CutCards {
     pub fn new(p_index: usize, c_index: usize) -> Result<CutCards, CribbageError> {
         if p_index > 51 || c_index > 51 {
-            return Err(CribbageError {
-                message: format!("card index can't be > 51"),
-                error_kind: CribbageErrorKind::BadCard,
-            });
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadCard,
+                format!("card index can't be > 51"),
+            ));
         }
         Ok(CutCards {
             Player: ClientCard::from_card(Card::from_index(p_index), "Player".to_string()),
@@ -58,6 +60,9 @@ impl ScoreInfo {
 pub struct ScoreResponse {
     pub Score: u32,
     pub ScoreInfo: Vec<ScoreInfo>,
+    /// the count as it is said at the table.  only sent when asked for with ?explain=true
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub Explanation: Option<String>,
 }
 
 impl ScoreResponse {
@@ -65,10 +70,17 @@ impl ScoreResponse {
         ScoreResponse {
             Score: 0,
             ScoreInfo: Vec::<ScoreInfo>::default(),
+            Explanation: None,
         }
     }
 
     pub fn from_score(score: Score) -> ScoreResponse {
+        ScoreResponse::from_score_explained(score, false)
+    }
+
+    /// like `from_score`, and adds `score.explain()` if `explain` is true
+    pub fn from_score_explained(score: Score, explain: bool) -> ScoreResponse {
+        let explanation = if explain { Some(score.explain()) } else { None };
         let mut scores = Vec::<ScoreInfo>::new();
         score.combinations.into_iter().for_each(|combi| {
             scores.push(ScoreInfo::from_combination(combi));
@@ -76,30 +88,27 @@ impl ScoreResponse {
         ScoreResponse {
             Score: score.total_score,
             ScoreInfo: scores,
+            Explanation: explanation,
         }
     }
 }
 
+/// the optional query string of the urls that score cards
+#[derive(Debug, Default, Deserialize)]
+pub struct ScoreQuery {
+    #[serde(default)]
+    pub explain: bool,
+}
+
 #[derive(Debug)]
 pub struct ParsedHand {
     pub hand: Vec<Card>,
 }
 
 impl ParsedHand {
+    /// accepts the long (FiveOfHearts) or short (5H) form of the cards, separated by commas or spaces
     pub fn from_string(csv: String) -> Result<ParsedHand, CribbageError> {
-        let mut hand: Vec<Card> = Vec::<Card>::new();
-        let tokens: Vec<&str> = csv.split(",").collect();
-        for token in tokens {
-            let _ = match Card::from_string(token) {
-                Ok(card) => {
-                    hand.push(card);
-                }
-                Err(e) => {
-                    return Err(e);
-                }
-            };
-        }
-
+        let hand = parse_hand(&csv)?;
         Ok(ParsedHand { hand })
     }
 }
@@ -149,6 +158,61 @@ impl RandomHandResponse {
         }
     }
 }
+/// a random deal for three player cribbage.  `RandomCards` has the shared card, the 15 cards dealt
+/// to the three players and the card dealt to the crib
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ThreePlayerHandResponse {
+    pub RandomCards: Vec<ClientCard>,
+    pub CribCard: ClientCard,
+    pub ComputerCribCards: Vec<ClientCard>,
+    pub SharedCard: ClientCard,
+    pub Dealer: String,
+    pub RepeatUrl: String,
+}
+/// the optional query string of analyzediscards
+#[derive(Debug, Default, Deserialize)]
+pub struct DiscardQuery {
+    /// the variant being played, such as FiveCard.  without it a 5 card hand is taken to be three or
+    /// four player cribbage
+    pub variant: Option<Variant>,
+}
+
+/// one way to discard from a hand, as evaluated by `evaluate_crib_cards`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiscardOptionResponse {
+    pub Keep: Vec<ClientCard>,
+    pub Discard: Vec<ClientCard>,
+    /// the average points of the kept cards over every starter
+    pub AverageHandScore: f32,
+    pub MinHandScore: u32,
+    pub MaxHandScore: u32,
+    /// the points the crib is expected to score, whoever owns it
+    pub ExpectedCribScore: f32,
+    /// the average hand score plus the crib if it is ours, or minus it if it is the opponent's
+    pub ExpectedValue: f32,
+    /// the starters that give the kept cards `MaxHandScore`
+    pub BestStarters: Vec<ClientCard>,
+}
+impl DiscardOptionResponse {
+    pub fn from_option(option: DiscardOption) -> DiscardOptionResponse {
+        let client_cards = |cards: Vec<Card>| {
+            cards
+                .into_iter()
+                .map(|card| ClientCard::from_card(card, "unknown".to_string()))
+                .collect()
+        };
+        DiscardOptionResponse {
+            Keep: client_cards(option.keep),
+            Discard: client_cards(option.discard),
+            AverageHandScore: option.average_hand_score,
+            MinHandScore: option.min_hand_score,
+            MaxHandScore: option.max_hand_score,
+            ExpectedCribScore: option.expected_crib_score,
+            ExpectedValue: option.expected_value,
+            BestStarters: client_cards(option.best_starters),
+        }
+    }
+}
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ClientCard {
     OrdinalName: Rank,
@@ -156,7 +220,7 @@ pub struct ClientCard {
     Value: i32,
     Suit: Suit,
     pub cardName: String,
-    Owner: String,
+    pub Owner: String,
     Ordinal: i32,
 }
 
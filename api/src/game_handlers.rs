@@ -1,16 +1,29 @@
 use crate::client_structs::{
-    ClientCard, CountedCardResponse, CutCardResponse, CutCards, ParsedHand, RandomHandResponse,
-    ScoreResponse,
+    ClientCard, CountedCardResponse, CutCardResponse, CutCards, DiscardOptionResponse,
+    DiscardQuery, ParsedHand, RandomHandResponse, ScoreQuery, ScoreResponse,
+    ThreePlayerHandResponse,
+};
+use actix_web::{
+    error::BlockingError,
+    web::{self, Path, Query},
+    HttpRequest, HttpResponse, Responder,
 };
-use actix_web::{web::Path, HttpRequest, HttpResponse, Responder};
 use cribbage_library::{
-    cards::Card,
+    cards::{Card, Deck, DECK_SIZE},
     counting::score_counting_cards_played,
     cribbage_errors::{CribbageError, CribbageErrorKind},
+    hand_statistics::{self, keep_statistics},
+    match_play::{FirstDealer, Match},
+    rules::RuleSet,
     scoring::{score_hand as scorehand, Score},
-    select_cards::{get_next_counted_card, select_crib_cards},
+    select_cards::{evaluate_crib_cards, evaluate_crib_cards_for},
+    strategy::{strategy_by_name, DefaultStrategy, Strategy, StrategyContext},
+    variant::Variant,
+    win_probability::WinProbabilities,
 };
-use rand::prelude::{Rng, SliceRandom};
+
+/// the owners of the cards in a three player hand, by seat
+const THREE_PLAYER_OWNERS: [&str; 3] = ["player", "computer1", "computer2"];
 
 ///
 /// given the HttpRequest returns the hostname in the form of localhost:8080/api
@@ -28,12 +41,13 @@ macro_rules! get_hostname {
 ///  returns: the two cut cards and the repeat URL.  the client is written to assume a shared notion of the deck
 ///           so we just return 2 numbers bewtween 0 and 51
 pub async fn cut_cards(req: HttpRequest) -> impl Responder {
-    let mut rng = rand::thread_rng();
-    let first = rng.gen_range(0..51) as usize;
-    let mut second = rng.gen_range(0..51) as usize;
+    let mut deck = Deck::new();
+    deck.shuffle(&mut rand::thread_rng());
+    let first = deck.draw().unwrap().index(); // a full deck has plenty of cards
+    let mut second = deck.draw().unwrap().index();
     while first % 13 == second % 13 {
         // % 13 gives Rank and we can't have the rank the same, as it'd be a tie and we'd just draw again
-        second = rng.gen_range(0..51) as usize;
+        second = deck.draw().unwrap().index();
     }
 
     let _cc: CutCards = match CutCards::new(first, second) {
@@ -88,14 +102,19 @@ pub async fn cut_cards_repeat(req: HttpRequest, cards: Path<String>) -> impl Res
 ///
 ///  sample URLs:
 ///              localhost:8088/api/scorehand/FiveOfHearts,FiveOfClubs,FiveOfSpades,JackOfDiamonds/FourOfDiamonds/false
+///              localhost:8088/api/scorehand/5H,5C,5S,JD/4D/false     (the short form of the cards works too)
 ///              localhost:8088/api/scorehand/FiveOfHearts,SixOfHearts,SevenOfHearts,EightOfHearts/NineOfDiamonds/false  (should be a flush)
 ///              localhost:8088/api/scorehand/FiveOfHearts,SixOfHearts,SevenOfHearts,EightOfHearts/NineOfDiamonds/true   (no flush - need 5 of same suit in crib)
 ///              localhost:8088/api/scorehand/FiveOfHearts,SixOfHearts,SevenOfHearts,EightOfHearts/NineOfHearts/true     (should be a flush)
 ///              localhost:8088/api/scorehand/FiveOfHearts,SixOfHearts,FourOfHearts,FourOFClubs/SixOfDiamonds/true     (bad card)
 ///              localhost:8088/api/scorehand/FiveOfHearts,SixOfHearts,FourOfHearts,FourOfClubs/SixOfDiamonds/true     (double double run with 15s - 24 points)
 ///              localhost:8088/api/scorehand/ThreeOfSpades,TwoOfSpades,QueenOfHearts,QueenOfClubs/AceOfHearts/false
+///              localhost:8088/api/scorehand/FiveOfHearts,SixOfClubs,FourOfSpades,JackOfDiamonds/SixOfDiamonds/false?explain=true  (adds the spoken count)
 ///
-pub async fn score_hand(path: Path<(String, String, bool)>) -> impl Responder {
+pub async fn score_hand(
+    path: Path<(String, String, bool)>,
+    query: Query<ScoreQuery>,
+) -> impl Responder {
     let path = path.into_inner();
     let parsed_hand = match ParsedHand::from_string(path.0) {
         Ok(parsed_hand) => parsed_hand,
@@ -113,7 +132,7 @@ pub async fn score_hand(path: Path<(String, String, bool)>) -> impl Responder {
 
     let score: Score = scorehand(parsed_hand.hand, Some(shared_card), path.2);
 
-    let score_response: ScoreResponse = ScoreResponse::from_score(score);
+    let score_response: ScoreResponse = ScoreResponse::from_score_explained(score, query.explain);
 
     HttpResponse::Ok().body(serde_json::to_string(&score_response).unwrap())
 }
@@ -126,6 +145,8 @@ pub async fn score_hand(path: Path<(String, String, bool)>) -> impl Responder {
 ///   localhost:8088/api/getcribcards/FourOfHearts,FiveOfHearts,SixOfSpades,JackOfHearts,QueenOfHearts,SixOfDiamonds/true
 ///   localhost:8088/api/getcribcards/FourOfHearts,FiveOfHearts,SixOfSpades,JackOfHearts,QueenOfHearts,SixOfDiamonds/false
 ///
+///  given 5 cards, returns the 1 card to discard in three or four player cribbage.  the five card game
+///  also deals 5 cards but discards 2, which this route doesn't support.
 ///
 pub async fn get_crib(path: Path<(String, bool)>) -> impl Responder {
     let path = path.into_inner();
@@ -137,7 +158,8 @@ pub async fn get_crib(path: Path<(String, bool)>) -> impl Responder {
         }
     };
 
-    let crib = match select_crib_cards(parsed_hand.hand.as_slice(), path.1) {
+    let context = StrategyContext::from_hand(parsed_hand.hand.as_slice(), path.1);
+    let crib = match DefaultStrategy.choose_discard(&context) {
         Ok(crib) => crib,
         Err(e) => {
             return HttpResponse::BadRequest().body(serde_json::to_string(&e).unwrap());
@@ -152,6 +174,38 @@ pub async fn get_crib(path: Path<(String, bool)>) -> impl Responder {
     HttpResponse::Ok().body(serde_json::to_string(&result).unwrap())
 }
 
+///  given 6 cards, return all 15 ways to discard 2 of them, ranked from the best expected value to the
+///  worst.  unlike getcribcards, each option says why: the average, lowest and highest points of the
+///  kept cards over the 46 starters, the expected points of the crib, the net value and the starters
+///  that give the kept cards the most points.  a 5 card hand returns the 5 ways to discard 1, as in
+///  three or four player cribbage.  the five card game discards 2 of 5, so add ?variant=FiveCard for it.
+///
+///  sample URLs:
+///   localhost:8088/api/analyzediscards/FiveOfHearts,FiveOfClubs,FiveOfSpades,JackOfDiamonds,SixOfClubs,FourOfDiamonds/false
+///   localhost:8088/api/analyzediscards/4H,5H,6S,JH,QH,6D/true
+///   localhost:8088/api/analyzediscards/4H,5H,6S,JH,QH/true?variant=FiveCard
+///
+pub async fn analyze_discards(
+    path: Path<(String, bool)>,
+    query: Query<DiscardQuery>,
+) -> impl Responder {
+    let (hand, my_crib) = path.into_inner();
+    let options = ParsedHand::from_string(hand).and_then(|parsed_hand| match query.variant {
+        Some(variant) => evaluate_crib_cards_for(&parsed_hand.hand, variant, my_crib),
+        None => evaluate_crib_cards(&parsed_hand.hand, my_crib),
+    });
+    match options {
+        Ok(options) => {
+            let result: Vec<DiscardOptionResponse> = options
+                .into_iter()
+                .map(DiscardOptionResponse::from_option)
+                .collect();
+            HttpResponse::Ok().body(serde_json::to_string(&result).unwrap())
+        }
+        Err(e) => HttpResponse::BadRequest().body(serde_json::to_string(&e).unwrap()),
+    }
+}
+
 ///  URL example:
 ///          localhost:8088/api/getnextcountedcard/AceOfSpades,AceOfHearts,TwoOfClubs,TenOfDiamonds/0
 ///          localhost:8088/api/getnextcountedcard/FiveOfClubs,QueenOfDiamonds/25/ThreeOfDiamonds,TenOfClubs,TwoOfSpades,QueenOfSpades
@@ -169,7 +223,8 @@ pub async fn get_first_counted_card(path: Path<(String, u32)>) -> impl Responder
         }
     };
 
-    let response = internal_get_next_counted_card(Vec::<Card>::new(), available_cards);
+    let response =
+        internal_get_next_counted_card(Vec::<Card>::new(), available_cards, &DefaultStrategy);
     return HttpResponse::Ok().body(serde_json::to_string(&response).unwrap());
 }
 
@@ -192,17 +247,20 @@ pub async fn next_counted_card(path: Path<(String, u32, String)>) -> impl Respon
         }
     };
 
-    let response = internal_get_next_counted_card(played_cards, available_cards);
+    let response = internal_get_next_counted_card(played_cards, available_cards, &DefaultStrategy);
     return HttpResponse::Ok().body(serde_json::to_string(&response).unwrap());
 }
 
-/// helper function that gets the counted card and then formats the proper response
+/// helper function that asks `strategy` for the counted card and then formats the proper response
 ///
 fn internal_get_next_counted_card(
     played_cards: Vec<Card>,
     available_cards: Vec<Card>,
+    strategy: &dyn Strategy,
 ) -> CountedCardResponse {
-    let _ = match get_next_counted_card(played_cards.clone(), available_cards) {
+    let mut context = StrategyContext::from_hand(available_cards.as_slice(), false);
+    context.pile = played_cards.as_slice();
+    let _ = match strategy.choose_play(&context) {
         Ok(card) => {
             let _ = match card {
                 Some(card) => {
@@ -245,8 +303,12 @@ pub async fn score_first_counted_card(path: Path<(String, u32)>) -> impl Respond
 }
 
 /// routed to when the player plays a card and there are already some cards played.
+/// add ?explain=true to get the spoken count too
 ///
-pub async fn score_counted_cards(path: Path<(String, u32, String)>) -> impl Responder {
+pub async fn score_counted_cards(
+    path: Path<(String, u32, String)>,
+    query: Query<ScoreQuery>,
+) -> impl Responder {
     let path = path.into_inner();
     let played_cards = match ParsedHand::from_string(path.2) {
         Ok(parsed_hand) => parsed_hand.hand,
@@ -264,7 +326,8 @@ pub async fn score_counted_cards(path: Path<(String, u32, String)>) -> impl Resp
 
     let _ = match score_counting_cards_played(played_cards.as_slice(), card) {
         Ok(score) => {
-            let score_response: ScoreResponse = ScoreResponse::from_score(score);
+            let score_response: ScoreResponse =
+                ScoreResponse::from_score_explained(score, query.explain);
 
             return HttpResponse::Ok().body(serde_json::to_string(&score_response).unwrap());
         }
@@ -275,42 +338,39 @@ pub async fn score_counted_cards(path: Path<(String, u32, String)>) -> impl Resp
     };
 }
 
-/// helper function for getting a random hand
+/// helper function for getting a random hand.  deals 6 cards each from the top of `deck`, starting
+/// with the player who doesn't have the crib, then turns up the next card as the shared card
 ///
 fn get_random_hand_internal(
     req: HttpRequest,
     is_computer_crib: bool,
-    cards: Vec<usize>,
-) -> Option<RandomHandResponse> {
-    if cards.len() < 13 {
-        return None;
-    }
+    mut deck: Deck,
+) -> Result<RandomHandResponse, CribbageError> {
     let owner_array: [String; 2] = ["player".to_string(), "computer".to_string()];
     let mut toggle_owner: usize = 1; // which owner gets the first card?
     if is_computer_crib {
         toggle_owner = 0;
     }
+    let hands = deck.deal(2, 6)?;
+    let computer_hand = &hands[1 - toggle_owner];
+    let shared_card = deck.draw()?;
 
     let mut indices: String = "".to_owned();
     let mut response = RandomHandResponse::default();
-    let mut computer_hand: Vec<Card> = Vec::<Card>::default();
-    for i in 0..12 {
-        let index = cards[i];
-        if owner_array[toggle_owner] == "computer" {
-            computer_hand.push(Card::from_index(index));
-        }
+    for card in deck.dealt()[..12].iter() {
         response.RandomCards.push(ClientCard::from_card(
-            Card::from_index(index),
+            *card,
             owner_array[toggle_owner].to_string(),
         ));
-        indices.push_str(&format!("{},", index));
+        indices.push_str(&format!("{},", card.index()));
         toggle_owner = 1 - toggle_owner;
     }
     indices.pop(); // remove the trailing ","
-    response.SharedCard = ClientCard::from_card(Card::from_index(cards[12]), "shared".to_string());
+    response.SharedCard = ClientCard::from_card(shared_card, "shared".to_string());
     response.RandomCards.insert(0, response.SharedCard.clone()); // to avoid "partially borrowing" the response object
 
-    let crib_cards = select_crib_cards(computer_hand.as_slice(), true).unwrap();
+    let context = StrategyContext::from_hand(computer_hand.as_slice(), true);
+    let crib_cards = DefaultStrategy.choose_discard(&context)?;
     for card in crib_cards.iter() {
         response
             .ComputerCribCards
@@ -322,9 +382,9 @@ fn get_random_hand_internal(
         get_hostname!(req),
         is_computer_crib,
         indices,
-        cards[12]
+        shared_card.index()
     );
-    Some(response)
+    Ok(response)
 }
 
 /// routed to when a new hand is needed.
@@ -336,20 +396,13 @@ fn get_random_hand_internal(
 pub async fn get_random_hand(req: HttpRequest, path: Path<bool>) -> impl Responder {
     let is_computer_crib = path.into_inner();
 
-    let mut rng = rand::thread_rng();
-    let mut deck = (0..51).collect::<Vec<_>>();
-    deck.shuffle(&mut rng);
+    let mut deck = Deck::new();
+    deck.shuffle(&mut rand::thread_rng());
 
     let response = match get_random_hand_internal(req, is_computer_crib, deck) {
-        Some(response) => response,
-        None => {
-            return HttpResponse::BadRequest().body(
-                serde_json::to_string(&CribbageError::new(
-                    CribbageErrorKind::BadCount,
-                    "Error getting random hand".to_string(),
-                ))
-                .unwrap(),
-            );
+        Ok(response) => response,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(serde_json::to_string(&e).unwrap());
         }
     };
 
@@ -366,60 +419,267 @@ pub async fn get_random_hand_repeat(
     let path = path.into_inner();
     let is_computer_crib = path.0;
 
-    let shared_card_index = match path.2.parse::<usize>() {
+    let response = match stacked_deck(&path.1, &path.2, 12)
+        .and_then(|deck| get_random_hand_internal(req, is_computer_crib, deck))
+    {
+        Ok(response) => response,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(serde_json::to_string(&e).unwrap());
+        }
+    };
+
+    HttpResponse::Ok().body(serde_json::to_string(&response).unwrap())
+}
+
+/// helper function for getting a random three player hand.  deals 5 cards each from the top of
+/// `deck`, starting with the player after the `dealer`, then one card to the crib, and then turns
+/// up the next card as the shared card.  the computers each pick the card they discard.
+///
+fn get_random_three_player_hand_internal(
+    req: HttpRequest,
+    dealer: usize,
+    mut deck: Deck,
+) -> Result<ThreePlayerHandResponse, CribbageError> {
+    let variant = Variant::ThreePlayer;
+    if dealer >= variant.players() {
+        return Err(CribbageError::new(
+            CribbageErrorKind::BadAction,
+            format!("the dealer must be 0, 1 or 2.  {} is invalid", dealer),
+        ));
+    }
+    let dealt = deck.deal(variant.players(), variant.cards_dealt())?;
+    let crib_card = deck.draw()?;
+    let shared_card = deck.draw()?;
+
+    // the first hand dealt goes to the player after the dealer
+    let seat_of = |hand: usize| (dealer + 1 + hand) % variant.players();
+    let mut indices: String = "".to_owned();
+    let mut random_cards = Vec::new();
+    for (i, card) in deck.dealt()[..deck.dealt().len() - 1].iter().enumerate() {
+        let owner = match i < dealt.len() * variant.cards_dealt() {
+            true => THREE_PLAYER_OWNERS[seat_of(i % variant.players())],
+            false => "crib",
+        };
+        random_cards.push(ClientCard::from_card(*card, owner.to_string()));
+        indices.push_str(&format!("{},", card.index()));
+    }
+    indices.pop(); // remove the trailing ","
+    let shared = ClientCard::from_card(shared_card, "shared".to_string());
+    random_cards.insert(0, shared.clone());
+
+    let mut computer_crib_cards = Vec::new();
+    for (i, hand) in dealt.iter().enumerate() {
+        let seat = seat_of(i);
+        if seat == 0 {
+            continue; // the player picks their own discard
+        }
+        let context = StrategyContext::from_hand(hand.as_slice(), seat == dealer);
+        for card in DefaultStrategy.choose_discard(&context)? {
+            computer_crib_cards.push(ClientCard::from_card(
+                card,
+                THREE_PLAYER_OWNERS[seat].to_string(),
+            ));
+        }
+    }
+
+    Ok(ThreePlayerHandResponse {
+        RandomCards: random_cards,
+        CribCard: ClientCard::from_card(crib_card, "crib".to_string()),
+        ComputerCribCards: computer_crib_cards,
+        SharedCard: shared,
+        Dealer: THREE_PLAYER_OWNERS[dealer].to_string(),
+        RepeatUrl: format!(
+            "{}/getrandomhand/threeplayer/{}/{}/{}",
+            get_hostname!(req),
+            dealer,
+            indices,
+            shared_card.index()
+        ),
+    })
+}
+
+/// routed to when a new three player hand is needed.  `dealer` is the seat of the dealer: 0 for
+/// the player and 1 or 2 for the computers.  returns the 17 cards (5 for each player, 1 for the
+/// crib and 1 shared) and the card each computer puts in the crib
+///
+/// sample url: http://localhost:8080/api/getrandomhand/threeplayer/1
+///
+pub async fn get_random_three_player_hand(req: HttpRequest, path: Path<usize>) -> impl Responder {
+    let dealer = path.into_inner();
+
+    let mut deck = Deck::new();
+    deck.shuffle(&mut rand::thread_rng());
+
+    let response = match get_random_three_player_hand_internal(req, dealer, deck) {
+        Ok(response) => response,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(serde_json::to_string(&e).unwrap());
+        }
+    };
+
+    HttpResponse::Ok().body(serde_json::to_string(&response).unwrap())
+}
+
+/// gives the same hand that was returned from get_random_three_player_hand
+///
+/// sample url: localhost:8080/api/getrandomhand/threeplayer/1/21,49,41,15,46,17,34,24,22,38,19,31,0,1,2,3/20
+pub async fn get_random_three_player_hand_repeat(
+    req: HttpRequest,
+    path: Path<(usize, String, String)>,
+) -> impl Responder {
+    let (dealer, indices, shared_index) = path.into_inner();
+
+    let response = match stacked_deck(&indices, &shared_index, 16)
+        .and_then(|deck| get_random_three_player_hand_internal(req, dealer, deck))
+    {
+        Ok(response) => response,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(serde_json::to_string(&e).unwrap());
+        }
+    };
+
+    HttpResponse::Ok().body(serde_json::to_string(&response).unwrap())
+}
+
+/// plays a tournament match between two computer strategies, with the loser of each game dealing
+/// first in the next.  `first` plays in seat 0 and `second` in seat 1.
+///
+/// sample url: http://localhost:8080/api/playmatch/default/expected_value/7/42
+///
+/// returns: the `Match`, with how each game ended and the games won and game points of each seat.
+///          a match can be best of at most 99 games.
+pub async fn play_match(path: Path<(String, String, u32, u64)>) -> impl Responder {
+    let (first, second, best_of, seed) = path.into_inner();
+
+    // the slower strategies can take a while over a long match, so it is played off the worker thread
+    let response =
+        match web::block(move || play_match_internal(&first, &second, best_of, seed)).await {
+            Ok(response) => response,
+            Err(BlockingError::Error(e)) => {
+                return HttpResponse::BadRequest().body(serde_json::to_string(&e).unwrap());
+            }
+            Err(BlockingError::Canceled) => {
+                return HttpResponse::InternalServerError().finish();
+            }
+        };
+
+    HttpResponse::Ok().body(serde_json::to_string(&response).unwrap())
+}
+
+fn play_match_internal(
+    first: &str,
+    second: &str,
+    best_of: u32,
+    seed: u64,
+) -> Result<Match, CribbageError> {
+    let strategy = |name: &str| {
+        strategy_by_name(name).ok_or_else(|| {
+            CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!("there is no strategy called {}", name),
+            )
+        })
+    };
+    let (first, second) = (strategy(first)?, strategy(second)?);
+    let mut series = Match::new(
+        best_of,
+        Variant::TwoPlayer,
+        RuleSet::default(),
+        FirstDealer::LoserDeals,
+        seed,
+    )?;
+    series.play_to_end(&[first.as_ref(), second.as_ref()])?;
+    Ok(series)
+}
+
+/// the score histogram, mean, variance and percentiles of a kept hand of 4 cards over each of the 48
+/// starters that could be cut
+///
+/// sample urls:
+///             http://localhost:8080/api/handstatistics/5H,5C,5S,JD/false
+///             http://localhost:8080/api/handstatistics/FourOfHearts,FiveOfHearts,SixOfSpades,SixOfDiamonds/true
+///
+/// returns: a `ScoreStatistics`, where `histogram` counts the starters that give each number of points
+pub async fn hand_statistics(path: Path<(String, bool)>) -> impl Responder {
+    let (hand, is_crib) = path.into_inner();
+    let statistics = ParsedHand::from_string(hand)
+        .and_then(|parsed_hand| keep_statistics(&parsed_hand.hand, is_crib));
+    match statistics {
+        Ok(statistics) => HttpResponse::Ok().body(serde_json::to_string(&statistics).unwrap()),
+        Err(e) => HttpResponse::BadRequest().body(serde_json::to_string(&e).unwrap()),
+    }
+}
+
+/// the score histogram, mean, variance and percentiles of all 12,994,800 hands of 4 cards with every
+/// starter.  they are counted ahead of time and shipped with the library, so this is quick.
+///
+/// sample url: http://localhost:8080/api/handstatistics/false
+pub async fn all_hands_statistics(path: Path<bool>) -> impl Responder {
+    let statistics = hand_statistics::all_hands_statistics(path.into_inner());
+    HttpResponse::Ok().body(serde_json::to_string(statistics).unwrap())
+}
+
+/// the chance of winning a two player game to 121 from the start of a deal, when the score is `me` to
+/// `opp` and `dealer` says whether it is my deal
+///
+/// sample url: http://localhost:8080/api/winprobability/98/104/true
+///
+/// returns: the probability of winning, the points each player still needs and the points each player is
+///          expected to score this deal
+pub async fn win_probability(path: Path<(u32, u32, bool)>) -> impl Responder {
+    let (me, opp, dealer) = path.into_inner();
+    match WinProbabilities::standard().odds(me, opp, dealer) {
+        Ok(odds) => HttpResponse::Ok().body(serde_json::to_string(&odds).unwrap()),
+        Err(e) => HttpResponse::BadRequest().body(serde_json::to_string(&e).unwrap()),
+    }
+}
+
+/// parses the CSV of `expected` card indices and the shared card index from a repeat url, and
+/// puts those cards on top of a deck so they are dealt the same way again
+fn stacked_deck(indices: &str, shared_index: &str, expected: usize) -> Result<Deck, CribbageError> {
+    let shared_card_index = match shared_index.parse::<usize>() {
         Ok(shared_card_index) => shared_card_index,
         Err(e) => {
             let msg = format!(
                 "unable to parse {} into usize.  Bad shared card index.\nparse error: {:?}",
-                path.2, e
-            );
-            return HttpResponse::BadRequest().body(
-                serde_json::to_string(&CribbageError::new(CribbageErrorKind::BadCount, msg))
-                    .unwrap(),
+                shared_index, e
             );
+            return Err(CribbageError::new(CribbageErrorKind::BadCount, msg));
         }
     };
 
-    let tokens = path.1.split(",").collect::<Vec<_>>();
-    if tokens.len() != 12 {
+    let tokens = indices.split(',').collect::<Vec<_>>();
+    if tokens.len() != expected {
         let msg = format!(
-            "Expected 12 tokens and got {} instead the CSV of indices is incorrect",
+            "Expected {} tokens and got {} instead the CSV of indices is incorrect",
+            expected,
             tokens.len()
         );
-        return HttpResponse::BadRequest().body(
-            serde_json::to_string(&CribbageError::new(CribbageErrorKind::BadCount, msg)).unwrap(),
-        );
+        return Err(CribbageError::new(CribbageErrorKind::BadCount, msg));
     };
 
     let mut indices = Vec::<usize>::new();
 
     for token in tokens {
-        let _ = match token.parse::<usize>() {
+        match token.parse::<usize>() {
             Ok(index) => indices.push(index),
             Err(_) => {
                 let msg = format!("unable to parse {} into usize.  Bad CSV.", token);
-                return HttpResponse::BadRequest().body(
-                    serde_json::to_string(&CribbageError::new(CribbageErrorKind::BadCount, msg))
-                        .unwrap(),
-                );
+                return Err(CribbageError::new(CribbageErrorKind::BadCount, msg));
             }
         };
     }
     indices.push(shared_card_index);
-    let response = match get_random_hand_internal(req, is_computer_crib, indices) {
-        Some(response) => response,
-        None => {
-            return HttpResponse::BadRequest().body(
-                serde_json::to_string(&CribbageError::new(
-                    CribbageErrorKind::BadCount,
-                    "Error getting random hand".to_string(),
-                ))
-                .unwrap(),
-            );
-        }
-    };
+    if let Some(index) = indices.iter().find(|i| **i >= DECK_SIZE) {
+        let msg = format!("card index can't be > 51.  {} is invalid", index);
+        return Err(CribbageError::new(CribbageErrorKind::BadCard, msg));
+    }
 
-    HttpResponse::Ok().body(serde_json::to_string(&response).unwrap())
+    let top = indices
+        .into_iter()
+        .map(Card::from_index)
+        .collect::<Vec<_>>();
+    Deck::stacked(&top)
 }
 
 ///  Tests for the Web API.  The actual logic of the game is already tested in the unit tests for that part of the project
@@ -434,7 +694,9 @@ mod tests {
     use super::*;
     use crate::{game_handlers, safe_set_port, PORT};
     use actix_web::{test, web, App};
+    use cribbage_library::hand_statistics::ScoreStatistics;
     use cribbage_library::scoring::CombinationName;
+    use cribbage_library::win_probability::PositionOdds;
     use std::env;
 
     macro_rules! get_repeat_url {
@@ -557,6 +819,55 @@ mod tests {
         assert_eq!(rhr.ComputerCribCards.len(), 2);
     }
 
+    #[actix_rt::test]
+    async fn test_random_three_player_hand() {
+        safe_set_port!();
+        let mut app = test::init_service(
+            App::new()
+                .route(
+                    "api/getrandomhand/threeplayer/{dealer}",
+                    web::get().to(game_handlers::get_random_three_player_hand),
+                )
+                .route(
+                    "api/getrandomhand/threeplayer/{dealer}/{indices}/{shared_index}",
+                    web::get().to(game_handlers::get_random_three_player_hand_repeat),
+                ),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/api/getrandomhand/threeplayer/2")
+            .to_request();
+        let response: ThreePlayerHandResponse = test::read_response_json(&mut app, req).await;
+
+        test_repeatability!(app, response, response.RepeatUrl);
+
+        assert_eq!(response.RandomCards.len(), 17);
+        assert_eq!(response.ComputerCribCards.len(), 2);
+        assert_eq!(response.Dealer, "computer2");
+        for owner in THREE_PLAYER_OWNERS.iter() {
+            assert_eq!(
+                response
+                    .RandomCards
+                    .iter()
+                    .filter(|c| c.Owner == *owner)
+                    .count(),
+                5
+            );
+        }
+        // the player after the dealer gets the first card
+        assert_eq!(response.RandomCards[1].Owner, "player");
+        assert_eq!(
+            response.RandomCards[16].cardName,
+            response.CribCard.cardName
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/api/getrandomhand/threeplayer/3")
+            .to_request();
+        let response = test::call_service(&mut app, req).await;
+        assert_eq!(response.status(), 400);
+    }
+
     #[actix_rt::test]
     async fn test_score_counted_cards() {
         safe_set_port!();
@@ -620,6 +931,175 @@ mod tests {
             vec_contains_score!(score_response.ScoreInfo, CombinationName::RoyalPair),
             true
         );
+        assert_eq!(score_response.Explanation, None);
+
+        let req = test::TestRequest::get()
+            .uri("/api/scorehand/5H,5C,5S,JD/4D/false")
+            .to_request();
+        let short: ScoreResponse = test::read_response_json(&mut app, req).await;
+        assert_eq!(short.Score, 15);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("{}?explain=true", uri))
+            .to_request();
+        let score_response: ScoreResponse = test::read_response_json(&mut app, req).await;
+        let explanation = score_response.Explanation.unwrap();
+        assert!(explanation.starts_with("fifteen 2"));
+        assert!(explanation.contains("three of a kind is"));
+        assert!(explanation.contains("nobs is 15"));
+    }
+
+    #[actix_rt::test]
+    async fn test_play_match() {
+        safe_set_port!();
+        let mut app = test::init_service(App::new().route(
+            "api/playmatch/{first}/{second}/{best_of}/{seed}",
+            web::get().to(game_handlers::play_match),
+        ))
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/api/playmatch/default/expected_value/3/42")
+            .to_request();
+        let response: Match = test::read_response_json(&mut app, req).await;
+        let winner = response.winner().unwrap();
+        assert!(response.wins()[winner] >= 2);
+        assert!(!response.games().is_empty());
+        assert_eq!(response.best_of, 3);
+
+        let req = test::TestRequest::get()
+            .uri("/api/playmatch/default/nobody/3/42")
+            .to_request();
+        let response = test::call_service(&mut app, req).await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::get()
+            .uri("/api/playmatch/exact/exact/4294967295/1")
+            .to_request();
+        let response = test::call_service(&mut app, req).await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_analyze_discards() {
+        safe_set_port!();
+        let mut app = test::init_service(App::new().route(
+            "api/analyzediscards/{hand}/{my_crib}",
+            web::get().to(game_handlers::analyze_discards),
+        ))
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/api/analyzediscards/FiveOfHearts,FiveOfClubs,FiveOfSpades,JackOfDiamonds,SixOfClubs,FourOfDiamonds/false")
+            .to_request();
+        let response: Vec<DiscardOptionResponse> = test::read_response_json(&mut app, req).await;
+        assert_eq!(response.len(), 15);
+        for pair in response.windows(2) {
+            assert!(pair[0].ExpectedValue >= pair[1].ExpectedValue);
+        }
+        let best = &response[0];
+        let discarded: Vec<&str> = best.Discard.iter().map(|c| c.cardName.as_str()).collect();
+        assert_eq!(discarded, vec!["SixOfClubs", "FourOfDiamonds"]);
+        assert_eq!(best.Keep.len(), 4);
+        assert_eq!((best.MinHandScore, best.MaxHandScore), (14, 29));
+        assert_eq!(best.BestStarters.len(), 1);
+        assert_eq!(best.BestStarters[0].cardName, "FiveOfDiamonds");
+
+        let req = test::TestRequest::get()
+            .uri("/api/analyzediscards/5H,5C,5S,JD,6C/true")
+            .to_request();
+        let response: Vec<DiscardOptionResponse> = test::read_response_json(&mut app, req).await;
+        assert_eq!(response.len(), 5);
+
+        let req = test::TestRequest::get()
+            .uri("/api/analyzediscards/5H,5C,5S,JD,6C/true?variant=FiveCard")
+            .to_request();
+        let response: Vec<DiscardOptionResponse> = test::read_response_json(&mut app, req).await;
+        assert_eq!(response.len(), 10);
+        assert!(response.iter().all(|option| option.Discard.len() == 2));
+
+        for uri in &[
+            "/api/analyzediscards/5H,5C,5S,JD/true",
+            "/api/analyzediscards/5H,5C,5S,JD,6C,UnknownOfUnknown/true",
+            "/api/analyzediscards/5H,5H,5C,JD,6C,4D/false",
+        ] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let response = test::call_service(&mut app, req).await;
+            assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_hand_statistics() {
+        safe_set_port!();
+        let mut app = test::init_service(
+            App::new()
+                .route(
+                    "api/handstatistics/{hand}/{is_crib}",
+                    web::get().to(game_handlers::hand_statistics),
+                )
+                .route(
+                    "api/handstatistics/{is_crib}",
+                    web::get().to(game_handlers::all_hands_statistics),
+                ),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/api/handstatistics/5H,5C,5S,JD/false")
+            .to_request();
+        let response: ScoreStatistics = test::read_response_json(&mut app, req).await;
+        assert_eq!(response.hands, 48);
+        assert_eq!(response.histogram.len(), 30);
+        assert_eq!(response.histogram[29], 1);
+        assert_eq!(response.max, 29);
+        assert!(response.mean > 14.0 && response.variance > 0.0);
+        assert_eq!(response.percentiles.len(), 8);
+
+        let req = test::TestRequest::get()
+            .uri("/api/handstatistics/true")
+            .to_request();
+        let response: ScoreStatistics = test::read_response_json(&mut app, req).await;
+        assert_eq!(response.hands, 12_994_800);
+        assert_eq!(response.histogram[29], 4);
+
+        for uri in &[
+            "/api/handstatistics/5H,5C,5S/false",
+            "/api/handstatistics/5H,5C,5S,5H/false",
+            "/api/handstatistics/5H,5C,5S,XX/false",
+            "/api/handstatistics/5H,5C,5S,UnknownOfUnknown/false",
+        ] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let response = test::call_service(&mut app, req).await;
+            assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_win_probability() {
+        safe_set_port!();
+        let mut app = test::init_service(App::new().route(
+            "api/winprobability/{me}/{opp}/{dealer}",
+            web::get().to(game_handlers::win_probability),
+        ))
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/api/winprobability/98/104/true")
+            .to_request();
+        let response: PositionOdds = test::read_response_json(&mut app, req).await;
+        assert!(response.win_probability > 0.0 && response.win_probability < 1.0);
+        assert_eq!(response.points_needed, 23);
+        assert_eq!(response.opponent_points_needed, 17);
+        assert!(response.my_deal);
+
+        let req = test::TestRequest::get()
+            .uri("/api/winprobability/98/104/false")
+            .to_request();
+        let pone: PositionOdds = test::read_response_json(&mut app, req).await;
+        assert!(pone.win_probability < response.win_probability);
+
+        let req = test::TestRequest::get()
+            .uri("/api/winprobability/130/104/true")
+            .to_request();
+        let response = test::call_service(&mut app, req).await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
     }
 
     #[actix_rt::test]
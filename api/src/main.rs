@@ -66,6 +66,10 @@ async fn main() -> std::io::Result<()> {
                         web::resource("getcribcards/{hand}/{my_crib}")
                             .route(web::get().to(game_handlers::get_crib)),
                     )
+                    .service(
+                        web::resource("analyzediscards/{hand}/{my_crib}")
+                            .route(web::get().to(game_handlers::analyze_discards)),
+                    )
                     .service(
                         // note trailing '/' as that is what the client uses
                         web::resource("getnextcountedcard/{available_cards}/{total_count}/")
@@ -96,6 +100,32 @@ async fn main() -> std::io::Result<()> {
                     .service(
                         web::resource("getrandomhand/{is_computer_crib}/{indices}/{shared_index}")
                             .route(web::get().to(game_handlers::get_random_hand_repeat)),
+                    )
+                    .service(
+                        web::resource("getrandomhand/threeplayer/{dealer}")
+                            .route(web::get().to(game_handlers::get_random_three_player_hand)),
+                    )
+                    .service(
+                        web::resource(
+                            "getrandomhand/threeplayer/{dealer}/{indices}/{shared_index}",
+                        )
+                        .route(web::get().to(game_handlers::get_random_three_player_hand_repeat)),
+                    )
+                    .service(
+                        web::resource("playmatch/{first}/{second}/{best_of}/{seed}")
+                            .route(web::get().to(game_handlers::play_match)),
+                    )
+                    .service(
+                        web::resource("handstatistics/{hand}/{is_crib}")
+                            .route(web::get().to(game_handlers::hand_statistics)),
+                    )
+                    .service(
+                        web::resource("handstatistics/{is_crib}")
+                            .route(web::get().to(game_handlers::all_hands_statistics)),
+                    )
+                    .service(
+                        web::resource("winprobability/{me}/{opp}/{dealer}")
+                            .route(web::get().to(game_handlers::win_probability)),
                     ),
             )
             .service(
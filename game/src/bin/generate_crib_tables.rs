@@ -0,0 +1,57 @@
+//! generates the crib tables that `select_crib_cards` uses and writes them
+//! to a file
+//!
+//! usage: generate_crib_tables [samples] [seed] [opponent strategy] [file]
+//!
+//! the file defaults to tables/crib_tables.txt, so run it from the game
+//! directory to replace the shipped tables.  the opponent should be a
+//! strategy that doesn't discard with the crib tables, such as table_free,
+//! or the tables will depend on the ones they replace.
+
+use cribbage_library::{
+    crib_tables::CribTables,
+    strategy::{strategy_by_name, STRATEGY_NAMES},
+};
+use std::{env, fs, process};
+
+const DEFAULT_SAMPLES: usize = 4_000;
+const DEFAULT_SEED: u64 = 42;
+const DEFAULT_OPPONENT: &str = "table_free";
+const DEFAULT_FILE: &str = "tables/crib_tables.txt";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let samples = match args.first() {
+        Some(arg) => arg
+            .parse()
+            .unwrap_or_else(|_| usage(&format!("bad sample count: {}", arg))),
+        None => DEFAULT_SAMPLES,
+    };
+    let seed = match args.get(1) {
+        Some(arg) => arg
+            .parse()
+            .unwrap_or_else(|_| usage(&format!("bad seed: {}", arg))),
+        None => DEFAULT_SEED,
+    };
+    let name = args.get(2).map(String::as_str).unwrap_or(DEFAULT_OPPONENT);
+    let opponent =
+        strategy_by_name(name).unwrap_or_else(|| usage(&format!("unknown strategy: {}", name)));
+    let file = args.get(3).map(String::as_str).unwrap_or(DEFAULT_FILE);
+
+    let tables = CribTables::generate(opponent.as_ref(), samples, seed).unwrap_or_else(|e| {
+        eprintln!("{}", e.message);
+        process::exit(1);
+    });
+    if let Err(e) = fs::write(file, tables.to_string()) {
+        eprintln!("couldn't write {}: {}", file, e);
+        process::exit(1);
+    }
+    println!("wrote {} with {} samples against {}", file, samples, name);
+}
+
+fn usage(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!("usage: generate_crib_tables [samples] [seed] [opponent strategy] [file]");
+    eprintln!("strategies: {}", STRATEGY_NAMES.join(", "));
+    process::exit(2);
+}
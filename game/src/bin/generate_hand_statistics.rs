@@ -0,0 +1,28 @@
+//! scores every hand of four cards with every starter, as a hand and as a
+//! crib, and writes how many score each number of points to a file
+//!
+//! usage: generate_hand_statistics [file]
+//!
+//! the file defaults to tables/hand_statistics.txt, so run it from the game
+//! directory to replace the shipped statistics
+
+use cribbage_library::hand_statistics::AllHandsStatistics;
+use std::{env, fs, process};
+
+const DEFAULT_FILE: &str = "tables/hand_statistics.txt";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() > 1 {
+        eprintln!("usage: generate_hand_statistics [file]");
+        process::exit(2);
+    }
+    let file = args.first().map(String::as_str).unwrap_or(DEFAULT_FILE);
+
+    let statistics = AllHandsStatistics::count();
+    if let Err(e) = fs::write(file, statistics.to_string()) {
+        eprintln!("couldn't write {}: {}", file, e);
+        process::exit(1);
+    }
+    println!("wrote {} from {} hands", file, statistics.hand.hands);
+}
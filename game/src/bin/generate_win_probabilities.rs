@@ -0,0 +1,47 @@
+//! simulates deals of the default strategy against itself, works out the
+//! chance of winning from every position and writes it to a file
+//!
+//! usage: generate_win_probabilities [deals] [seed] [file]
+//!
+//! the file defaults to tables/win_probabilities.txt, so run it from the
+//! game directory to replace the shipped probabilities
+
+use cribbage_library::win_probability::WinProbabilities;
+use std::{env, fs, process};
+
+const DEFAULT_DEALS: usize = 1_000_000;
+const DEFAULT_SEED: u64 = 42;
+const DEFAULT_FILE: &str = "tables/win_probabilities.txt";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let deals = match args.first() {
+        Some(arg) => arg
+            .parse()
+            .unwrap_or_else(|_| usage(&format!("bad deal count: {}", arg))),
+        None => DEFAULT_DEALS,
+    };
+    let seed = match args.get(1) {
+        Some(arg) => arg
+            .parse()
+            .unwrap_or_else(|_| usage(&format!("bad seed: {}", arg))),
+        None => DEFAULT_SEED,
+    };
+    let file = args.get(2).map(String::as_str).unwrap_or(DEFAULT_FILE);
+
+    let probabilities = WinProbabilities::generate(deals, seed).unwrap_or_else(|e| {
+        eprintln!("{}", e.message);
+        process::exit(1);
+    });
+    if let Err(e) = fs::write(file, probabilities.to_string()) {
+        eprintln!("couldn't write {}: {}", file, e);
+        process::exit(1);
+    }
+    println!("wrote {} from {} deals", file, deals);
+}
+
+fn usage(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!("usage: generate_win_probabilities [deals] [seed] [file]");
+    process::exit(2);
+}
@@ -0,0 +1,60 @@
+//! plays two strategies against each other and prints how they did
+//!
+//! usage: simulate [games] [seed] [first strategy] [second strategy] [best of]
+//!
+//! with a best of, the strategies play that many matches instead of games
+
+use cribbage_library::{
+    simulation::{simulate, simulate_matches},
+    strategy::{strategy_by_name, STRATEGY_NAMES},
+};
+use std::{env, process};
+
+const DEFAULT_GAMES: usize = 10_000;
+const DEFAULT_SEED: u64 = 42;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let games = match args.first() {
+        Some(arg) => arg
+            .parse()
+            .unwrap_or_else(|_| usage(&format!("bad game count: {}", arg))),
+        None => DEFAULT_GAMES,
+    };
+    let seed = match args.get(1) {
+        Some(arg) => arg
+            .parse()
+            .unwrap_or_else(|_| usage(&format!("bad seed: {}", arg))),
+        None => DEFAULT_SEED,
+    };
+    let name = |i: usize| args.get(i).map(String::as_str).unwrap_or("default");
+    let first = strategy_by_name(name(2))
+        .unwrap_or_else(|| usage(&format!("unknown strategy: {}", name(2))));
+    let second = strategy_by_name(name(3))
+        .unwrap_or_else(|| usage(&format!("unknown strategy: {}", name(3))));
+
+    let best_of: Option<u32> = args.get(4).map(|arg| {
+        arg.parse()
+            .unwrap_or_else(|_| usage(&format!("bad best of: {}", arg)))
+    });
+
+    let result = match best_of {
+        Some(best_of) => simulate_matches(first.as_ref(), second.as_ref(), games, best_of, seed)
+            .map(|report| report.to_string()),
+        None => simulate(first.as_ref(), second.as_ref(), games, seed).map(|r| r.to_string()),
+    };
+    match result {
+        Ok(report) => print!("{}", report),
+        Err(e) => {
+            eprintln!("{}", e.message);
+            process::exit(1);
+        }
+    }
+}
+
+fn usage(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!("usage: simulate [games] [seed] [first strategy] [second strategy] [best of]");
+    eprintln!("strategies: {}", STRATEGY_NAMES.join(", "));
+    process::exit(2);
+}
@@ -0,0 +1,198 @@
+//! `board` models the cribbage board.  Each side has two pegs: the front
+//! peg is their score and the back peg is where the front peg was before
+//! the last points were pegged, so the board shows the last move of each
+//! side.  The game ends the moment a front peg reaches the game hole, and a
+//! peg can't go past it.
+
+use crate::{
+    cribbage_errors::{CribbageError, CribbageErrorKind},
+    rules::RuleSet,
+};
+use serde::{Deserialize, Serialize};
+
+/// How badly a side lost
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Loss {
+    /// the loser passed the skunk line
+    Plain,
+    /// the loser didn't pass the skunk line, 91 in the standard game
+    Skunk,
+    /// the loser didn't pass the double skunk line, 61 in the standard game
+    DoubleSkunk,
+}
+
+/// The pegs of each side.  Sides are numbered from 0, the same as the
+/// scores of a `Game`.  A board serializes with everything needed to draw
+/// it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Board {
+    pub game_length: u32,
+    pub skunk_line: u32,
+    pub double_skunk_line: u32,
+    /// the front peg of each side, which is their score
+    front: Vec<u32>,
+    /// the back peg of each side
+    back: Vec<u32>,
+    winner: Option<usize>,
+}
+
+impl Board {
+    /// a board for `sides` sides with every peg at the start, using the game
+    /// length and skunk lines of `rules`
+    pub fn new(sides: usize, rules: &RuleSet) -> Board {
+        Board {
+            game_length: rules.game_length,
+            skunk_line: rules.skunk_line,
+            double_skunk_line: rules.double_skunk_line,
+            front: vec![0; sides],
+            back: vec![0; sides],
+            winner: None,
+        }
+    }
+
+    /// a board part way through a game, with the front pegs at `scores` and
+    /// the back pegs at the start
+    pub fn from_scores(scores: &[u32], rules: &RuleSet) -> Result<Board, CribbageError> {
+        let mut board = Board::new(scores.len(), rules);
+        for (side, score) in scores.iter().enumerate() {
+            board.peg(side, *score)?;
+            board.back[side] = 0;
+        }
+        Ok(board)
+    }
+
+    /// the score of each side, which is where their front peg is
+    pub fn scores(&self) -> &[u32] {
+        &self.front
+    }
+
+    pub fn front_peg(&self, side: usize) -> u32 {
+        self.front[side]
+    }
+
+    pub fn back_peg(&self, side: usize) -> u32 {
+        self.back[side]
+    }
+
+    /// the side that reached the game hole
+    pub fn winner(&self) -> Option<usize> {
+        self.winner
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    /// Moves the back peg of `side` up to its front peg and the front peg
+    /// `points` holes on.  The front peg stops in the game hole, and the side
+    /// wins.  It is an error to peg once the game is over.
+    ///
+    /// # Returns
+    ///
+    /// true if these points won the game
+    pub fn peg(&mut self, side: usize, points: u32) -> Result<bool, CribbageError> {
+        if side >= self.front.len() {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!("there is no side {} on the board", side),
+            ));
+        }
+        if let Some(winner) = self.winner {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!("the game is over.  side {} has won", winner),
+            ));
+        }
+        if points == 0 {
+            return Ok(false);
+        }
+
+        self.back[side] = self.front[side];
+        self.front[side] = (self.front[side] + points).min(self.game_length);
+        if self.front[side] == self.game_length {
+            self.winner = Some(side);
+        }
+        Ok(self.winner.is_some())
+    }
+
+    /// how badly `side` lost, or `None` if the game isn't over or they won
+    pub fn loss(&self, side: usize) -> Option<Loss> {
+        match self.winner {
+            Some(winner) if winner != side => {
+                let score = self.front[side];
+                Some(if score < self.double_skunk_line {
+                    Loss::DoubleSkunk
+                } else if score < self.skunk_line {
+                    Loss::Skunk
+                } else {
+                    Loss::Plain
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::GAME_LENGTH;
+
+    #[test]
+    fn pegs_leapfrog() {
+        let mut board = Board::new(2, &RuleSet::default());
+        assert!(!board.peg(0, 5).unwrap());
+        assert!(!board.peg(0, 12).unwrap());
+        assert_eq!((board.back_peg(0), board.front_peg(0)), (5, 17));
+        // no points leave both pegs where they are
+        board.peg(0, 0).unwrap();
+        assert_eq!((board.back_peg(0), board.front_peg(0)), (5, 17));
+        assert_eq!(board.scores(), &[17, 0]);
+        assert!(board.peg(2, 1).is_err());
+    }
+
+    #[test]
+    fn stops_in_the_game_hole() {
+        let mut board = Board::from_scores(&[119, 80], &RuleSet::default()).unwrap();
+        assert_eq!(board.loss(1), None);
+        assert!(board.peg(0, 6).unwrap());
+        assert_eq!(board.front_peg(0), GAME_LENGTH);
+        assert_eq!(board.back_peg(0), 119);
+        assert_eq!(board.winner(), Some(0));
+        assert_eq!(
+            board.peg(1, 2).unwrap_err().error_kind,
+            CribbageErrorKind::BadAction
+        );
+        assert_eq!(board.loss(0), None);
+        assert_eq!(board.loss(1), Some(Loss::Skunk));
+    }
+
+    #[test]
+    fn skunk_lines() {
+        let rules = RuleSet::default();
+        for (score, loss) in [
+            (60, Loss::DoubleSkunk),
+            (61, Loss::Skunk),
+            (90, Loss::Skunk),
+            (91, Loss::Plain),
+        ] {
+            let mut board = Board::from_scores(&[120, score], &rules).unwrap();
+            board.peg(0, 1).unwrap();
+            assert_eq!(board.loss(1), Some(loss));
+        }
+
+        let mut board = Board::from_scores(&[60, 20], &RuleSet::short_game()).unwrap();
+        board.peg(0, 1).unwrap();
+        assert_eq!(board.loss(1), Some(Loss::Skunk));
+    }
+
+    #[test]
+    fn serializes_for_drawing() {
+        let mut board = Board::new(2, &RuleSet::default());
+        board.peg(1, 4).unwrap();
+        let json = serde_json::to_value(&board).unwrap();
+        assert_eq!(json["front"], serde_json::json!([0, 4]));
+        assert_eq!(json["skunk_line"], 91);
+        assert_eq!(serde_json::from_value::<Board>(json).unwrap(), board);
+    }
+}
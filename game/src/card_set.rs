@@ -0,0 +1,263 @@
+//! `card_set` is a compact representation of a set of cards: a `u64` with
+//! one bit per card, where the bit for a card is `card.index()`.  Sets are
+//! `Copy`, so the hot paths in scoring and card selection can build and
+//! combine them without allocating.
+
+use crate::cards::{Card, Hand, Rank, Suit, DECK_SIZE};
+use serde::{Deserialize, Serialize};
+use std::iter::FromIterator;
+
+/// the bits of the 13 cards of one suit
+const SUIT_MASK: u64 = (1 << 13) - 1;
+
+/// the bits of the Aces.  shift left by `rank - 1` for the other ranks.
+const RANK_MASK: u64 = 1 | (1 << 13) | (1 << 26) | (1 << 39);
+
+/// A set of cards from a standard deck
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CardSet(u64);
+
+impl CardSet {
+    /// Returns an empty set
+    pub fn new() -> CardSet {
+        CardSet(0)
+    }
+
+    /// Returns a set with all 52 cards
+    pub fn full() -> CardSet {
+        CardSet((1 << DECK_SIZE) - 1)
+    }
+
+    pub fn from_bits(bits: u64) -> CardSet {
+        CardSet(bits & CardSet::full().0)
+    }
+
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    pub fn insert(&mut self, card: Card) {
+        self.0 |= CardSet::bit(card);
+    }
+
+    pub fn remove(&mut self, card: Card) {
+        self.0 &= !CardSet::bit(card);
+    }
+
+    pub fn contains(self, card: Card) -> bool {
+        self.0 & CardSet::bit(card) != 0
+    }
+
+    /// a copy of the set with `card` added
+    pub fn with(self, card: Card) -> CardSet {
+        CardSet(self.0 | CardSet::bit(card))
+    }
+
+    pub fn union(self, other: CardSet) -> CardSet {
+        CardSet(self.0 | other.0)
+    }
+
+    /// the cards in this set that are not in `other`
+    pub fn difference(self, other: CardSet) -> CardSet {
+        CardSet(self.0 & !other.0)
+    }
+
+    pub fn intersection(self, other: CardSet) -> CardSet {
+        CardSet(self.0 & other.0)
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// iterates over the cards in `Card::from_index` order
+    pub fn iter(self) -> CardSetIter {
+        CardSetIter(self.0)
+    }
+
+    /// one bit per rank for the cards of `suit`.  bit 0 is the Ace.
+    pub fn suit_mask(self, suit: Suit) -> u16 {
+        match suit {
+            Suit::Unknown => 0,
+            _ => ((self.0 >> ((suit as usize - 1) * 13)) & SUIT_MASK) as u16,
+        }
+    }
+
+    /// one bit per rank that is in the set, whatever the suit
+    pub fn rank_mask(self) -> u16 {
+        (0..4).fold(0, |mask, suit| {
+            mask | ((self.0 >> (suit * 13)) & SUIT_MASK) as u16
+        })
+    }
+
+    /// the number of cards of each rank.  index 0 is the Ace.
+    pub fn rank_counts(self) -> [u8; 13] {
+        let mut counts = [0; 13];
+        for (rank, count) in counts.iter_mut().enumerate() {
+            *count = (self.0 & (RANK_MASK << rank)).count_ones() as u8;
+        }
+        counts
+    }
+
+    /// the number of cards of `rank`
+    pub fn count_rank(self, rank: Rank) -> usize {
+        match rank {
+            Rank::Unknown => 0,
+            _ => (self.0 & (RANK_MASK << (rank as usize - 1))).count_ones() as usize,
+        }
+    }
+
+    pub fn to_hand(self) -> Hand {
+        self.iter().collect()
+    }
+
+    /// panics for a card with an unknown rank or suit, which would otherwise
+    /// give the bit of some other card
+    fn bit(card: Card) -> u64 {
+        assert!(
+            card.rank != Rank::Unknown && card.suit != Suit::Unknown,
+            "{:?} isn't a card in the deck",
+            card
+        );
+        1 << card.index()
+    }
+}
+
+impl From<&[Card]> for CardSet {
+    fn from(cards: &[Card]) -> Self {
+        cards.iter().copied().collect()
+    }
+}
+
+impl From<CardSet> for Hand {
+    fn from(set: CardSet) -> Self {
+        set.to_hand()
+    }
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = Card>>(iter: I) -> Self {
+        let mut set = CardSet::new();
+        for card in iter {
+            set.insert(card);
+        }
+        set
+    }
+}
+
+impl IntoIterator for CardSet {
+    type Item = Card;
+    type IntoIter = CardSetIter;
+
+    fn into_iter(self) -> CardSetIter {
+        self.iter()
+    }
+}
+
+/// Iterates over the cards of a `CardSet` by clearing the lowest bit
+#[derive(Copy, Clone, Debug)]
+pub struct CardSetIter(u64);
+
+impl Iterator for CardSetIter {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Card> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(Card::from_index(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for CardSetIter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{Rank::*, Suit as Of};
+    use crate::new_card as c;
+
+    #[test]
+    #[should_panic]
+    fn unknown_cards_are_not_in_the_deck() {
+        CardSet::new().insert(Card::default());
+    }
+
+    #[test]
+    fn insert_remove_contains() {
+        let mut set = CardSet::new();
+        assert!(set.is_empty());
+        set.insert(c!(Five, Of::Hearts));
+        set.insert(c!(King, Of::Spades));
+        set.insert(c!(Five, Of::Hearts));
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(c!(King, Of::Spades)));
+        assert!(!set.contains(c!(King, Of::Clubs)));
+        set.remove(c!(King, Of::Spades));
+        assert_eq!(set.to_hand(), vec![c!(Five, Of::Hearts)]);
+        assert_eq!(CardSet::full().len(), DECK_SIZE);
+    }
+
+    #[test]
+    fn set_operations() {
+        let a: CardSet = [c!(Ace, Of::Clubs), c!(Two, Of::Clubs)]
+            .iter()
+            .copied()
+            .collect();
+        let b = CardSet::new()
+            .with(c!(Two, Of::Clubs))
+            .with(c!(Ace, Of::Spades));
+        assert_eq!(a.union(b).len(), 3);
+        assert_eq!(a.intersection(b).to_hand(), vec![c!(Two, Of::Clubs)]);
+        assert_eq!(a.difference(b).to_hand(), vec![c!(Ace, Of::Clubs)]);
+        assert_eq!(CardSet::full().difference(a).len(), 50);
+    }
+
+    #[test]
+    fn hand_round_trip() {
+        let hand = vec![
+            c!(Jack, Of::Diamonds),
+            c!(Five, Of::Hearts),
+            c!(Ace, Of::Clubs),
+            c!(King, Of::Spades),
+        ];
+        let set = CardSet::from(hand.as_slice());
+        let mut sorted = hand.clone();
+        sorted.sort_by_key(|c| c.index());
+        assert_eq!(Hand::from(set), sorted);
+        assert_eq!(set.iter().len(), 4);
+        assert_eq!(CardSet::from_bits(set.bits()), set);
+    }
+
+    #[test]
+    fn ranks_and_suits() {
+        let set: CardSet = vec![
+            c!(Five, Of::Hearts),
+            c!(Five, Of::Clubs),
+            c!(Six, Of::Hearts),
+            c!(King, Of::Spades),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(set.suit_mask(Suit::Hearts), 0b11_0000);
+        assert_eq!(set.suit_mask(Suit::Diamonds), 0);
+        assert_eq!(set.rank_mask(), 0b1_0000_0011_0000);
+        assert_eq!(set.count_rank(Five), 2);
+        assert_eq!(set.count_rank(King), 1);
+        assert_eq!(set.count_rank(Ace), 0);
+        let counts = set.rank_counts();
+        assert_eq!(counts[4], 2);
+        assert_eq!(counts[12], 1);
+    }
+}
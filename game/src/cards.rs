@@ -2,8 +2,9 @@
 //! Only the cards required to represent the game cribbage are supported.
 
 use crate::cribbage_errors::{CribbageError, CribbageErrorKind};
-use rand::Rng;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, str::FromStr};
 use strum::IntoEnumIterator;
 use strum_macros::{EnumIter, EnumString};
 
@@ -48,7 +49,7 @@ const SUITS: &'static [Suit] = &[Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit
 
 /// Iterable container of `Rank`s that could be replaced with the Step
 /// trait when it is finalized
-const RANKS: &'static [Rank] = &[
+pub(crate) const RANKS: &'static [Rank] = &[
     Rank::Ace,
     Rank::Two,
     Rank::Three,
@@ -72,9 +73,6 @@ pub struct Card {
     pub suit: Suit,
 }
 
-/// `Deck` is a convenience type for more fluent code.
-pub type Deck = Vec<Card>;
-
 /// `Hand` is a convenience type for more fluent code.
 pub type Hand = Vec<Card>;
 
@@ -86,9 +84,48 @@ impl PartialEq for Card {
 
 impl Eq for Card {}
 
+/// writes the short form of the card, like "5H" or "10S".  the alternate
+/// form, `{:#}`, uses the suit symbols, like "5♥" or "10♠".
 impl std::fmt::Display for Card {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        f.write_str(&self.name())
+        let suit = match (self.suit, f.alternate()) {
+            (Suit::Clubs, false) => "C",
+            (Suit::Diamonds, false) => "D",
+            (Suit::Hearts, false) => "H",
+            (Suit::Spades, false) => "S",
+            (Suit::Clubs, true) => "♣",
+            (Suit::Diamonds, true) => "♦",
+            (Suit::Hearts, true) => "♥",
+            (Suit::Spades, true) => "♠",
+            (Suit::Unknown, _) => "?",
+        };
+        let rank = match self.rank {
+            Rank::Ace => "A".to_string(),
+            Rank::Jack => "J".to_string(),
+            Rank::Queen => "Q".to_string(),
+            Rank::King => "K".to_string(),
+            Rank::Unknown => "?".to_string(),
+            _ => (self.rank as i32).to_string(),
+        };
+        write!(f, "{}{}", rank, suit)
+    }
+}
+
+/// parses either the long form of a card, "FiveOfHearts", or the short form
+/// such as "5H", "10d", "TS" or "J♣"
+impl FromStr for Card {
+    type Err = CribbageError;
+
+    fn from_str(card_as_string: &str) -> Result<Self, Self::Err> {
+        Card::from_string(card_as_string)
+    }
+}
+
+impl TryFrom<&str> for Card {
+    type Error = CribbageError;
+
+    fn try_from(card_as_string: &str) -> Result<Self, Self::Error> {
+        Card::from_string(card_as_string)
     }
 }
 
@@ -149,7 +186,60 @@ impl Card {
         Card { rank, value, suit }
     }
 
+    /// the inverse of `from_index`
+    pub fn index(&self) -> usize {
+        (self.suit as usize - 1) * 13 + self.rank as usize - 1
+    }
+
+    /// parses either the long form of a card, "FiveOfHearts", or the short
+    /// form: the rank (A, 2-10, T, J, Q, K) followed by the suit (C, D, H, S or
+    /// ♣, ♦, ♥, ♠), in any case
     pub fn from_string(card_as_string: &str) -> Result<Self, CribbageError> {
+        if card_as_string.contains("Of") {
+            Card::from_long_name(card_as_string)
+        } else {
+            Card::from_short_name(card_as_string)
+        }
+    }
+
+    fn from_short_name(card_as_string: &str) -> Result<Self, CribbageError> {
+        let invalid = |what: &str| {
+            CribbageError::new(
+                CribbageErrorKind::ParseError,
+                format!(
+                    "{:?} is an invalid Card because of the {}.  expected a card like 5H, 10D or J♠",
+                    card_as_string, what
+                ),
+            )
+        };
+        let mut chars = card_as_string.chars();
+        let suit = match chars.next_back().map(|c| c.to_ascii_uppercase()) {
+            Some('C') | Some('♣') | Some('♧') => Suit::Clubs,
+            Some('D') | Some('♦') | Some('♢') => Suit::Diamonds,
+            Some('H') | Some('♥') | Some('♡') => Suit::Hearts,
+            Some('S') | Some('♠') | Some('♤') => Suit::Spades,
+            _ => return Err(invalid("suit")),
+        };
+        let rank = match chars.as_str().to_ascii_uppercase().as_str() {
+            "A" => Rank::Ace,
+            "2" => Rank::Two,
+            "3" => Rank::Three,
+            "4" => Rank::Four,
+            "5" => Rank::Five,
+            "6" => Rank::Six,
+            "7" => Rank::Seven,
+            "8" => Rank::Eight,
+            "9" => Rank::Nine,
+            "10" | "T" => Rank::Ten,
+            "J" => Rank::Jack,
+            "Q" => Rank::Queen,
+            "K" => Rank::King,
+            _ => return Err(invalid("rank")),
+        };
+        Ok(Card::new(rank, suit))
+    }
+
+    fn from_long_name(card_as_string: &str) -> Result<Self, CribbageError> {
         let tokens = card_as_string.split("Of").collect::<Vec<_>>();
         if tokens.len() != 2 {
             let msg = format!(
@@ -159,9 +249,10 @@ impl Card {
             return Err(CribbageError::new(CribbageErrorKind::ParseError, msg));
         };
 
+        // `Unknown` parses, but it isn't a card that can be played
         let rank: Rank = match tokens[0].parse() {
-            Ok(rank) => rank,
-            Err(_) => {
+            Ok(rank) if rank != Rank::Unknown => rank,
+            _ => {
                 return Err(CribbageError::new(
                     CribbageErrorKind::ParseError,
                     format!(
@@ -174,8 +265,8 @@ impl Card {
         };
 
         let suit: Suit = match tokens[1].parse() {
-            Ok(suit) => suit,
-            Err(_) => {
+            Ok(suit) if suit != Suit::Unknown => suit,
+            _ => {
                 return Err(CribbageError::new(
                     CribbageErrorKind::ParseError,
                     format!(
@@ -197,6 +288,207 @@ impl Card {
         format!("{:?}Of{:?}", rank, suit)
     }
 }
+
+/// Parses a list of cards separated by commas and/or spaces, in either the
+/// long or the short form, e.g. "5H 5C,5S, JD".  If a card can't be parsed
+/// the error has the position of the first character of that card.
+pub fn parse_hand(hand: &str) -> Result<Hand, CribbageError> {
+    let mut cards = Hand::new();
+    let mut token = String::new();
+    let mut start = 0;
+    // the extra separator at the end finishes the last card
+    for (position, ch) in hand.chars().chain(std::iter::once(',')).enumerate() {
+        if ch == ',' || ch.is_whitespace() {
+            if !token.is_empty() {
+                let card = Card::from_string(&token)
+                    .map_err(|e| CribbageError::at(e.error_kind, e.message, start))?;
+                cards.push(card);
+                token.clear();
+            }
+        } else {
+            if token.is_empty() {
+                start = position;
+            }
+            token.push(ch);
+        }
+    }
+    Ok(cards)
+}
+
+/// the number of cards in a standard deck
+pub const DECK_SIZE: usize = 52;
+
+/// `Deck` always holds the 52 cards of a standard deck in some order.
+/// Cards are dealt from the top, and dealt cards stay in the deck (in the
+/// order they were dealt) so that the whole order can be saved and the deal
+/// replayed.  Shuffling gathers the dealt cards back in.
+///
+/// a deck serializes as the list of its cards, top first
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<Card>", into = "Vec<Card>")]
+pub struct Deck {
+    cards: Vec<Card>,
+    dealt: usize, // cards[..dealt] have been dealt
+}
+
+impl Deck {
+    /// Returns an unshuffled deck in `Card::from_index` order
+    pub fn new() -> Deck {
+        Deck {
+            cards: (0..DECK_SIZE).map(Card::from_index).collect(),
+            dealt: 0,
+        }
+    }
+
+    /// Returns a deck shuffled by a `StdRng` seeded with `seed`, so the same
+    /// seed always gives the same order
+    pub fn from_seed(seed: u64) -> Deck {
+        let mut deck = Deck::new();
+        deck.shuffle(&mut StdRng::seed_from_u64(seed));
+        deck
+    }
+
+    /// Returns a deck in the order of `cards`, top first.  `cards` must be
+    /// the 52 different cards of a standard deck.
+    pub fn from_cards(cards: &[Card]) -> Result<Deck, CribbageError> {
+        if cards.len() != DECK_SIZE {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadCount,
+                format!("a deck has {} cards, not {}", DECK_SIZE, cards.len()),
+            ));
+        }
+        Deck::stacked(cards)
+    }
+
+    /// Returns a deck with `top` on top, in order, and the rest of the cards
+    /// under them in `Card::from_index` order.  this is used to replay a
+    /// deal when only the dealt cards were saved.
+    pub fn stacked(top: &[Card]) -> Result<Deck, CribbageError> {
+        let mut seen = [false; DECK_SIZE];
+        for card in top {
+            if card.rank == Rank::Unknown || card.suit == Suit::Unknown {
+                return Err(CribbageError::new(
+                    CribbageErrorKind::BadCard,
+                    format!("{} is not in a standard deck", card),
+                ));
+            }
+            if seen[card.index()] {
+                return Err(CribbageError::new(
+                    CribbageErrorKind::BadCard,
+                    format!("{} is in the deck more than once", card),
+                ));
+            }
+            seen[card.index()] = true;
+        }
+
+        let mut cards = top.to_vec();
+        cards.extend((0..DECK_SIZE).filter(|i| !seen[*i]).map(Card::from_index));
+        Ok(Deck { cards, dealt: 0 })
+    }
+
+    /// Gathers in any dealt cards and shuffles the whole deck
+    pub fn shuffle<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.cards.shuffle(rng);
+        self.dealt = 0;
+    }
+
+    /// Moves the top `at` cards that have not been dealt to the bottom of
+    /// the deck
+    pub fn cut(&mut self, at: usize) -> Result<(), CribbageError> {
+        if at > self.remaining().len() {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadCount,
+                format!(
+                    "can't cut {} cards from a deck with {} cards left",
+                    at,
+                    self.remaining().len()
+                ),
+            ));
+        }
+        self.cards[self.dealt..].rotate_left(at);
+        Ok(())
+    }
+
+    /// Deals the top card
+    pub fn draw(&mut self) -> Result<Card, CribbageError> {
+        match self.cards.get(self.dealt) {
+            Some(card) => {
+                self.dealt += 1;
+                Ok(*card)
+            }
+            None => Err(CribbageError::new(
+                CribbageErrorKind::BadCount,
+                "all of the cards in the deck have been dealt".to_string(),
+            )),
+        }
+    }
+
+    /// Deals `cards_each` cards to each of `players` players, one card at a
+    /// time.  `hands[0]` gets the first card.
+    pub fn deal(&mut self, players: usize, cards_each: usize) -> Result<Vec<Hand>, CribbageError> {
+        if players * cards_each > self.remaining().len() {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadCount,
+                format!(
+                    "can't deal {} cards to {} players from a deck with {} cards left",
+                    cards_each,
+                    players,
+                    self.remaining().len()
+                ),
+            ));
+        }
+        let mut hands = vec![Hand::with_capacity(cards_each); players];
+        for _ in 0..cards_each {
+            for hand in hands.iter_mut() {
+                hand.push(self.draw()?);
+            }
+        }
+        Ok(hands)
+    }
+
+    /// Cuts the cards that have not been dealt at `at` and turns up the
+    /// starter from the top of the bottom packet
+    pub fn starter(&mut self, at: usize) -> Result<Card, CribbageError> {
+        self.cut(at)?;
+        self.draw()
+    }
+
+    /// all 52 cards, top first, including the ones that have been dealt
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    /// the cards that have been dealt, in the order they were dealt
+    pub fn dealt(&self) -> &[Card] {
+        &self.cards[..self.dealt]
+    }
+
+    /// the cards that have not been dealt yet, top first
+    pub fn remaining(&self) -> &[Card] {
+        &self.cards[self.dealt..]
+    }
+}
+
+impl Default for Deck {
+    fn default() -> Self {
+        Deck::new()
+    }
+}
+
+impl TryFrom<Vec<Card>> for Deck {
+    type Error = CribbageError;
+
+    fn try_from(cards: Vec<Card>) -> Result<Self, Self::Error> {
+        Deck::from_cards(&cards)
+    }
+}
+
+impl From<Deck> for Vec<Card> {
+    fn from(deck: Deck) -> Self {
+        deck.cards
+    }
+}
+
 #[allow(unused_macros)]
 #[macro_export]
 macro_rules! new_card {
@@ -204,3 +496,147 @@ macro_rules! new_card {
         Card::new($rank, $suit)
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{Rank::*, Suit as Of};
+    use crate::new_card as c;
+
+    #[test]
+    fn index_round_trips() {
+        for index in 0..DECK_SIZE {
+            assert_eq!(Card::from_index(index).index(), index);
+        }
+    }
+
+    #[test]
+    fn short_names() {
+        assert_eq!("5H".parse::<Card>().unwrap(), c!(Five, Of::Hearts));
+        assert_eq!("10d".parse::<Card>().unwrap(), c!(Ten, Of::Diamonds));
+        assert_eq!("TS".parse::<Card>().unwrap(), c!(Ten, Of::Spades));
+        assert_eq!("jc".parse::<Card>().unwrap(), c!(Jack, Of::Clubs));
+        assert_eq!("A♠".parse::<Card>().unwrap(), c!(Ace, Of::Spades));
+        assert_eq!(Card::try_from("Q♡").unwrap(), c!(Queen, Of::Hearts));
+        assert_eq!(
+            Card::from_string("KingOfClubs").unwrap(),
+            c!(King, Of::Clubs)
+        );
+        for bad in [
+            "",
+            "5",
+            "5X",
+            "11H",
+            "1H",
+            "H",
+            "UnknownOfUnknown",
+            "FiveOfUnknown",
+            "UnknownOfHearts",
+        ] {
+            assert_eq!(
+                bad.parse::<Card>().unwrap_err().error_kind,
+                CribbageErrorKind::ParseError,
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for index in 0..DECK_SIZE {
+            let card = Card::from_index(index);
+            assert_eq!(card.to_string().parse::<Card>().unwrap(), card);
+            assert_eq!(format!("{:#}", card).parse::<Card>().unwrap(), card);
+        }
+        assert_eq!(c!(Ten, Of::Diamonds).to_string(), "10D");
+        assert_eq!(format!("{:#}", c!(Ace, Of::Spades)), "A♠");
+    }
+
+    #[test]
+    fn hands() {
+        let hand = parse_hand(" 5H,5c  FiveOfSpades, J♦").unwrap();
+        assert_eq!(
+            hand,
+            vec![
+                c!(Five, Of::Hearts),
+                c!(Five, Of::Clubs),
+                c!(Five, Of::Spades),
+                c!(Jack, Of::Diamonds)
+            ]
+        );
+        assert!(parse_hand("").unwrap().is_empty());
+
+        let err = parse_hand("5H, 5C 5X 6D").unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::ParseError);
+        assert_eq!(err.position, Some(7));
+        let err = parse_hand("♥5 5H").unwrap_err();
+        assert_eq!(err.position, Some(0));
+    }
+
+    #[test]
+    fn deck_has_52_different_cards() {
+        let deck = Deck::from_seed(3);
+        let mut indices: Vec<usize> = deck.cards().iter().map(|c| c.index()).collect();
+        indices.sort_unstable();
+        assert_eq!(indices, (0..DECK_SIZE).collect::<Vec<_>>());
+        assert_ne!(deck, Deck::new());
+        assert_eq!(deck, Deck::from_seed(3));
+    }
+
+    #[test]
+    fn deal_cut_and_starter() {
+        let mut deck = Deck::new();
+        let hands = deck.deal(2, 6).unwrap();
+        assert_eq!(hands[0][0], c!(Ace, Of::Clubs));
+        assert_eq!(hands[1][0], c!(Two, Of::Clubs));
+        assert_eq!(hands[0][5], c!(Jack, Of::Clubs));
+        assert_eq!(deck.dealt().len(), 12);
+        assert_eq!(deck.remaining().len(), 40);
+
+        let starter = deck.starter(3).unwrap();
+        assert_eq!(starter, c!(Three, Of::Diamonds));
+        assert_eq!(deck.cards().len(), DECK_SIZE);
+        assert_eq!(
+            deck.deal(5, 8).unwrap_err().error_kind,
+            CribbageErrorKind::BadCount
+        );
+        assert_eq!(
+            deck.cut(40).unwrap_err().error_kind,
+            CribbageErrorKind::BadCount
+        );
+
+        deck.shuffle(&mut StdRng::seed_from_u64(1));
+        assert_eq!(deck.remaining().len(), DECK_SIZE);
+    }
+
+    #[test]
+    fn stacked_and_from_cards() {
+        let deck = Deck::stacked(&[c!(King, Of::Spades), c!(Ace, Of::Clubs)]).unwrap();
+        assert_eq!(deck.cards()[0], c!(King, Of::Spades));
+        assert_eq!(deck.cards()[1], c!(Ace, Of::Clubs));
+        assert_eq!(deck.cards()[2], c!(Two, Of::Clubs));
+        assert_eq!(deck.cards().len(), DECK_SIZE);
+
+        let twice = [c!(Ace, Of::Clubs), c!(Ace, Of::Clubs)];
+        assert_eq!(
+            Deck::stacked(&twice).unwrap_err().error_kind,
+            CribbageErrorKind::BadCard
+        );
+        assert_eq!(
+            Deck::from_cards(&twice).unwrap_err().error_kind,
+            CribbageErrorKind::BadCount
+        );
+    }
+
+    #[test]
+    fn deck_order_round_trips_through_json() {
+        let mut deck = Deck::from_seed(8);
+        let json = serde_json::to_string(&deck).unwrap();
+        let mut replayed: Deck = serde_json::from_str(&json).unwrap();
+        assert_eq!(deck.deal(2, 6).unwrap(), replayed.deal(2, 6).unwrap());
+
+        let short = serde_json::to_string(&deck.cards()[..51]).unwrap();
+        assert!(serde_json::from_str::<Deck>(&short).is_err());
+    }
+}
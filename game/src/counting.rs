@@ -1,12 +1,22 @@
 use crate::cards::Card;
 use crate::cribbage_errors::{CribbageError, CribbageErrorKind};
-use crate::scoring::{score_run, Combination, CombinationKind, Score};
+use crate::rules::RuleSet;
+use crate::scoring::{score_run_with_rules, Combination, CombinationKind, Score};
 
 /// Calculates the score during the counting phase where played_cards have already been played and card is now played
 ///
 pub fn score_counting_cards_played(
     played_cards: &[Card],
     card: Card,
+) -> Result<Score, CribbageError> {
+    score_counting_cards_played_with_rules(played_cards, card, &RuleSet::default())
+}
+
+/// `score_counting_cards_played` with the house `rules` for runs
+pub fn score_counting_cards_played_with_rules(
+    played_cards: &[Card],
+    card: Card,
+    rules: &RuleSet,
 ) -> Result<Score, CribbageError> {
     let count: i32 = played_cards.iter().map(|c| c.value).sum::<i32>() + card.value;
     if count > 31 {
@@ -70,7 +80,7 @@ pub fn score_counting_cards_played(
             // we can't sort the slice because they sort in place and return ()
             let mut possible_run = all_cards.as_slice()[i..].to_vec();
             possible_run.sort();
-            if let Some(c) = score_run(possible_run) {
+            if let Some(c) = score_run_with_rules(possible_run, rules) {
                 score.combinations.push(c);
                 break; // stop on the largest run
             }
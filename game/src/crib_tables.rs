@@ -0,0 +1,349 @@
+//! `crib_tables` holds the expected points of a crib for each pair of cards
+//! that can be discarded to it, for our crib and for the opponent's, with
+//! separate tables for pairs of the same suit.
+//!
+//! The tables are generated by the `generate_crib_tables` tool, which deals
+//! the opponent random hands and has them discard with a `Strategy`, so the
+//! values depend on how the opponent is assumed to discard.  The shipped
+//! tables are in `tables/crib_tables.txt`, which records the version, the
+//! opponent, the samples and the seed that made it.
+
+use crate::{
+    card_set::CardSet,
+    cards::{Card, Suit, RANKS},
+    cribbage_errors::{CribbageError, CribbageErrorKind},
+    discard_analysis::unseen_cards,
+    scoring::score_points,
+    strategy::{Strategy, StrategyContext},
+    table_file::{write_row, TableLines},
+};
+use lazy_static::lazy_static;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use rayon::prelude::*;
+use std::{fmt, str::FromStr};
+
+/// the version of the table file format
+pub const CRIB_TABLES_VERSION: u32 = 1;
+
+lazy_static! {
+    static ref SHIPPED: CribTables = include_str!("../tables/crib_tables.txt")
+        .parse()
+        .expect("the shipped crib tables should parse");
+}
+
+/// the expected crib points for each pair of ranks, indexed by the rank
+/// minus one.  the suited table has nothing on its diagonal, as a pair
+/// can't be of the same suit.
+pub type RankTable = [[f32; 13]; 13];
+
+/// The crib tables, and how they were generated
+#[derive(Clone, Debug, PartialEq)]
+pub struct CribTables {
+    pub version: u32,
+    /// the name of the strategy the opponent discards with
+    pub opponent: String,
+    /// the cribs averaged for each value
+    pub samples: usize,
+    pub seed: u64,
+    /// indexed by whether it's our crib, then whether the cards are suited
+    tables: [[RankTable; 2]; 2],
+}
+
+impl CribTables {
+    /// the tables in `tables/crib_tables.txt`, which `select_crib_cards`
+    /// uses
+    pub fn shipped() -> &'static CribTables {
+        &SHIPPED
+    }
+
+    /// the expected points of a crib that `card1` and `card2` are discarded
+    /// to
+    pub fn value(&self, card1: Card, card2: Card, my_crib: bool) -> f32 {
+        let suited = card1.suit == card2.suit;
+        self.table(my_crib, suited)[card1.rank as usize - 1][card2.rank as usize - 1]
+    }
+
+    pub fn table(&self, my_crib: bool, suited: bool) -> &RankTable {
+        &self.tables[my_crib as usize][suited as usize]
+    }
+
+    /// Generates the tables by dealing `samples` random opponent hands for
+    /// each value and letting `opponent` discard from them.  each value has
+    /// its own random numbers, so any one of them can be checked on its own
+    /// with `generate_value`.
+    pub fn generate(
+        opponent: &dyn Strategy,
+        samples: usize,
+        seed: u64,
+    ) -> Result<CribTables, CribbageError> {
+        let mut keys = Vec::new();
+        for my_crib in [false, true] {
+            for suited in [false, true] {
+                for rank1 in 1..=13 {
+                    // the tables are symmetric, and a pair can't be suited
+                    for rank2 in rank1 + suited as usize..=13 {
+                        keys.push((my_crib, suited, rank1, rank2));
+                    }
+                }
+            }
+        }
+        let values = keys
+            .par_iter()
+            .map(|(my_crib, suited, rank1, rank2)| {
+                generate_value(opponent, *rank1, *rank2, *suited, *my_crib, samples, seed)
+            })
+            .collect::<Result<Vec<f32>, CribbageError>>()?;
+
+        let mut tables = [[[[0.0; 13]; 13]; 2]; 2];
+        for ((my_crib, suited, rank1, rank2), value) in keys.iter().zip(values) {
+            let table = &mut tables[*my_crib as usize][*suited as usize];
+            table[rank1 - 1][rank2 - 1] = value;
+            table[rank2 - 1][rank1 - 1] = value;
+        }
+        Ok(CribTables {
+            version: CRIB_TABLES_VERSION,
+            opponent: opponent.name().to_string(),
+            samples,
+            seed,
+            tables,
+        })
+    }
+}
+
+/// Generates one value of the crib tables: the average points of `samples`
+/// cribs that a card of `rank1` and one of `rank2` are discarded to.  for
+/// each crib the rest of our hand, the opponent's hand and the starter are
+/// dealt at random, and `opponent` discards.  ranks count from 1 for an ace.
+pub fn generate_value(
+    opponent: &dyn Strategy,
+    rank1: usize,
+    rank2: usize,
+    suited: bool,
+    my_crib: bool,
+    samples: usize,
+    seed: u64,
+) -> Result<f32, CribbageError> {
+    if rank1 == rank2 && suited {
+        return Err(CribbageError::new(
+            CribbageErrorKind::BadCard,
+            "a pair can't be of the same suit".to_string(),
+        ));
+    }
+    let rank = |rank: usize| {
+        RANKS.get(rank.wrapping_sub(1)).copied().ok_or_else(|| {
+            CribbageError::new(
+                CribbageErrorKind::BadCard,
+                format!("{} isn't a rank from 1 to 13", rank),
+            )
+        })
+    };
+    let discard = [
+        Card::new(rank(rank1)?, Suit::Hearts),
+        Card::new(
+            rank(rank2)?,
+            if suited { Suit::Hearts } else { Suit::Spades },
+        ),
+    ];
+    let mut deck = unseen_cards(&discard);
+
+    // every value gets its own stream of random numbers
+    let key = ((my_crib as usize * 2 + suited as usize) * 13 + rank1 - 1) * 13 + rank2 - 1;
+    let mut rng = StdRng::seed_from_u64(seed.wrapping_add(key as u64));
+    let mut total = 0;
+    for _ in 0..samples {
+        // the other 4 cards of our hand, the opponent's 6 and the starter
+        let (dealt, _) = deck.partial_shuffle(&mut rng, 11);
+        let hand = &dealt[4..10];
+        let context = StrategyContext::from_hand(hand, !my_crib);
+        let crib = opponent
+            .choose_discard(&context)?
+            .iter()
+            .fold(CardSet::from(&discard[..]), |crib, card| crib.with(*card));
+        total += score_points(crib, Some(dealt[10]), true);
+    }
+    Ok(total as f32 / samples as f32)
+}
+
+/// The table file: a line each for the version, opponent, samples and seed,
+/// then each table as a header and 13 rows of 13 values.  `#` starts a
+/// comment.
+impl fmt::Display for CribTables {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "# expected crib points by the ranks discarded, from ace to king"
+        )?;
+        writeln!(f, "version {}", self.version)?;
+        writeln!(f, "opponent {}", self.opponent)?;
+        writeln!(f, "samples {}", self.samples)?;
+        writeln!(f, "seed {}", self.seed)?;
+        for my_crib in [true, false] {
+            for suited in [false, true] {
+                writeln!(f, "{}", table_name(my_crib, suited))?;
+                for row in self.table(my_crib, suited) {
+                    write_row(f, row, 3)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CribTables {
+    type Err = CribbageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = TableLines::new(s, "the crib tables");
+        let version = lines.version(CRIB_TABLES_VERSION)?;
+        let opponent = lines.value("opponent")?;
+        let samples = lines.value("samples")?;
+        let seed = lines.value("seed")?;
+
+        let mut tables = [[[[0.0; 13]; 13]; 2]; 2];
+        for my_crib in [true, false] {
+            for suited in [false, true] {
+                let name = table_name(my_crib, suited);
+                lines.name(&name)?;
+                for row in tables[my_crib as usize][suited as usize].iter_mut() {
+                    row.copy_from_slice(&lines.row(&name, Some(13))?);
+                }
+            }
+        }
+        lines.finish()?;
+
+        Ok(CribTables {
+            version,
+            opponent,
+            samples,
+            seed,
+            tables,
+        })
+    }
+}
+
+fn table_name(my_crib: bool, suited: bool) -> String {
+    format!(
+        "{} {}",
+        if my_crib { "my_crib" } else { "your_crib" },
+        if suited { "suited" } else { "unsuited" }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{Rank::*, Suit as Of};
+    use crate::new_card as c;
+    use crate::strategy::strategy_by_name;
+
+    #[test]
+    fn shipped_tables_match_a_fresh_generation() {
+        let shipped = CribTables::shipped();
+        assert_eq!(shipped.version, CRIB_TABLES_VERSION);
+        let opponent = strategy_by_name(&shipped.opponent).unwrap();
+        // the whole generation is slow, so a few values are regenerated
+        for (rank1, rank2, suited, my_crib) in [
+            (5, 5, false, true),
+            (5, 11, true, false),
+            (1, 13, false, false),
+            (7, 8, true, true),
+        ] {
+            let value = generate_value(
+                opponent.as_ref(),
+                rank1,
+                rank2,
+                suited,
+                my_crib,
+                shipped.samples,
+                shipped.seed,
+            )
+            .unwrap();
+            let table = shipped.table(my_crib, suited);
+            assert_eq!(
+                format!("{:.3}", value),
+                format!("{:.3}", table[rank1 - 1][rank2 - 1])
+            );
+        }
+    }
+
+    /// this regenerates all 338 values, which takes a while even in a
+    /// release build, so it is only run on request:
+    /// `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn shipped_tables_match_a_full_generation() {
+        let shipped = CribTables::shipped();
+        let opponent = strategy_by_name(&shipped.opponent).unwrap();
+        let generated =
+            CribTables::generate(opponent.as_ref(), shipped.samples, shipped.seed).unwrap();
+        for my_crib in [true, false] {
+            for suited in [false, true] {
+                let rows = shipped
+                    .table(my_crib, suited)
+                    .iter()
+                    .zip(generated.table(my_crib, suited));
+                for (rank1, (shipped_row, generated_row)) in rows.enumerate() {
+                    for (rank2, (value, fresh)) in shipped_row.iter().zip(generated_row).enumerate()
+                    {
+                        assert_eq!(
+                            format!("{:.3}", value),
+                            format!("{:.3}", fresh),
+                            "{} ranks {} and {}",
+                            table_name(my_crib, suited),
+                            rank1 + 1,
+                            rank2 + 1
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tables_are_symmetric() {
+        let shipped = CribTables::shipped();
+        for my_crib in [true, false] {
+            for suited in [false, true] {
+                let table = shipped.table(my_crib, suited);
+                for (rank1, row) in table.iter().enumerate() {
+                    assert_eq!(suited, row[rank1] == 0.0);
+                    for (rank2, value) in row.iter().enumerate() {
+                        assert_eq!(*value, table[rank2][rank1]);
+                    }
+                }
+            }
+        }
+        // fives are worth the most in either crib
+        let five = c!(Five, Of::Hearts);
+        assert!(shipped.value(five, c!(Five, Of::Spades), true) > 8.0);
+        assert!(
+            shipped.value(five, c!(Five, Of::Spades), true)
+                > shipped.value(c!(King, Of::Hearts), c!(Nine, Of::Spades), true)
+        );
+    }
+
+    #[test]
+    fn round_trips_the_text_form() {
+        let shipped = CribTables::shipped();
+        let text = shipped.to_string();
+        assert_eq!(&text.parse::<CribTables>().unwrap(), shipped);
+
+        let old = text.replace("version 1", "version 0");
+        let err = old.parse::<CribTables>().unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::ParseError);
+        assert!(err.message.starts_with("line 2:"), "{}", err.message);
+        assert!(text[..text.len() - 10].parse::<CribTables>().is_err());
+    }
+
+    #[test]
+    fn generates_small_tables() {
+        let opponent = strategy_by_name("table_free").unwrap();
+        let tables = CribTables::generate(opponent.as_ref(), 2, 7).unwrap();
+        assert_eq!(tables.opponent, "table_free");
+        let value = generate_value(opponent.as_ref(), 3, 9, true, false, 2, 7).unwrap();
+        assert_eq!(tables.table(false, true)[8][2], value);
+        assert!(generate_value(opponent.as_ref(), 4, 4, true, true, 2, 7).is_err());
+        assert!(generate_value(opponent.as_ref(), 0, 4, false, true, 2, 7).is_err());
+        assert!(generate_value(opponent.as_ref(), 14, 4, false, true, 2, 7).is_err());
+    }
+}
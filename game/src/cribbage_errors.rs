@@ -7,6 +7,7 @@ pub enum CribbageErrorKind {
     ParseError,
     BadHand,
     BadCount,
+    BadAction,
 }
 ///
 /// a hopefully useful message that tells the caller why the Web API failed
@@ -14,6 +15,10 @@ pub enum CribbageErrorKind {
 pub struct CribbageError {
     pub error_kind: CribbageErrorKind,
     pub message: String,
+    /// where in the input the error is, in characters from the start, for
+    /// errors from parsing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
 }
 
 impl CribbageError {
@@ -21,6 +26,29 @@ impl CribbageError {
         CribbageError {
             error_kind: kind,
             message: msg,
+            position: None,
+        }
+    }
+
+    /// an error at character `position` of the input that was being parsed
+    pub fn at(kind: CribbageErrorKind, msg: String, position: usize) -> CribbageError {
+        CribbageError {
+            error_kind: kind,
+            message: msg,
+            position: Some(position),
+        }
+    }
+}
+
+impl std::fmt::Display for CribbageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some(position) => write!(
+                f,
+                "{:?} at position {}: {}",
+                self.error_kind, position, self.message
+            ),
+            None => write!(f, "{:?}: {}", self.error_kind, self.message),
         }
     }
 }
@@ -0,0 +1,381 @@
+//! `discard_analysis` evaluates every way of discarding two cards from a six
+//! card hand, or one card from the five card hand of three player cribbage.
+//! The kept hand is scored against each of the starters that could be cut,
+//! so the average, minimum and maximum are exact.  The value of
+//! the crib depends on cards we can't see and is estimated as described by
+//! `CribEstimate`.
+
+use crate::{
+    card_set::CardSet,
+    cards::{Card, Hand, Rank, Suit},
+    combinator::all_combinations_of_size,
+    cribbage_errors::{CribbageError, CribbageErrorKind},
+    scoring::score_points,
+    select_cards::discard_table_value,
+    variant::{Variant, CRIB_SIZE},
+};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// How the expected value of the crib is estimated
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CribEstimate {
+    /// look up the discarded cards in the crib tables
+    Table,
+    /// average the points of the discards with each starter, leaving out
+    /// the cards the opponent adds.  it doesn't use the crib tables, so they
+    /// can be generated against it.
+    DiscardsOnly,
+    /// average over `samples` random opponent discards and starters
+    Simulation { samples: usize, seed: u64 },
+    /// average over every possible opponent discard and starter.  this is
+    /// 45,540 cribs per discard of two cards and 713,460 per discard of one,
+    /// so it is slow.
+    Exact,
+}
+
+/// The evaluation of keeping `keep` and putting `discard` in the crib
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DiscardOption {
+    pub keep: Hand,
+    pub discard: Hand,
+    pub average_hand_score: f32,
+    pub min_hand_score: u32,
+    pub max_hand_score: u32,
+    /// the points the crib is expected to score, whoever owns it
+    pub expected_crib_score: f32,
+    /// the average hand score plus the crib if it is ours, minus the crib if
+    /// it is the opponent's
+    pub expected_value: f32,
+    /// the starters that give the kept hand `max_hand_score`
+    pub best_starters: Hand,
+}
+
+/// Evaluates each of the 15 ways to discard two cards from `six_card_hand`,
+/// or the 5 ways to discard one card if it only has five cards.
+///
+/// five cards are also dealt in the five card game, which discards two of
+/// them, but the hand alone can't tell the two apart, so five cards are
+/// always taken to be three or four player cribbage.  use
+/// `analyze_discards_for` with `Variant::FiveCard` for the five card game.
+///
+/// # Returns
+///
+/// the options sorted from the highest `expected_value` to the lowest
+pub fn analyze_discards(
+    six_card_hand: &[Card],
+    my_crib: bool,
+    crib_estimate: CribEstimate,
+) -> Result<Vec<DiscardOption>, CribbageError> {
+    match Variant::from_cards_dealt(six_card_hand.len()) {
+        Some(variant) => analyze_discards_for(six_card_hand, variant, my_crib, crib_estimate),
+        None => Err(CribbageError::new(
+            CribbageErrorKind::BadHand,
+            format!(
+                "a hand should have 5 or 6 cards to analyze discards.  you passed in {}",
+                six_card_hand.len()
+            ),
+        )),
+    }
+}
+
+/// `analyze_discards` for a hand dealt in `variant`.  the five card game
+/// keeps 3 cards, so there are 10 options.
+pub fn analyze_discards_for(
+    six_card_hand: &[Card],
+    variant: Variant,
+    my_crib: bool,
+    crib_estimate: CribEstimate,
+) -> Result<Vec<DiscardOption>, CribbageError> {
+    if six_card_hand.len() != variant.cards_dealt() {
+        return Err(CribbageError::new(
+            CribbageErrorKind::BadHand,
+            format!(
+                "a hand should have {} cards to analyze discards.  you passed in {}",
+                variant.cards_dealt(),
+                six_card_hand.len()
+            ),
+        ));
+    }
+    if let Some(card) = six_card_hand
+        .iter()
+        .find(|c| c.rank == Rank::Unknown || c.suit == Suit::Unknown)
+    {
+        return Err(CribbageError::new(
+            CribbageErrorKind::BadCard,
+            format!("{:?} isn't a card in the deck", card),
+        ));
+    }
+    if CardSet::from(six_card_hand).len() != six_card_hand.len() {
+        return Err(CribbageError::new(
+            CribbageErrorKind::BadHand,
+            format!(
+                "a hand can't have the same card twice.  you passed in {:?}",
+                six_card_hand
+            ),
+        ));
+    }
+    if let CribEstimate::Simulation { samples: 0, .. } = crib_estimate {
+        return Err(CribbageError::new(
+            CribbageErrorKind::BadAction,
+            "a simulated crib needs at least one sample".to_string(),
+        ));
+    }
+
+    let kept_cards = variant.cards_kept() as u32;
+    let unseen = unseen_cards(six_card_hand);
+    let mut options = Vec::new();
+    for keep in all_combinations_of_size(six_card_hand.to_vec(), kept_cards, kept_cards) {
+        let discard: Hand = six_card_hand
+            .iter()
+            .filter(|c| !keep.contains(c))
+            .copied()
+            .collect();
+
+        let kept = CardSet::from(keep.as_slice());
+        let mut total = 0;
+        let mut min = u32::MAX;
+        let mut max = 0;
+        let mut best_starters = Vec::new();
+        for starter in unseen.iter() {
+            let points = score_points(kept, Some(*starter), false);
+            total += points;
+            min = min.min(points);
+            if points > max {
+                max = points;
+                best_starters.clear();
+            }
+            if points == max {
+                best_starters.push(*starter);
+            }
+        }
+        let average = total as f32 / unseen.len() as f32;
+        let crib = expected_crib_score(&discard, &unseen, my_crib, crib_estimate);
+
+        options.push(DiscardOption {
+            keep,
+            discard,
+            average_hand_score: average,
+            min_hand_score: min,
+            max_hand_score: max,
+            expected_crib_score: crib,
+            expected_value: if my_crib {
+                average + crib
+            } else {
+                average - crib
+            },
+            best_starters,
+        });
+    }
+
+    options.sort_by(|a, b| b.expected_value.total_cmp(&a.expected_value));
+    Ok(options)
+}
+
+/// Estimates the points scored by a crib that contains `discard`, where
+/// `unseen` are the cards that the opponent's discards and the starter can
+/// come from.
+pub fn expected_crib_score(
+    discard: &[Card],
+    unseen: &[Card],
+    my_crib: bool,
+    crib_estimate: CribEstimate,
+) -> f32 {
+    match crib_estimate {
+        CribEstimate::Table => discard_table_value(discard, my_crib),
+        CribEstimate::DiscardsOnly => {
+            let discard = CardSet::from(discard);
+            let total: u32 = unseen
+                .iter()
+                .map(|starter| score_points(discard, Some(*starter), true))
+                .sum();
+            total as f32 / unseen.len() as f32
+        }
+        CribEstimate::Simulation { samples, seed } => {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut cards = unseen.to_vec();
+            let needed = CRIB_SIZE - discard.len();
+            let discard = CardSet::from(discard);
+            let mut total = 0;
+            for _ in 0..samples {
+                let (picked, _) = cards.partial_shuffle(&mut rng, needed + 1);
+                let crib = picked[..needed]
+                    .iter()
+                    .fold(discard, |crib, card| crib.with(*card));
+                total += score_points(crib, Some(picked[needed]), true);
+            }
+            total as f32 / samples as f32
+        }
+        CribEstimate::Exact => {
+            let mut total = 0;
+            let mut count = 0;
+            // the combinator masks every subset of its input, which is far too
+            // many for 46 cards, so the cribs are enumerated directly
+            for_each_crib(
+                unseen,
+                CRIB_SIZE - discard.len(),
+                CardSet::from(discard),
+                &mut |crib| {
+                    for starter in unseen.iter().filter(|c| !crib.contains(**c)) {
+                        total += score_points(crib, Some(*starter), true);
+                        count += 1;
+                    }
+                },
+            );
+            total as f32 / count as f32
+        }
+    }
+}
+
+/// calls `f` with `crib` plus each way of picking `needed` more of `cards`
+pub(crate) fn for_each_crib(cards: &[Card], needed: usize, crib: CardSet, f: &mut dyn FnMut(CardSet)) {
+    if needed == 0 {
+        f(crib);
+        return;
+    }
+    for (i, card) in cards.iter().enumerate() {
+        for_each_crib(&cards[i + 1..], needed - 1, crib.with(*card), f);
+    }
+}
+
+/// the cards in the deck that are not in `hand`
+pub fn unseen_cards(hand: &[Card]) -> Hand {
+    CardSet::full().difference(CardSet::from(hand)).to_hand()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{Rank::*, Suit as Of};
+    use crate::new_card as c;
+
+    fn hand() -> Hand {
+        vec![
+            c!(Five, Of::Hearts),
+            c!(Five, Of::Clubs),
+            c!(Five, Of::Spades),
+            c!(Jack, Of::Diamonds),
+            c!(Six, Of::Clubs),
+            c!(Four, Of::Diamonds),
+        ]
+    }
+
+    #[test]
+    fn fifteen_options_sorted_by_value() {
+        let options = analyze_discards(&hand(), false, CribEstimate::Table).unwrap();
+        assert_eq!(options.len(), 15);
+        for pair in options.windows(2) {
+            assert!(pair[0].expected_value >= pair[1].expected_value);
+        }
+        for option in options.iter() {
+            assert_eq!(option.keep.len(), 4);
+            assert_eq!(option.discard.len(), 2);
+            assert!(option.min_hand_score as f32 <= option.average_hand_score);
+            assert!(option.average_hand_score <= option.max_hand_score as f32);
+        }
+    }
+
+    #[test]
+    fn three_fives_and_a_jack() {
+        let options = analyze_discards(&hand(), false, CribEstimate::Table).unwrap();
+        let option = options
+            .iter()
+            .find(|o| {
+                o.discard.contains(&c!(Six, Of::Clubs))
+                    && o.discard.contains(&c!(Four, Of::Diamonds))
+            })
+            .unwrap();
+        // 14 points without a starter, 29 with the five of diamonds
+        assert_eq!(option.min_hand_score, 14);
+        assert_eq!(option.max_hand_score, 29);
+        assert_eq!(option.best_starters, vec![c!(Five, Of::Diamonds)]);
+        assert_eq!(options[0].discard, option.discard);
+    }
+
+    #[test]
+    fn simulation_is_repeatable_and_close_to_exact() {
+        let discard = [c!(Five, Of::Hearts), c!(Five, Of::Clubs)];
+        let unseen = unseen_cards(&hand());
+        let estimate = CribEstimate::Simulation {
+            samples: 2000,
+            seed: 11,
+        };
+        let simulated = expected_crib_score(&discard, &unseen, true, estimate);
+        assert_eq!(
+            simulated,
+            expected_crib_score(&discard, &unseen, true, estimate)
+        );
+        let exact = expected_crib_score(&discard, &unseen, true, CribEstimate::Exact);
+        assert!(
+            (simulated - exact).abs() < 0.5,
+            "{} vs {}",
+            simulated,
+            exact
+        );
+    }
+
+    #[test]
+    fn five_card_hands_discard_one() {
+        let options = analyze_discards(&hand()[0..5], true, CribEstimate::Table).unwrap();
+        assert_eq!(options.len(), 5);
+        assert!(options.iter().all(|o| o.discard.len() == 1));
+        assert_eq!(options[0].discard, vec![c!(Six, Of::Clubs)]);
+
+        let unseen = unseen_cards(&hand()[0..5]);
+        let discard = [c!(Six, Of::Clubs)];
+        let estimate = CribEstimate::Simulation {
+            samples: 2000,
+            seed: 3,
+        };
+        let simulated = expected_crib_score(&discard, &unseen, true, estimate);
+        let exact = expected_crib_score(&discard, &unseen, true, CribEstimate::Exact);
+        assert!(
+            (simulated - exact).abs() < 0.5,
+            "{} vs {}",
+            simulated,
+            exact
+        );
+    }
+
+    #[test]
+    fn five_card_game_keeps_three() {
+        let options =
+            analyze_discards_for(&hand()[0..5], Variant::FiveCard, false, CribEstimate::Table)
+                .unwrap();
+        assert_eq!(options.len(), 10);
+        assert!(options
+            .iter()
+            .all(|o| o.keep.len() == 3 && o.discard.len() == 2));
+        // three fives are 6 for the pairs and 2 for the fifteen
+        let fives = options
+            .iter()
+            .find(|o| o.keep.iter().all(|c| c.rank == Five))
+            .unwrap();
+        assert_eq!(fives.min_hand_score, 8);
+    }
+
+    #[test]
+    fn bad_hands() {
+        let mut repeated = hand();
+        repeated[1] = repeated[0];
+        let err = analyze_discards(&repeated, false, CribEstimate::Table).unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::BadHand);
+
+        let mut unknown = hand();
+        unknown[5] = Card::default();
+        let err = analyze_discards(&unknown, false, CribEstimate::Table).unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::BadCard);
+
+        let no_samples = CribEstimate::Simulation {
+            samples: 0,
+            seed: 1,
+        };
+        assert!(analyze_discards(&hand(), true, no_samples).is_err());
+    }
+
+    #[test]
+    fn bad_hand_size() {
+        let err = analyze_discards(&hand()[0..4], true, CribEstimate::Table).unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::BadHand);
+    }
+}
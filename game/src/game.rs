@@ -0,0 +1,1012 @@
+//! `game` drives a complete game of cribbage, from the cut for deal through
+//! to the first player to reach 121 points, or the game length of its
+//! `RuleSet`.  The `Variant` says how many players there are and how the
+//! cards are dealt.
+//!
+//! `Game` is a state machine.  Each action is only legal in one `GamePhase`
+//! and an illegal action is rejected with a `CribbageError` without changing
+//! the state of the game.
+
+use crate::{
+    board::Board,
+    cards::{Card, Deck, Hand},
+    cribbage_errors::{CribbageError, CribbageErrorKind},
+    muggins::{check_claim, Claim, MugginsCheck},
+    pegging::PeggingRound,
+    record::{GameMove, GameRecord, GameResult},
+    rules::RuleSet,
+    scoring::{his_heels_score, score_hand_with_rules, Score},
+    strategy::{Strategy, StrategyContext},
+    variant::{Variant, CRIB_SIZE},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// The phases of a game.  `Game` moves through them in this order, returning
+/// to `Deal` after the crib is counted, until somebody wins.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GamePhase {
+    CutForDeal,
+    Deal,
+    Discard,
+    Starter,
+    Pegging,
+    Show,
+    Crib,
+    GameOver,
+}
+
+/// `Game` holds all of the state for a game.  Players are identified by
+/// their seat, from 0, and play in seat order.  Scores are kept for each
+/// side, which is each seat except in partnership games, where it is the
+/// partnership given by `Variant::team_of`.
+#[derive(Debug, Clone)]
+pub struct Game {
+    phase: GamePhase,
+    rng: StdRng,
+    deck: Deck,
+    dealer: usize,
+    hands: Vec<Hand>, // the cards each player keeps for the show
+    discards: Vec<Hand>,
+    crib: Hand,
+    starter: Option<Card>,
+    board: Board,
+    pegging: PeggingRound,
+    hands_shown: usize,
+    rules: RuleSet,
+    variant: Variant,
+    record: GameRecord,
+}
+
+impl Game {
+    /// Returns a new game that shuffles with a random seed
+    pub fn new() -> Game {
+        Game::from_seed(rand::thread_rng().gen())
+    }
+
+    /// Returns a new game whose shuffles are reproducible from `seed`
+    pub fn from_seed(seed: u64) -> Game {
+        Game::build(seed, Variant::TwoPlayer, RuleSet::default())
+    }
+
+    /// Returns a new two player game played with the house `rules`, whose
+    /// shuffles are reproducible from `seed`
+    pub fn with_rules(seed: u64, rules: RuleSet) -> Result<Game, CribbageError> {
+        Game::with_variant(seed, Variant::TwoPlayer, rules)
+    }
+
+    /// Returns a new game of `variant` played with the house `rules`, whose
+    /// shuffles are reproducible from `seed`
+    pub fn with_variant(
+        seed: u64,
+        variant: Variant,
+        rules: RuleSet,
+    ) -> Result<Game, CribbageError> {
+        rules.validate()?;
+        Ok(Game::build(seed, variant, rules))
+    }
+
+    fn build(seed: u64, variant: Variant, rules: RuleSet) -> Game {
+        let players = variant.players();
+        Game {
+            phase: GamePhase::CutForDeal,
+            rng: StdRng::seed_from_u64(seed),
+            deck: Deck::new(),
+            dealer: 0,
+            hands: vec![Hand::new(); players],
+            discards: vec![Hand::new(); players],
+            crib: Hand::new(),
+            starter: None,
+            board: Board::new(variant.teams(), &rules),
+            pegging: PeggingRound::with_rules(vec![Hand::new(); players], 0, rules),
+            hands_shown: 0,
+            rules,
+            variant,
+            record: GameRecord::new(seed, variant, rules),
+        }
+    }
+
+    /// the house rules the game is played with
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn players(&self) -> usize {
+        self.variant.players()
+    }
+
+    pub fn phase(&self) -> GamePhase {
+        self.phase
+    }
+
+    pub fn dealer(&self) -> usize {
+        self.dealer
+    }
+
+    /// the pone is the player after the dealer.  they play first and count
+    /// first.
+    pub fn pone(&self) -> usize {
+        self.next_player(self.dealer)
+    }
+
+    /// the score of each side
+    pub fn scores(&self) -> &[u32] {
+        self.board.scores()
+    }
+
+    /// the side that won
+    pub fn winner(&self) -> Option<usize> {
+        self.board.winner()
+    }
+
+    /// the pegs of each side
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// the cards `player` holds for the show.  this includes the cards they
+    /// have already played during pegging.
+    pub fn hand(&self, player: usize) -> &[Card] {
+        &self.hands[player]
+    }
+
+    /// the cards `player` still holds during pegging
+    pub fn unplayed_cards(&self, player: usize) -> &[Card] {
+        self.pegging.hand(player)
+    }
+
+    pub fn crib(&self) -> &[Card] {
+        &self.crib
+    }
+
+    pub fn starter(&self) -> Option<Card> {
+        self.starter
+    }
+
+    /// the cards played since the count was last reset
+    pub fn pile(&self) -> &[Card] {
+        self.pegging.pile()
+    }
+
+    pub fn count(&self) -> i32 {
+        self.pegging.count()
+    }
+
+    /// the player who has to act next during pegging
+    pub fn turn(&self) -> usize {
+        self.pegging.turn()
+    }
+
+    /// the state of the current round of pegging
+    pub fn pegging(&self) -> &PeggingRound {
+        &self.pegging
+    }
+
+    /// the deck as it was dealt.  saving it lets the deal be replayed.
+    pub fn deck(&self) -> &Deck {
+        &self.deck
+    }
+
+    /// every move made so far.  saving it lets the whole game be replayed.
+    pub fn record(&self) -> &GameRecord {
+        &self.record
+    }
+
+    /// what `player` can see of the game, for a `Strategy` to make its
+    /// next decision with
+    pub fn context(&self, player: usize) -> StrategyContext<'_> {
+        let hand = match self.phase {
+            GamePhase::Pegging => self.pegging.hand(player),
+            _ => &self.hands[player],
+        };
+        StrategyContext {
+            player,
+            dealer: self.dealer,
+            partner: self.variant.partner_of(player),
+            variant: self.variant,
+            rules: self.rules,
+            scores: self.board.scores(),
+            hand,
+            discarded: &self.discards[player],
+            starter: self.starter,
+            pile: self.pegging.pile(),
+            history: self.pegging.history(),
+        }
+    }
+
+    /// Each player cuts a card from a freshly shuffled deck and the lowest
+    /// card deals.  If the lowest cards have the same rank, the game stays in
+    /// the `CutForDeal` phase and the players cut again.
+    ///
+    /// # Returns
+    ///
+    /// the card cut by each player, in seat order
+    pub fn cut_for_deal(&mut self) -> Result<Vec<Card>, CribbageError> {
+        self.check_phase(GamePhase::CutForDeal)?;
+        self.shuffle();
+        let cuts = (0..self.players())
+            .map(|_| self.cut_card())
+            .collect::<Result<Vec<Card>, CribbageError>>()?;
+        self.record
+            .moves
+            .push(GameMove::CutForDeal { cuts: cuts.clone() });
+
+        let lowest = cuts.iter().map(|c| c.rank).min().unwrap(); // there are at least two players
+        let low_cuts: Vec<usize> = (0..cuts.len())
+            .filter(|p| cuts[*p].rank == lowest)
+            .collect();
+        if low_cuts.len() == 1 {
+            self.start(low_cuts[0]);
+        }
+        Ok(cuts)
+    }
+
+    /// Starts the game with `dealer` dealing first instead of cutting for
+    /// deal, such as when the loser of the last game of a match deals first
+    pub fn choose_dealer(&mut self, dealer: usize) -> Result<(), CribbageError> {
+        self.check_phase(GamePhase::CutForDeal)?;
+        self.check_player(dealer)?;
+        self.record.moves.push(GameMove::FirstDealer { dealer });
+        self.start(dealer);
+        Ok(())
+    }
+
+    /// Shuffles and deals the cards for the `Variant` to each player,
+    /// starting with the pone.  in three player cribbage the next card goes
+    /// straight into the crib.
+    pub fn deal(&mut self) -> Result<(), CribbageError> {
+        self.check_phase(GamePhase::Deal)?;
+        self.shuffle();
+        self.crib.clear();
+        for discards in self.discards.iter_mut() {
+            discards.clear();
+        }
+        self.starter = None;
+        self.hands_shown = 0;
+        for hand in self.hands.iter_mut() {
+            hand.clear();
+        }
+
+        // the first card goes to the pone
+        let dealt = self.deck.deal(self.players(), self.variant.cards_dealt())?;
+        let mut player = self.pone();
+        for hand in dealt {
+            self.hands[player] = hand;
+            player = self.next_player(player);
+        }
+        for _ in 0..self.variant.cards_to_crib() {
+            self.crib.push(self.deck.draw()?);
+        }
+        self.record.moves.push(GameMove::Deal);
+        self.phase = GamePhase::Discard;
+        Ok(())
+    }
+
+    /// `player` puts cards in the dealer's crib: two each with two players
+    /// and one each with three.  once every player has discarded the game
+    /// moves on to cutting the starter.
+    pub fn discard(&mut self, player: usize, cards: &[Card]) -> Result<(), CribbageError> {
+        self.check_phase(GamePhase::Discard)?;
+        self.check_player(player)?;
+        if self.hands[player].len() != self.variant.cards_dealt() {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!("player {} has already discarded", player),
+            ));
+        }
+        let different = cards
+            .iter()
+            .enumerate()
+            .all(|(i, c)| !cards[..i].contains(c));
+        if cards.len() != self.variant.cards_discarded() || !different {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadHand,
+                format!(
+                    "a player must discard {} different cards to the crib",
+                    self.variant.cards_discarded()
+                ),
+            ));
+        }
+        if let Some(card) = cards.iter().find(|c| !self.hands[player].contains(c)) {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadCard,
+                format!("{} is not in the hand of player {}", card, player),
+            ));
+        }
+
+        self.hands[player].retain(|c| !cards.contains(c));
+        self.crib.extend_from_slice(cards);
+        self.discards[player] = cards.to_vec();
+        self.record.moves.push(GameMove::Discard {
+            player,
+            cards: cards.to_vec(),
+        });
+        if self.crib.len() == CRIB_SIZE {
+            self.phase = GamePhase::Starter;
+        }
+        Ok(())
+    }
+
+    /// Cuts the starter card.  if it is a Jack the dealer pegs for his
+    /// heels, which can end the game.
+    pub fn cut_starter(&mut self) -> Result<Card, CribbageError> {
+        self.check_phase(GamePhase::Starter)?;
+        let starter = self.cut_card()?;
+        self.starter = Some(starter);
+        self.record.moves.push(GameMove::Starter { card: starter });
+
+        self.pegging = PeggingRound::with_rules(self.hands.clone(), self.pone(), self.rules);
+        self.phase = GamePhase::Pegging;
+
+        let heels = his_heels_score(starter, &self.rules);
+        if heels.total_score > 0 {
+            self.award(self.dealer, heels.total_score);
+        }
+        Ok(starter)
+    }
+
+    /// `player` plays `card` during pegging.
+    ///
+    /// # Returns
+    ///
+    /// the `Score` pegged by playing the card, including the point for the
+    /// last card
+    pub fn play_card(&mut self, player: usize, card: Card) -> Result<Score, CribbageError> {
+        self.play_card_claiming(player, card, None)
+            .map(|(score, _)| score)
+    }
+
+    /// like `play_card`, but `player` only pegs what they `claim`.  when
+    /// playing muggins, any points they miss go to the next player.  a
+    /// `claim` of `None` claims every point.
+    pub fn play_card_claiming(
+        &mut self,
+        player: usize,
+        card: Card,
+        claim: Option<&Claim>,
+    ) -> Result<(Score, MugginsCheck), CribbageError> {
+        self.check_phase(GamePhase::Pegging)?;
+        self.check_player(player)?;
+        let score = self.pegging.play(player, card)?;
+        self.record.moves.push(GameMove::Play {
+            player,
+            card,
+            claim: claim.cloned(),
+            points: score.total_score,
+        });
+        let check = self.award_claim(player, &score, claim);
+        self.check_pegging_finished();
+        Ok((score, check))
+    }
+
+    /// `player` says "Go" because they have no card that keeps the count at
+    /// 31 or less.  It is an error to say "Go" when a card can be played.
+    ///
+    /// # Returns
+    ///
+    /// the player that pegged a point for the go, if any, and its `Score`
+    pub fn go(&mut self, player: usize) -> Result<Option<(usize, Score)>, CribbageError> {
+        self.check_phase(GamePhase::Pegging)?;
+        self.check_player(player)?;
+        let go = self.pegging.go(player)?;
+        self.record.moves.push(GameMove::Go {
+            player,
+            points: go.as_ref().map_or(0, |(_, score)| score.total_score),
+        });
+        if let Some((player, score)) = &go {
+            self.award(*player, score.total_score);
+        }
+        self.check_pegging_finished();
+        Ok(go)
+    }
+
+    /// Counts the next hand in the show, in seat order from the pone, so the
+    /// dealer counts last.
+    ///
+    /// # Returns
+    ///
+    /// the player whose hand was counted and its `Score`
+    pub fn show(&mut self) -> Result<(usize, Score), CribbageError> {
+        self.show_claiming(None)
+            .map(|(player, score, _)| (player, score))
+    }
+
+    /// like `show`, but the player only scores what they `claim` and the
+    /// points they miss go to the next player
+    pub fn show_claiming(
+        &mut self,
+        claim: Option<&Claim>,
+    ) -> Result<(usize, Score, MugginsCheck), CribbageError> {
+        self.check_phase(GamePhase::Show)?;
+        let player = (self.dealer + 1 + self.hands_shown) % self.players();
+        let score =
+            score_hand_with_rules(self.hands[player].clone(), self.starter, false, &self.rules);
+        self.hands_shown += 1;
+        self.record.moves.push(GameMove::Show {
+            player,
+            claim: claim.cloned(),
+            points: score.total_score,
+        });
+        if self.hands_shown == self.players() {
+            self.phase = GamePhase::Crib;
+        }
+        let check = self.award_claim(player, &score, claim);
+        Ok((player, score, check))
+    }
+
+    /// Counts the crib for the dealer and passes the deal to the pone
+    pub fn count_crib(&mut self) -> Result<Score, CribbageError> {
+        self.count_crib_claiming(None).map(|(score, _)| score)
+    }
+
+    /// like `count_crib`, but the dealer only scores what they `claim` and
+    /// the points they miss go to the pone
+    pub fn count_crib_claiming(
+        &mut self,
+        claim: Option<&Claim>,
+    ) -> Result<(Score, MugginsCheck), CribbageError> {
+        self.check_phase(GamePhase::Crib)?;
+        let score = score_hand_with_rules(self.crib.clone(), self.starter, true, &self.rules);
+        let dealer = self.dealer;
+        self.record.moves.push(GameMove::Crib {
+            player: dealer,
+            claim: claim.cloned(),
+            points: score.total_score,
+        });
+        self.dealer = self.pone();
+        self.phase = GamePhase::Deal;
+        let check = self.award_claim(dealer, &score, claim);
+        Ok((score, check))
+    }
+
+    /// Takes the next action in the game, asking `strategies[seat]` for
+    /// each player's decisions.  All of the players discard in the same step.
+    pub fn step(&mut self, strategies: &[&dyn Strategy]) -> Result<(), CribbageError> {
+        if strategies.len() != self.players() {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!(
+                    "there must be a strategy for each of the {} players",
+                    self.players()
+                ),
+            ));
+        }
+        match self.phase {
+            GamePhase::CutForDeal => {
+                self.cut_for_deal()?;
+            }
+            GamePhase::Deal => self.deal()?,
+            GamePhase::Discard => {
+                for (player, strategy) in strategies.iter().enumerate() {
+                    let cards = strategy.choose_discard(&self.context(player))?;
+                    self.discard(player, &cards)?;
+                }
+            }
+            GamePhase::Starter => {
+                self.cut_starter()?;
+            }
+            GamePhase::Pegging => {
+                let player = self.turn();
+                match strategies[player].choose_play(&self.context(player))? {
+                    Some(card) => {
+                        self.play_card(player, card)?;
+                    }
+                    None => {
+                        self.go(player)?;
+                    }
+                }
+            }
+            GamePhase::Show => {
+                self.show()?;
+            }
+            GamePhase::Crib => {
+                self.count_crib()?;
+            }
+            GamePhase::GameOver => {
+                return Err(CribbageError::new(
+                    CribbageErrorKind::BadAction,
+                    "the game is over".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Plays the rest of the game with `strategies`
+    ///
+    /// # Returns
+    ///
+    /// the side that won
+    pub fn play_to_end(&mut self, strategies: &[&dyn Strategy]) -> Result<usize, CribbageError> {
+        while self.phase != GamePhase::GameOver {
+            self.step(strategies)?;
+        }
+        Ok(self.winner().unwrap()) // the game is only over when somebody wins
+    }
+
+    /// pegs `points` for the side of `player` and ends the game if they
+    /// reach the game hole.  every action checks the phase first, so the
+    /// board can't refuse the points because the game is over.
+    fn award(&mut self, player: usize, points: u32) {
+        let team = self.variant.team_of(player);
+        if let Ok(true) = self.board.peg(team, points) {
+            self.phase = GamePhase::GameOver;
+            self.record.result = Some(GameResult {
+                winner: team,
+                scores: self.board.scores().to_vec(),
+            });
+        }
+    }
+
+    /// gives `player` the points they claimed and the next player the
+    /// points they missed.  nothing is missed if the game is already won.
+    fn award_claim(&mut self, player: usize, score: &Score, claim: Option<&Claim>) -> MugginsCheck {
+        let check = match claim {
+            Some(claim) => check_claim(score, claim),
+            None => check_claim(score, &Claim::total(score.total_score)),
+        };
+        self.award(player, check.credited);
+        if !self.board.is_over() && check.missed_points > 0 {
+            self.award(self.next_player(player), check.missed_points);
+        }
+        check
+    }
+
+    fn check_pegging_finished(&mut self) {
+        if self.phase == GamePhase::Pegging && self.pegging.is_finished() {
+            self.phase = GamePhase::Show;
+        }
+    }
+
+    /// moves on to the first deal.  in the five card game the pone pegs
+    /// their 3 points for the dealer's first crib.
+    fn start(&mut self, dealer: usize) {
+        self.dealer = dealer;
+        self.phase = GamePhase::Deal;
+        let points = self.variant.pone_start_points();
+        if points > 0 {
+            self.award(self.pone(), points);
+        }
+    }
+
+    fn next_player(&self, player: usize) -> usize {
+        (player + 1) % self.players()
+    }
+
+    fn shuffle(&mut self) {
+        self.deck.shuffle(&mut self.rng);
+    }
+
+    /// cuts the cards left in the deck at a random place and turns up the
+    /// top card of the bottom packet
+    fn cut_card(&mut self) -> Result<Card, CribbageError> {
+        let at = self.rng.gen_range(0..self.deck.remaining().len());
+        self.deck.starter(at)
+    }
+
+    fn check_phase(&self, phase: GamePhase) -> Result<(), CribbageError> {
+        if self.phase != phase {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!(
+                    "expected the game to be in {:?} but it is in {:?}",
+                    phase, self.phase
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_player(&self, player: usize) -> Result<(), CribbageError> {
+        if player >= self.players() {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!("there is no player {}", player),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{Rank::*, Suit as Of};
+    use crate::cribbage_errors::CribbageErrorKind;
+    use crate::new_card as c;
+    use crate::rules::{GAME_LENGTH, SHORT_GAME_LENGTH};
+    use crate::strategy::{DefaultStrategy, ExpectedValueStrategy};
+
+    /// plays a whole game using the default strategy for both players
+    fn play_game(seed: u64) -> Game {
+        let mut game = Game::from_seed(seed);
+        game.play_to_end(&[&DefaultStrategy, &DefaultStrategy])
+            .unwrap();
+        game
+    }
+
+    /// puts the game into the pegging phase with the given hands
+    fn pegging_game(dealer_cards: Hand, pone_cards: Hand) -> Game {
+        let mut game = Game::from_seed(1);
+        game.dealer = 0;
+        game.hands = vec![dealer_cards, pone_cards];
+        game.starter = Some(c!(Ace, Of::Clubs));
+        game.pegging = PeggingRound::new(game.hands.clone(), 1);
+        game.phase = GamePhase::Pegging;
+        game
+    }
+
+    #[test]
+    fn full_game_has_a_winner() {
+        for seed in 0..10 {
+            let game = play_game(seed);
+            let winner = game.winner().unwrap();
+            assert_eq!(game.scores()[winner], GAME_LENGTH);
+            assert!(game.scores()[1 - winner] < GAME_LENGTH);
+        }
+
+        let mut game = play_game(0);
+        let err = game
+            .step(&[&DefaultStrategy, &DefaultStrategy])
+            .unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::BadAction);
+        assert_eq!(err.message, "the game is over");
+    }
+
+    #[test]
+    fn short_game_ends_at_61() {
+        let mut game = Game::with_rules(3, RuleSet::short_game()).unwrap();
+        let winner = game
+            .play_to_end(&[&DefaultStrategy, &DefaultStrategy])
+            .unwrap();
+        assert_eq!(game.scores()[winner], SHORT_GAME_LENGTH);
+        assert_eq!(game.rules(), &RuleSet::short_game());
+
+        let rules = RuleSet {
+            game_length: 0,
+            ..RuleSet::default()
+        };
+        assert!(Game::with_rules(3, rules).is_err());
+    }
+
+    #[test]
+    fn five_card_game() {
+        let variant = Variant::FiveCard;
+        let mut game = Game::with_variant(4, variant, variant.default_rules()).unwrap();
+        let strategies: [&dyn Strategy; 2] = [&DefaultStrategy, &ExpectedValueStrategy];
+        while game.phase() != GamePhase::Discard {
+            game.step(&strategies).unwrap();
+        }
+        assert_eq!(game.scores()[game.pone()], 3);
+        assert_eq!(game.scores()[game.dealer()], 0);
+        assert!((0..2).all(|p| game.hand(p).len() == 5));
+        assert!(game.crib().is_empty());
+        game.step(&strategies).unwrap();
+        assert!((0..2).all(|p| game.hand(p).len() == 3));
+        assert_eq!(game.crib().len(), 4);
+
+        let winner = game.play_to_end(&strategies).unwrap();
+        assert_eq!(game.scores()[winner], SHORT_GAME_LENGTH);
+    }
+
+    #[test]
+    fn three_player_game() {
+        let mut game = Game::with_variant(2, Variant::ThreePlayer, RuleSet::default()).unwrap();
+        let strategies: [&dyn Strategy; 3] = [&DefaultStrategy, &DefaultStrategy, &DefaultStrategy];
+        while game.phase() != GamePhase::Discard {
+            game.step(&strategies).unwrap();
+        }
+        assert!((0..3).all(|p| game.hand(p).len() == 5));
+        assert_eq!(game.crib().len(), 1);
+        game.step(&strategies).unwrap();
+        assert!((0..3).all(|p| game.hand(p).len() == 4));
+        assert_eq!(game.crib().len(), 4);
+        assert_eq!(game.phase(), GamePhase::Starter);
+
+        // everybody plays in turn from the player after the dealer
+        game.step(&strategies).unwrap();
+        let first = game.turn();
+        assert_eq!(first, (game.dealer() + 1) % 3);
+        game.step(&strategies).unwrap();
+        if game.turn() != first {
+            assert_eq!(game.turn(), (first + 1) % 3);
+        }
+
+        let winner = game.play_to_end(&strategies).unwrap();
+        assert_eq!(game.scores()[winner], GAME_LENGTH);
+        assert_eq!(game.scores().len(), 3);
+        assert_eq!(
+            game.step(&strategies[0..2]).unwrap_err().error_kind,
+            CribbageErrorKind::BadAction
+        );
+    }
+
+    #[test]
+    fn four_player_partnership_game() {
+        let mut game = Game::with_variant(4, Variant::FourPlayer, RuleSet::default()).unwrap();
+        let strategies: [&dyn Strategy; 4] = [&DefaultStrategy; 4];
+        while game.phase() != GamePhase::Discard {
+            game.step(&strategies).unwrap();
+        }
+        assert!((0..4).all(|p| game.hand(p).len() == 5));
+        assert!(game.crib().is_empty());
+        game.step(&strategies).unwrap();
+        assert_eq!(game.crib().len(), 4);
+        game.step(&strategies).unwrap();
+
+        // pegging goes round all four seats, and the partners see each
+        // other's cards
+        let first = game.turn();
+        assert_eq!(first, (game.dealer() + 1) % 4);
+        let played = game.unplayed_cards(first).to_vec();
+        game.step(&strategies).unwrap();
+        let card = played
+            .iter()
+            .find(|c| !game.unplayed_cards(first).contains(c))
+            .unwrap();
+        assert_eq!(game.context((first + 2) % 4).partner, Some(first));
+        assert_eq!(game.context((first + 2) % 4).partner_played(), vec![*card]);
+        assert!(game.context((first + 1) % 4).partner_played().is_empty());
+        assert_eq!(game.turn(), (first + 1) % 4);
+
+        let winner = game.play_to_end(&strategies).unwrap();
+        assert_eq!(game.scores().len(), 2);
+        assert_eq!(game.scores()[winner], GAME_LENGTH);
+        assert!(game.scores()[1 - winner] < GAME_LENGTH);
+    }
+
+    #[test]
+    fn partners_score_together() {
+        let mut game = Game::with_variant(4, Variant::FourPlayer, RuleSet::default()).unwrap();
+        game.dealer = 3;
+        game.hands = vec![
+            vec![c!(Five, Of::Hearts)],
+            vec![c!(King, Of::Clubs)],
+            vec![c!(Five, Of::Spades)],
+            vec![c!(Ten, Of::Diamonds)],
+        ];
+        game.pegging = PeggingRound::new(game.hands.clone(), 0);
+        game.phase = GamePhase::Pegging;
+        game.play_card(0, c!(Five, Of::Hearts)).unwrap();
+        game.play_card(1, c!(King, Of::Clubs)).unwrap(); // fifteen 2
+        game.play_card(2, c!(Five, Of::Spades)).unwrap();
+        game.play_card(3, c!(Ten, Of::Diamonds)).unwrap(); // 30 and the last card
+        assert_eq!(game.scores(), &[0, 3]);
+    }
+
+    #[test]
+    fn context_for_strategies() {
+        let mut game = Game::from_seed(5);
+        let strategies: [&dyn Strategy; 2] = [&DefaultStrategy, &DefaultStrategy];
+        while game.phase() != GamePhase::Pegging {
+            game.step(&strategies).unwrap();
+        }
+        let player = game.turn();
+        let context = game.context(player);
+        assert_eq!(context.player, player);
+        assert_eq!(context.hand.len(), 4);
+        assert_eq!(context.discarded.len(), 2);
+        assert_eq!(context.starter, game.starter());
+        assert_eq!(context.known_cards().len(), 7);
+
+        game.step(&strategies).unwrap();
+        assert_eq!(game.context(player).history.len(), 1);
+        assert_eq!(
+            game.step(&strategies[0..1]).unwrap_err().error_kind,
+            CribbageErrorKind::BadAction
+        );
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        assert_eq!(play_game(42).scores(), play_game(42).scores());
+    }
+
+    #[test]
+    fn deal_and_discard() {
+        let mut game = Game::from_seed(7);
+        while game.phase() == GamePhase::CutForDeal {
+            game.cut_for_deal().unwrap();
+        }
+        game.deal().unwrap();
+        assert_eq!(game.hand(0).len(), 6);
+        assert_eq!(game.hand(1).len(), 6);
+
+        let discard = game.hand(0)[0..2].to_vec();
+        game.discard(0, &discard).unwrap();
+        assert_eq!(game.hand(0).len(), 4);
+        assert_eq!(game.crib().len(), 2);
+
+        let err = game.discard(0, &discard).unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::BadAction);
+
+        let other = game.hand(1)[0];
+        let err = game.discard(1, &[other, other]).unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::BadHand);
+
+        let err = game.discard(1, &discard).unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::BadCard);
+    }
+
+    #[test]
+    fn actions_out_of_phase_are_rejected() {
+        let mut game = Game::from_seed(3);
+        assert_eq!(
+            game.deal().unwrap_err().error_kind,
+            CribbageErrorKind::BadAction
+        );
+        assert_eq!(
+            game.cut_starter().unwrap_err().error_kind,
+            CribbageErrorKind::BadAction
+        );
+        assert_eq!(
+            game.show().unwrap_err().error_kind,
+            CribbageErrorKind::BadAction
+        );
+        assert_eq!(
+            game.play_card(0, c!(Five, Of::Hearts))
+                .unwrap_err()
+                .error_kind,
+            CribbageErrorKind::BadAction
+        );
+    }
+
+    #[test]
+    fn play_out_of_turn_is_rejected() {
+        let mut game = pegging_game(
+            vec![c!(Five, Of::Hearts), c!(Six, Of::Hearts)],
+            vec![c!(Ten, Of::Clubs), c!(Jack, Of::Clubs)],
+        );
+        let err = game.play_card(0, c!(Five, Of::Hearts)).unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::BadAction);
+        let err = game.play_card(1, c!(Five, Of::Hearts)).unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::BadCard);
+        let err = game.go(1).unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::BadAction);
+    }
+
+    #[test]
+    fn go_and_last_card() {
+        let mut game = pegging_game(
+            vec![c!(King, Of::Hearts), c!(Five, Of::Hearts)],
+            vec![c!(Ten, Of::Clubs), c!(Jack, Of::Clubs)],
+        );
+        game.play_card(1, c!(Ten, Of::Clubs)).unwrap();
+        game.play_card(0, c!(King, Of::Hearts)).unwrap();
+        assert_eq!(game.count(), 20);
+        game.play_card(1, c!(Jack, Of::Clubs)).unwrap();
+        assert_eq!(game.count(), 30);
+        // the dealer can't play the five, and the pone has no cards left
+        game.go(0).unwrap();
+        assert_eq!(game.scores(), &[0, 1]);
+        assert_eq!(game.count(), 0);
+        assert_eq!(game.turn(), 0);
+        game.play_card(0, c!(Five, Of::Hearts)).unwrap();
+        assert_eq!(game.scores(), &[1, 1]); // last card
+        assert_eq!(game.phase(), GamePhase::Show);
+    }
+
+    #[test]
+    fn thirty_one_resets_the_count() {
+        let mut game = pegging_game(
+            vec![c!(Ten, Of::Hearts), c!(Ace, Of::Hearts)],
+            vec![c!(Ten, Of::Clubs), c!(Jack, Of::Clubs)],
+        );
+        game.play_card(1, c!(Ten, Of::Clubs)).unwrap();
+        game.play_card(0, c!(Ten, Of::Hearts)).unwrap(); // pair for 2
+        game.play_card(1, c!(Jack, Of::Clubs)).unwrap();
+        game.play_card(0, c!(Ace, Of::Hearts)).unwrap(); // 31 for 2
+        assert_eq!(game.scores(), &[4, 0]);
+        assert_eq!(game.phase(), GamePhase::Show);
+    }
+
+    #[test]
+    fn show_counts_pone_first_then_crib() {
+        let mut game = pegging_game(
+            vec![
+                c!(Five, Of::Hearts),
+                c!(Five, Of::Clubs),
+                c!(Five, Of::Spades),
+                c!(Jack, Of::Diamonds),
+            ],
+            vec![
+                c!(Ace, Of::Spades),
+                c!(Four, Of::Diamonds),
+                c!(Six, Of::Spades),
+                c!(Jack, Of::Hearts),
+            ],
+        );
+        game.starter = Some(c!(Five, Of::Diamonds));
+        game.crib = vec![
+            c!(Ace, Of::Clubs),
+            c!(Two, Of::Clubs),
+            c!(Three, Of::Clubs),
+            c!(Four, Of::Clubs),
+        ];
+        game.phase = GamePhase::Show;
+
+        let (player, score) = game.show().unwrap();
+        assert_eq!((player, score.total_score), (1, 9));
+        let (player, score) = game.show().unwrap();
+        assert_eq!((player, score.total_score), (0, 29));
+        assert_eq!(game.phase(), GamePhase::Crib);
+        assert_eq!(game.count_crib().unwrap().total_score, 7);
+        assert_eq!(game.scores(), &[36, 9]);
+        assert_eq!(game.dealer(), 1);
+        assert_eq!(game.phase(), GamePhase::Deal);
+    }
+
+    #[test]
+    fn muggins_gives_missed_points_to_the_opponent() {
+        let mut game = pegging_game(
+            vec![
+                c!(Five, Of::Hearts),
+                c!(Five, Of::Clubs),
+                c!(Five, Of::Spades),
+                c!(Jack, Of::Diamonds),
+            ],
+            vec![c!(Ace, Of::Spades)],
+        );
+        game.starter = Some(c!(Five, Of::Diamonds));
+        game.phase = GamePhase::Show;
+        game.hands_shown = 1;
+
+        // the dealer has 29 and only claims 28
+        let (player, score, check) = game.show_claiming(Some(&Claim::total(28))).unwrap();
+        assert_eq!((player, score.total_score), (0, 29));
+        assert_eq!(check.missed_points, 1);
+        assert_eq!(game.scores(), &[28, 1]);
+
+        game.crib = vec![
+            c!(Ace, Of::Clubs),
+            c!(Two, Of::Clubs),
+            c!(Three, Of::Clubs),
+            c!(Four, Of::Clubs),
+        ];
+        let (score, check) = game.count_crib_claiming(Some(&Claim::total(0))).unwrap();
+        assert_eq!(score.total_score, 7);
+        assert_eq!(check.missed_points, 7);
+        assert_eq!(game.scores(), &[28, 8]);
+    }
+
+    #[test]
+    fn muggins_while_pegging() {
+        let mut game = pegging_game(vec![c!(Five, Of::Hearts)], vec![c!(Ten, Of::Clubs)]);
+        game.play_card(1, c!(Ten, Of::Clubs)).unwrap();
+        // fifteen 2 and 1 for the last card, but only 2 claimed
+        let (score, check) = game
+            .play_card_claiming(0, c!(Five, Of::Hearts), Some(&Claim::total(2)))
+            .unwrap();
+        assert_eq!(score.total_score, 3);
+        assert_eq!(check.credited, 2);
+        assert_eq!(game.scores(), &[2, 1]);
+    }
+
+    #[test]
+    fn pone_can_win_before_dealer_counts() {
+        let mut game = pegging_game(
+            vec![c!(Two, Of::Hearts)],
+            vec![c!(Five, Of::Hearts), c!(Five, Of::Clubs)],
+        );
+        game.starter = Some(c!(Ten, Of::Diamonds));
+        game.board = Board::from_scores(&[120, 115], &game.rules).unwrap();
+        game.phase = GamePhase::Show;
+        game.show().unwrap();
+        assert_eq!(game.winner(), Some(1));
+        assert_eq!(game.phase(), GamePhase::GameOver);
+        assert_eq!(
+            game.show().unwrap_err().error_kind,
+            CribbageErrorKind::BadAction
+        );
+    }
+}
@@ -0,0 +1,347 @@
+//! `hand_statistics` counts how often each score comes up, either for a
+//! kept hand of four cards over every starter that could be cut, or for
+//! every hand and starter in the deck.  The counts are exact, so they answer
+//! questions like "how rare is a 29?" or "how often does this keep score 12
+//! or more?".
+//!
+//! Counting every hand takes a while, so the counts are shipped in
+//! `tables/hand_statistics.txt`, which the `generate_hand_statistics` tool
+//! writes.
+
+use crate::{
+    card_set::CardSet,
+    cards::{Card, Suit, RANKS},
+    cribbage_errors::{CribbageError, CribbageErrorKind},
+    discard_analysis::{for_each_crib, unseen_cards},
+    scoring::score_points,
+    table_file::TableLines,
+};
+use lazy_static::lazy_static;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// the percentiles that `ScoreStatistics` reports
+pub const REPORTED_PERCENTILES: [u32; 8] = [5, 10, 25, 50, 75, 90, 95, 99];
+
+/// the version of the table file format
+pub const HAND_STATISTICS_VERSION: u32 = 1;
+
+lazy_static! {
+    static ref SHIPPED: AllHandsStatistics = include_str!("../tables/hand_statistics.txt")
+        .parse()
+        .expect("the shipped hand statistics should parse");
+}
+
+/// The points scored at a percentile
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Percentile {
+    pub percent: u32,
+    /// the fewest points that at least `percent` percent of the hands score
+    /// no more than
+    pub points: u32,
+}
+
+/// The distribution of the points a set of hands score
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScoreStatistics {
+    /// how many hands score each number of points, indexed by the points
+    pub histogram: Vec<u64>,
+    /// the number of hands counted
+    pub hands: u64,
+    pub mean: f64,
+    pub variance: f64,
+    pub min: u32,
+    pub max: u32,
+    /// the points at each of `REPORTED_PERCENTILES`
+    pub percentiles: Vec<Percentile>,
+}
+
+impl ScoreStatistics {
+    /// the statistics of `histogram`, which counts the hands that score
+    /// each number of points
+    pub fn from_histogram(histogram: Vec<u64>) -> ScoreStatistics {
+        let hands: u64 = histogram.iter().sum();
+        let moment = |power: i32| {
+            histogram
+                .iter()
+                .enumerate()
+                .map(|(points, count)| (points as f64).powi(power) * *count as f64)
+                .sum::<f64>()
+                / hands as f64
+        };
+        let mean = moment(1);
+        let scored = || {
+            histogram
+                .iter()
+                .enumerate()
+                .filter(|(_, count)| **count > 0)
+                .map(|(points, _)| points as u32)
+        };
+        let mut statistics = ScoreStatistics {
+            hands,
+            mean,
+            variance: moment(2) - mean * mean,
+            min: scored().next().unwrap_or(0),
+            max: scored().next_back().unwrap_or(0),
+            percentiles: Vec::new(),
+            histogram,
+        };
+        statistics.percentiles = REPORTED_PERCENTILES
+            .iter()
+            .map(|percent| Percentile {
+                percent: *percent,
+                points: statistics.percentile(*percent as f64),
+            })
+            .collect();
+        statistics
+    }
+
+    /// the fewest points that at least `percent` percent of the hands score
+    /// no more than
+    pub fn percentile(&self, percent: f64) -> u32 {
+        let needed = self.hands as f64 * percent / 100.0;
+        let mut counted = 0;
+        for (points, count) in self.histogram.iter().enumerate() {
+            counted += count;
+            if counted > 0 && counted as f64 >= needed {
+                return points as u32;
+            }
+        }
+        self.max
+    }
+
+    /// the chance of scoring exactly `points`
+    pub fn probability(&self, points: u32) -> f64 {
+        self.histogram.get(points as usize).copied().unwrap_or(0) as f64 / self.hands as f64
+    }
+
+    /// the chance of scoring `points` or more
+    pub fn at_least(&self, points: u32) -> f64 {
+        self.histogram.iter().skip(points as usize).sum::<u64>() as f64 / self.hands as f64
+    }
+
+    pub fn standard_deviation(&self) -> f64 {
+        self.variance.sqrt()
+    }
+}
+
+/// Counts the points `keep` scores with each of the 48 starters that could
+/// be cut.  `keep` has to be four different cards.
+pub fn keep_statistics(keep: &[Card], is_crib: bool) -> Result<ScoreStatistics, CribbageError> {
+    let kept = CardSet::from(keep);
+    if keep.len() != 4 || kept.len() != 4 {
+        return Err(CribbageError::new(
+            CribbageErrorKind::BadHand,
+            format!(
+                "a kept hand should be 4 different cards.  you passed in {:?}",
+                keep
+            ),
+        ));
+    }
+    let mut histogram = Vec::new();
+    for starter in unseen_cards(keep) {
+        add(
+            &mut histogram,
+            score_points(kept, Some(starter), is_crib),
+            1,
+        );
+    }
+    Ok(ScoreStatistics::from_histogram(histogram))
+}
+
+/// The statistics of each of the 12,994,800 ways to hold four cards and cut
+/// a starter, scored as a hand and as a crib
+#[derive(Clone, Debug, PartialEq)]
+pub struct AllHandsStatistics {
+    pub hand: ScoreStatistics,
+    pub crib: ScoreStatistics,
+}
+
+impl AllHandsStatistics {
+    /// the statistics in `tables/hand_statistics.txt`
+    pub fn shipped() -> &'static AllHandsStatistics {
+        &SHIPPED
+    }
+
+    /// Scores every hand with every starter, on all cores
+    pub fn count() -> AllHandsStatistics {
+        AllHandsStatistics {
+            hand: count_all_hands(false),
+            crib: count_all_hands(true),
+        }
+    }
+
+    pub fn get(&self, is_crib: bool) -> &ScoreStatistics {
+        if is_crib {
+            &self.crib
+        } else {
+            &self.hand
+        }
+    }
+}
+
+/// the shipped statistics of every hand with every starter
+pub fn all_hands_statistics(is_crib: bool) -> &'static ScoreStatistics {
+    AllHandsStatistics::shipped().get(is_crib)
+}
+
+/// every suit scores the same, so only starters of one suit are dealt, and
+/// each hand counts for all four
+fn count_all_hands(is_crib: bool) -> ScoreStatistics {
+    let histograms: Vec<Vec<u64>> = RANKS
+        .par_iter()
+        .map(|rank| {
+            let starter = Card::new(*rank, Suit::Hearts);
+            let mut histogram = Vec::new();
+            for_each_crib(&unseen_cards(&[starter]), 4, CardSet::new(), &mut |hand| {
+                add(
+                    &mut histogram,
+                    score_points(hand, Some(starter), is_crib),
+                    4,
+                )
+            });
+            histogram
+        })
+        .collect();
+
+    let mut histogram = Vec::new();
+    for counts in histograms {
+        for (points, count) in counts.iter().enumerate() {
+            add(&mut histogram, points as u32, *count);
+        }
+    }
+    ScoreStatistics::from_histogram(histogram)
+}
+
+/// The table file: the version, then the histogram of the hands and of the
+/// cribs, each as a name and a row of counts from 0 points up
+impl fmt::Display for AllHandsStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "# how many of the 12,994,800 hands and starters score each number of points"
+        )?;
+        writeln!(f, "version {}", HAND_STATISTICS_VERSION)?;
+        for is_crib in [false, true] {
+            writeln!(f, "{}", histogram_name(is_crib))?;
+            let counts: Vec<String> = self
+                .get(is_crib)
+                .histogram
+                .iter()
+                .map(|count| count.to_string())
+                .collect();
+            writeln!(f, "{}", counts.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for AllHandsStatistics {
+    type Err = CribbageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = TableLines::new(s, "the hand statistics");
+        lines.version(HAND_STATISTICS_VERSION)?;
+        let mut histogram = |is_crib: bool| {
+            let name = histogram_name(is_crib);
+            lines.name(name)?;
+            lines.row(name, None).map(ScoreStatistics::from_histogram)
+        };
+        let hand = histogram(false)?;
+        let crib = histogram(true)?;
+        lines.finish()?;
+        Ok(AllHandsStatistics { hand, crib })
+    }
+}
+
+fn histogram_name(is_crib: bool) -> &'static str {
+    if is_crib {
+        "crib"
+    } else {
+        "hand"
+    }
+}
+
+/// counts `count` more hands of `points`
+fn add(histogram: &mut Vec<u64>, points: u32, count: u64) {
+    let points = points as usize;
+    if histogram.len() <= points {
+        histogram.resize(points + 1, 0);
+    }
+    histogram[points] += count;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{Rank::*, Suit as Of};
+    use crate::new_card as c;
+
+    #[test]
+    fn shipped_statistics_match_a_fresh_count() {
+        assert_eq!(&AllHandsStatistics::count(), AllHandsStatistics::shipped());
+    }
+
+    #[test]
+    fn round_trips_the_text_form() {
+        let shipped = AllHandsStatistics::shipped();
+        let text = shipped.to_string();
+        assert_eq!(&text.parse::<AllHandsStatistics>().unwrap(), shipped);
+
+        let err = text
+            .replace("version 1", "version 2")
+            .parse::<AllHandsStatistics>()
+            .unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::ParseError);
+        assert!(text
+            .replace("crib", "cribs")
+            .parse::<AllHandsStatistics>()
+            .is_err());
+    }
+
+    #[test]
+    fn counts_every_hand() {
+        let hands = all_hands_statistics(false);
+        assert_eq!(hands.hands, 12_994_800);
+        assert_eq!(hands.histogram[28], 76);
+        assert_eq!(hands.histogram[29], 4);
+        assert_eq!(hands.histogram.len(), 30);
+        assert_eq!((hands.min, hands.max), (0, 29));
+        assert!(hands.mean > 4.7 && hands.mean < 5.0);
+        assert_eq!(hands.percentile(50.0), 4);
+        assert!((hands.probability(29) - 4.0 / 12_994_800.0).abs() < 1e-15);
+
+        // a crib only scores a flush of five, so it never does better
+        let cribs = all_hands_statistics(true);
+        assert_eq!(cribs.hands, hands.hands);
+        assert!(cribs.mean < hands.mean);
+        assert_eq!(cribs.histogram[29], 4);
+    }
+
+    #[test]
+    fn counts_the_starters_for_a_keep() {
+        let keep = [
+            c!(Five, Of::Hearts),
+            c!(Five, Of::Clubs),
+            c!(Five, Of::Spades),
+            c!(Jack, Of::Diamonds),
+        ];
+        let statistics = keep_statistics(&keep, false).unwrap();
+        assert_eq!(statistics.hands, 48);
+        assert_eq!(statistics.max, 29);
+        assert_eq!(statistics.histogram[29], 1);
+        assert_eq!(statistics.min, 14);
+        assert_eq!(statistics.at_least(0), 1.0);
+        assert_eq!(statistics.at_least(30), 0.0);
+        assert!(statistics.at_least(21) > 0.0);
+        assert_eq!(statistics.percentiles.len(), REPORTED_PERCENTILES.len());
+        for pair in statistics.percentiles.windows(2) {
+            assert!(pair[0].points <= pair[1].points);
+        }
+        assert!(statistics.variance > 0.0);
+
+        assert!(keep_statistics(&keep[..3], false).is_err());
+        assert!(keep_statistics(&[keep[0], keep[0], keep[1], keep[2]], false).is_err());
+    }
+}
@@ -1,7 +1,25 @@
 #[macro_use]
 pub mod cards;
+pub mod board;
+pub mod card_set;
 mod combinator;
 pub mod counting;
+pub mod crib_tables;
+pub mod discard_analysis;
+pub mod game;
+pub mod hand_statistics;
+pub mod match_play;
+pub mod muggins;
+pub mod pegging;
+pub mod pegging_search;
+pub mod record;
+pub mod rules;
+mod score_tables;
 pub mod scoring;
 pub mod select_cards;
+pub mod simulation;
+pub mod strategy;
+mod table_file;
+pub mod variant;
+pub mod win_probability;
 pub mod cribbage_errors;
@@ -0,0 +1,302 @@
+//! `match_play` plays a tournament match: a series of games between two
+//! sides where the first to win a majority of `best_of` games wins the
+//! match.  A skunk counts as two games.
+//!
+//! Matches also keep the game points used for the standings of American
+//! Cribbage Congress tournaments: 2 for a win and 3 for a skunk.  The ACC
+//! doesn't recognize double skunks, so they count as skunks.
+
+use crate::{
+    board::Loss,
+    cribbage_errors::{CribbageError, CribbageErrorKind},
+    game::Game,
+    rules::RuleSet,
+    strategy::Strategy,
+    variant::Variant,
+};
+use serde::{Deserialize, Serialize};
+
+/// the game points for a win
+pub const WIN_GAME_POINTS: u32 = 2;
+
+/// the game points for a win by a skunk or a double skunk
+pub const SKUNK_GAME_POINTS: u32 = 3;
+
+/// the longest match that can be played
+pub const MAX_BEST_OF: u32 = 99;
+
+/// How the first dealer of each game is chosen
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FirstDealer {
+    /// the first game is cut for and the loser of each game deals first in
+    /// the next, which is the tournament rule
+    LoserDeals,
+    /// every game is cut for
+    Cut,
+}
+
+/// How one game of a match ended
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchGame {
+    pub winner: usize,
+    pub loss: Loss,
+    pub scores: Vec<u32>,
+    /// the games this counts as towards the match: 2 for a skunk, else 1
+    pub match_games: u32,
+    pub game_points: u32,
+}
+
+/// A match between two sides, which are the sides of each `Game`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Match {
+    pub variant: Variant,
+    pub rules: RuleSet,
+    /// the most games the match can take
+    pub best_of: u32,
+    pub first_dealer: FirstDealer,
+    /// the seed of the first game.  each game after it adds one.
+    pub seed: u64,
+    games: Vec<MatchGame>,
+    /// the games each side has won, with a skunk counting as two
+    wins: [u32; 2],
+    game_points: [u32; 2],
+}
+
+impl Match {
+    /// Returns a new match of `best_of` games, which has to be odd and no
+    /// more than `MAX_BEST_OF`.  The variant has to have two sides.
+    pub fn new(
+        best_of: u32,
+        variant: Variant,
+        rules: RuleSet,
+        first_dealer: FirstDealer,
+        seed: u64,
+    ) -> Result<Match, CribbageError> {
+        if best_of % 2 != 1 {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!(
+                    "a match must be best of an odd number of games, not {}",
+                    best_of
+                ),
+            ));
+        }
+        if best_of > MAX_BEST_OF {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!(
+                    "a match can be best of at most {} games, not {}",
+                    MAX_BEST_OF, best_of
+                ),
+            ));
+        }
+        if variant.teams() != 2 {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!(
+                    "a match is between two sides, but {:?} has {}",
+                    variant,
+                    variant.teams()
+                ),
+            ));
+        }
+        rules.validate()?;
+        Ok(Match {
+            variant,
+            rules,
+            best_of,
+            first_dealer,
+            seed,
+            games: Vec::new(),
+            wins: [0, 0],
+            game_points: [0, 0],
+        })
+    }
+
+    /// the games played so far
+    pub fn games(&self) -> &[MatchGame] {
+        &self.games
+    }
+
+    /// the games each side has won, with a skunk counting as two
+    pub fn wins(&self) -> [u32; 2] {
+        self.wins
+    }
+
+    /// the tournament game points of each side
+    pub fn game_points(&self) -> [u32; 2] {
+        self.game_points
+    }
+
+    /// the number of games a side has to win to win the match
+    pub fn games_to_win(&self) -> u32 {
+        self.best_of / 2 + 1
+    }
+
+    /// the side that won the match
+    pub fn winner(&self) -> Option<usize> {
+        (0..2).find(|side| self.wins[*side] >= self.games_to_win())
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.winner().is_some()
+    }
+
+    /// Returns the next game to play.  With `FirstDealer::LoserDeals` every
+    /// game after the first already has its dealer, so it starts at the
+    /// deal.
+    pub fn next_game(&self) -> Result<Game, CribbageError> {
+        self.check_not_over()?;
+        let seed = self.seed.wrapping_add(self.games.len() as u64);
+        let mut game = Game::with_variant(seed, self.variant, self.rules)?;
+        if let (FirstDealer::LoserDeals, Some(last)) = (self.first_dealer, self.games.last()) {
+            // seat 0 and 1 are on side 0 and 1 in every variant
+            game.choose_dealer(1 - last.winner)?;
+        }
+        Ok(game)
+    }
+
+    /// Adds the result of `game`, which has to be over, to the match
+    ///
+    /// # Returns
+    ///
+    /// the game as it counts towards the match
+    pub fn add_game(&mut self, game: &Game) -> Result<&MatchGame, CribbageError> {
+        self.check_not_over()?;
+        let board = game.board();
+        let winner = board.winner().ok_or_else(|| {
+            CribbageError::new(
+                CribbageErrorKind::BadAction,
+                "only a finished game can be added to a match".to_string(),
+            )
+        })?;
+        let loss = board.loss(1 - winner).unwrap(); // the other side lost
+        let (match_games, game_points) = match loss {
+            Loss::Plain => (1, WIN_GAME_POINTS),
+            Loss::Skunk | Loss::DoubleSkunk => (2, SKUNK_GAME_POINTS),
+        };
+        self.wins[winner] += match_games;
+        self.game_points[winner] += game_points;
+        self.games.push(MatchGame {
+            winner,
+            loss,
+            scores: board.scores().to_vec(),
+            match_games,
+            game_points,
+        });
+        Ok(self.games.last().unwrap())
+    }
+
+    /// Plays the rest of the match with `strategies`, one per seat
+    ///
+    /// # Returns
+    ///
+    /// the side that won
+    pub fn play_to_end(&mut self, strategies: &[&dyn Strategy]) -> Result<usize, CribbageError> {
+        while !self.is_over() {
+            let mut game = self.next_game()?;
+            game.play_to_end(strategies)?;
+            self.add_game(&game)?;
+        }
+        Ok(self.winner().unwrap()) // the match is over
+    }
+
+    fn check_not_over(&self) -> Result<(), CribbageError> {
+        match self.winner() {
+            Some(winner) => Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!("the match is over.  side {} has won", winner),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GamePhase;
+    use crate::record::GameMove;
+    use crate::strategy::DefaultStrategy;
+
+    fn new_match(best_of: u32, first_dealer: FirstDealer) -> Match {
+        Match::new(
+            best_of,
+            Variant::TwoPlayer,
+            RuleSet::default(),
+            first_dealer,
+            17,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn plays_until_a_side_has_a_majority() {
+        let mut series = new_match(7, FirstDealer::LoserDeals);
+        let winner = series
+            .play_to_end(&[&DefaultStrategy, &DefaultStrategy])
+            .unwrap();
+        assert_eq!(series.winner(), Some(winner));
+        assert!(series.wins()[winner] >= 4);
+        assert!(series.wins()[1 - winner] < 4);
+        assert!(series.games().len() <= 7);
+
+        let points: u32 = series.games().iter().map(|g| g.game_points).sum();
+        assert_eq!(points, series.game_points()[0] + series.game_points()[1]);
+        for game in series.games() {
+            assert_eq!(game.match_games == 2, game.loss != Loss::Plain);
+        }
+        assert!(series.next_game().is_err());
+    }
+
+    #[test]
+    fn loser_deals_first() {
+        let mut series = new_match(3, FirstDealer::LoserDeals);
+        let first = series.next_game().unwrap();
+        assert_eq!(first.phase(), GamePhase::CutForDeal);
+
+        let mut game = first;
+        game.play_to_end(&[&DefaultStrategy, &DefaultStrategy])
+            .unwrap();
+        let winner = series.add_game(&game).unwrap().winner;
+        let next = series.next_game().unwrap();
+        assert_eq!(next.phase(), GamePhase::Deal);
+        assert_eq!(next.dealer(), 1 - winner);
+        assert_eq!(
+            next.record().moves,
+            vec![GameMove::FirstDealer { dealer: 1 - winner }]
+        );
+
+        let series = new_match(3, FirstDealer::Cut);
+        assert_eq!(series.next_game().unwrap().phase(), GamePhase::CutForDeal);
+    }
+
+    #[test]
+    fn skunks_count_double() {
+        let mut series = new_match(3, FirstDealer::Cut);
+        let mut game = Game::from_seed(1);
+        game.play_to_end(&[&DefaultStrategy, &DefaultStrategy])
+            .unwrap();
+        let added = series.add_game(&game).unwrap().clone();
+        let expected = if added.loss == Loss::Plain {
+            (1, WIN_GAME_POINTS)
+        } else {
+            (2, SKUNK_GAME_POINTS)
+        };
+        assert_eq!((added.match_games, added.game_points), expected);
+        assert_eq!(series.wins()[added.winner], expected.0);
+
+        // an unfinished game can't be added
+        assert!(series.add_game(&Game::from_seed(2)).is_err());
+    }
+
+    #[test]
+    fn bad_matches() {
+        let rules = RuleSet::default();
+        assert!(Match::new(4, Variant::TwoPlayer, rules, FirstDealer::Cut, 0).is_err());
+        assert!(Match::new(MAX_BEST_OF, Variant::TwoPlayer, rules, FirstDealer::Cut, 0).is_ok());
+        assert!(Match::new(101, Variant::TwoPlayer, rules, FirstDealer::Cut, 0).is_err());
+        assert!(Match::new(3, Variant::ThreePlayer, rules, FirstDealer::Cut, 0).is_err());
+        assert!(Match::new(3, Variant::FourPlayer, rules, FirstDealer::Cut, 0).is_ok());
+    }
+}
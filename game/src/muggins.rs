@@ -0,0 +1,202 @@
+//! `muggins` (also called cutthroat) lets an opponent take any points that a
+//! player misses when they count their hand, their crib or a card they
+//! pegged.  `check_claim` compares what a player claimed with the real
+//! `Score` to find the points they missed.
+
+use crate::{
+    cards::Card,
+    scoring::{Combination, CombinationName, Score},
+};
+use serde::{Deserialize, Serialize};
+
+/// A scoring combination that a player says they have
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimedCombination {
+    pub name: CombinationName,
+    pub cards: Vec<Card>,
+}
+
+/// What a player says they scored.  `combinations` is optional, as a player
+/// is allowed to just say their total.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claim {
+    pub total: u32,
+    pub combinations: Option<Vec<ClaimedCombination>>,
+}
+
+impl Claim {
+    /// a claim of just a total
+    pub fn total(total: u32) -> Claim {
+        Claim {
+            total,
+            combinations: None,
+        }
+    }
+
+    /// a claim of the combinations in `score`, which claims every point
+    pub fn all_of(score: &Score) -> Claim {
+        Claim {
+            total: score.total_score,
+            combinations: Some(
+                score
+                    .combinations
+                    .iter()
+                    .map(|c| ClaimedCombination {
+                        name: c.name,
+                        cards: c.cards.clone(),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// The result of comparing a `Claim` with the real `Score`
+#[derive(Clone, Debug, Serialize)]
+pub struct MugginsCheck {
+    /// the points really scored
+    pub actual: u32,
+    pub claimed: u32,
+    /// the points the player gets: what they claimed, up to the real score.
+    /// if they listed their combinations, only the real ones count.
+    pub credited: u32,
+    /// the points the opponent takes
+    pub missed_points: u32,
+    /// the real combinations that were not claimed.  this is only known when
+    /// the claim lists its combinations.
+    pub missed: Vec<Combination>,
+    /// claimed combinations that aren't in the real score
+    pub invalid: Vec<ClaimedCombination>,
+}
+
+impl MugginsCheck {
+    /// true if the player claimed exactly what they scored
+    pub fn is_correct(&self) -> bool {
+        self.missed_points == 0 && self.invalid.is_empty() && self.claimed == self.actual
+    }
+}
+
+/// Compares `claim` with the `actual` score.  A claimed combination matches
+/// a real one if they have the same name and the same cards, in any order.
+pub fn check_claim(actual: &Score, claim: &Claim) -> MugginsCheck {
+    let mut missed = Vec::new();
+    let mut invalid = Vec::new();
+    let credited = match &claim.combinations {
+        None => claim.total.min(actual.total_score),
+        Some(claimed) => {
+            let mut matched = vec![false; claimed.len()];
+            let mut found = 0;
+            for combination in actual.combinations.iter() {
+                let position = claimed
+                    .iter()
+                    .enumerate()
+                    .position(|(i, c)| !matched[i] && same_combination(c, combination));
+                match position {
+                    Some(i) => {
+                        matched[i] = true;
+                        found += combination.points;
+                    }
+                    None => missed.push(combination.clone()),
+                }
+            }
+            invalid.extend(
+                claimed
+                    .iter()
+                    .zip(matched)
+                    .filter(|(_, matched)| !matched)
+                    .map(|(c, _)| c.clone()),
+            );
+            claim.total.min(found)
+        }
+    };
+
+    MugginsCheck {
+        actual: actual.total_score,
+        claimed: claim.total,
+        credited,
+        missed_points: actual.total_score - credited,
+        missed,
+        invalid,
+    }
+}
+
+fn same_combination(claimed: &ClaimedCombination, actual: &Combination) -> bool {
+    claimed.name == actual.name
+        && claimed.cards.len() == actual.cards.len()
+        && claimed.cards.iter().all(|c| actual.cards.contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{Rank::*, Suit as Of};
+    use crate::new_card as c;
+    use crate::scoring::score_hand;
+
+    fn score() -> Score {
+        // fifteen 2, fifteen 4, a pair is 6
+        score_hand(
+            vec![
+                c!(Five, Of::Hearts),
+                c!(King, Of::Clubs),
+                c!(Ace, Of::Spades),
+                c!(Three, Of::Spades),
+            ],
+            Some(c!(Five, Of::Diamonds)),
+            false,
+        )
+    }
+
+    #[test]
+    fn correct_claim() {
+        let score = score();
+        assert_eq!(score.total_score, 6);
+        let check = check_claim(&score, &Claim::all_of(&score));
+        assert!(check.is_correct());
+        assert_eq!(check.credited, 6);
+        assert!(check_claim(&score, &Claim::total(6)).is_correct());
+    }
+
+    #[test]
+    fn missed_total() {
+        let check = check_claim(&score(), &Claim::total(4));
+        assert_eq!(check.credited, 4);
+        assert_eq!(check.missed_points, 2);
+        assert!(check.missed.is_empty());
+
+        // claiming too much gets the real score and nothing for the opponent
+        let check = check_claim(&score(), &Claim::total(10));
+        assert_eq!(check.credited, 6);
+        assert_eq!(check.missed_points, 0);
+        assert!(!check.is_correct());
+    }
+
+    #[test]
+    fn missed_and_invalid_combinations() {
+        let claim = Claim {
+            total: 6,
+            combinations: Some(vec![
+                ClaimedCombination {
+                    name: CombinationName::Pair,
+                    cards: vec![c!(Five, Of::Diamonds), c!(Five, Of::Hearts)],
+                },
+                ClaimedCombination {
+                    name: CombinationName::Fifteen,
+                    cards: vec![c!(Five, Of::Hearts), c!(King, Of::Clubs)],
+                },
+                ClaimedCombination {
+                    name: CombinationName::Fifteen,
+                    cards: vec![c!(Five, Of::Hearts), c!(Ace, Of::Spades)],
+                },
+            ]),
+        };
+        let check = check_claim(&score(), &claim);
+        assert_eq!(check.credited, 4);
+        assert_eq!(check.missed_points, 2);
+        assert_eq!(check.missed.len(), 1);
+        assert_eq!(check.missed[0].name, CombinationName::Fifteen);
+        assert!(check.missed[0].cards.contains(&c!(Five, Of::Diamonds)));
+        assert_eq!(check.invalid.len(), 1);
+        assert_eq!(check.invalid[0].cards[1], c!(Ace, Of::Spades));
+    }
+}
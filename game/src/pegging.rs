@@ -0,0 +1,479 @@
+//! `pegging` models a full round of pegging (the play): whose turn it is,
+//! "Go", the point for the last card, and starting a new count after 31.
+//!
+//! scoring of each card played is done by `score_counting_cards_played` so
+//! that the counting rules live in one place.
+
+use crate::{
+    card_set::CardSet,
+    cards::{Card, Hand},
+    counting::score_counting_cards_played_with_rules,
+    cribbage_errors::{CribbageError, CribbageErrorKind},
+    rules::RuleSet,
+    scoring::{Combination, CombinationKind, CombinationName, Score},
+};
+use serde::{Deserialize, Serialize};
+
+/// the highest count allowed during pegging
+pub const MAX_COUNT: i32 = 31;
+
+/// A record of each action taken during pegging, in order
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PeggingAction {
+    Play { player: usize, card: Card },
+    Go { player: usize },
+}
+
+/// Everything about a `PeggingRound` that decides how the rest of it can
+/// go, but not how it got there.  Searches use it to spot a position they
+/// have already seen.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PeggingPosition {
+    hands: Vec<CardSet>,
+    pile: Vec<u8>, // the index of each card, in the order played
+    before_thirty_one: Vec<u8>,
+    turn: usize,
+    said_go: Vec<bool>,
+    last_to_play: Option<usize>,
+}
+
+/// `PeggingRound` tracks the play of the cards after the starter is cut.
+/// Players are identified by their seat and play in seat order, starting
+/// with the player passed to `new`.
+#[derive(Clone, Debug)]
+pub struct PeggingRound {
+    hands: Vec<Hand>, // the cards each player has not played yet
+    pile: Vec<Card>,  // cards played since the count was last reset
+    history: Vec<PeggingAction>,
+    turn: usize,
+    said_go: Vec<bool>,
+    last_to_play: Option<usize>,
+    finished: bool,
+    rules: RuleSet,
+    before_thirty_one: Vec<Card>, // the pile of the count that just ended at 31
+}
+
+impl PeggingRound {
+    /// Starts a round of pegging where `hands[seat]` are the cards held by
+    /// each player and `first` is the seat that leads
+    pub fn new(hands: Vec<Hand>, first: usize) -> PeggingRound {
+        PeggingRound::with_rules(hands, first, RuleSet::default())
+    }
+
+    /// `new` with the house `rules` for runs and pairs across a 31
+    pub fn with_rules(hands: Vec<Hand>, first: usize, rules: RuleSet) -> PeggingRound {
+        let players = hands.len();
+        let finished = hands.iter().all(|h| h.is_empty());
+        PeggingRound {
+            hands,
+            pile: Vec::new(),
+            history: Vec::new(),
+            turn: first,
+            said_go: vec![false; players],
+            last_to_play: None,
+            finished,
+            rules,
+            before_thirty_one: Vec::new(),
+        }
+    }
+
+    /// the player who has to act next
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    /// the cards played since the count was last reset
+    pub fn pile(&self) -> &[Card] {
+        &self.pile
+    }
+
+    pub fn count(&self) -> i32 {
+        self.pile.iter().map(|c| c.value).sum()
+    }
+
+    /// the cards `player` has not played yet
+    pub fn hand(&self, player: usize) -> &[Card] {
+        &self.hands[player]
+    }
+
+    pub fn history(&self) -> &[PeggingAction] {
+        &self.history
+    }
+
+    /// every card played so far in the round, in the order played
+    pub fn played_cards(&self) -> Vec<Card> {
+        self.history
+            .iter()
+            .filter_map(|action| match action {
+                PeggingAction::Play { card, .. } => Some(*card),
+                PeggingAction::Go { .. } => None,
+            })
+            .collect()
+    }
+
+    /// the position of the round, which is the same for any two rounds that
+    /// will score the same from here on
+    pub fn position(&self) -> PeggingPosition {
+        let indices = |cards: &[Card]| cards.iter().map(|c| c.index() as u8).collect();
+        PeggingPosition {
+            hands: self
+                .hands
+                .iter()
+                .map(|h| CardSet::from(h.as_slice()))
+                .collect(),
+            pile: indices(&self.pile),
+            before_thirty_one: indices(&self.before_thirty_one),
+            turn: self.turn,
+            said_go: self.said_go.clone(),
+            last_to_play: self.last_to_play,
+        }
+    }
+
+    /// true when all cards have been played
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// true if `player` has a card that keeps the count at 31 or less
+    pub fn can_play(&self, player: usize) -> bool {
+        let count = self.count();
+        self.hands[player]
+            .iter()
+            .any(|c| count + c.value <= MAX_COUNT)
+    }
+
+    /// `player` plays `card`.
+    ///
+    /// # Returns
+    ///
+    /// the `Score` pegged by `player`.  If this was the last card that could
+    /// be played, it includes the point for the go or last card.
+    pub fn play(&mut self, player: usize, card: Card) -> Result<Score, CribbageError> {
+        self.check_turn(player)?;
+        if !self.hands[player].contains(&card) {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadCard,
+                format!("{} is not in the hand of player {}", card, player),
+            ));
+        }
+        let mut score = score_counting_cards_played_with_rules(&self.pile, card, &self.rules)?;
+        if self.rules.pairs_across_thirty_one {
+            self.pair_across_thirty_one(&mut score, card);
+        }
+
+        self.hands[player].retain(|c| *c != card);
+        self.pile.push(card);
+        self.history.push(PeggingAction::Play { player, card });
+        self.last_to_play = Some(player);
+        if self.count() == MAX_COUNT {
+            // the 31 is already scored, so there is no point for the go
+            let pile = std::mem::take(&mut self.pile);
+            self.reset_count();
+            self.before_thirty_one = pile;
+        }
+        if let Some((_, go)) = self.advance_turn() {
+            score.combinations.push(go);
+            score.total_score += 1;
+        }
+        Ok(score)
+    }
+
+    /// `player` says "Go" because they have no card that keeps the count at
+    /// 31 or less.  It is an error to say "Go" when a card can be played.
+    ///
+    /// # Returns
+    ///
+    /// the player that pegs a point for the go and its `Score`, if saying
+    /// "Go" ended the count
+    pub fn go(&mut self, player: usize) -> Result<Option<(usize, Score)>, CribbageError> {
+        self.check_turn(player)?;
+        if self.can_play(player) {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!("player {} has a card that can be played", player),
+            ));
+        }
+        self.said_go[player] = true;
+        self.history.push(PeggingAction::Go { player });
+        Ok(self.advance_turn().map(|(player, go)| {
+            let mut score = Score::new();
+            score.tally(vec![go]);
+            (player, score)
+        }))
+    }
+
+    /// moves the turn to the next player that can act.  when nobody can
+    /// act, the last player to play gets a point for the go (or the last
+    /// card) and the count starts over.
+    fn advance_turn(&mut self) -> Option<(usize, Combination)> {
+        if let Some(player) = self.next_to_act(self.turn) {
+            self.turn = player;
+            return None;
+        }
+
+        let last = self.last_to_play.unwrap(); // the count can only end after a card is played
+        let go = match self.pile.last() {
+            Some(card) => {
+                let kind = if self.hands.iter().all(|h| h.is_empty()) {
+                    CombinationKind::LastCard
+                } else {
+                    CombinationKind::Go
+                };
+                Some((last, Combination::new(kind, vec![*card])))
+            }
+            None => None,
+        };
+        self.reset_count();
+        match self.next_to_act(last) {
+            Some(player) => self.turn = player,
+            None => self.finished = true,
+        }
+        go
+    }
+
+    /// the next player after `player` who still has cards and has not said
+    /// "Go", including `player` themselves
+    fn next_to_act(&self, player: usize) -> Option<usize> {
+        let players = self.hands.len();
+        (1..=players)
+            .map(|offset| (player + offset) % players)
+            .find(|p| !self.said_go[*p] && !self.hands[*p].is_empty())
+    }
+
+    /// a card played after a 31 pairs with the cards of the same rank at the
+    /// end of the count before, as long as only cards of that rank have
+    /// been played since
+    fn pair_across_thirty_one(&self, score: &mut Score, card: Card) {
+        if !self.pile.iter().all(|c| c.rank == card.rank) {
+            return;
+        }
+        let before = self
+            .before_thirty_one
+            .iter()
+            .rev()
+            .take_while(|c| c.rank == card.rank)
+            .count();
+        if before == 0 {
+            return;
+        }
+        let mut cards = self.before_thirty_one[self.before_thirty_one.len() - before..].to_vec();
+        cards.extend_from_slice(&self.pile);
+        cards.push(card);
+
+        score.combinations.retain(|c| {
+            !matches!(
+                c.name,
+                CombinationName::Pair
+                    | CombinationName::RoyalPair
+                    | CombinationName::DoubleRoyalPair
+            )
+        });
+        score
+            .combinations
+            .push(Combination::new(CombinationKind::RankMatch, cards));
+        score.total_score = score.combinations.iter().map(|c| c.points).sum();
+    }
+
+    fn reset_count(&mut self) {
+        self.before_thirty_one.clear();
+        self.pile.clear();
+        self.said_go.iter_mut().for_each(|go| *go = false);
+    }
+
+    fn check_turn(&self, player: usize) -> Result<(), CribbageError> {
+        if self.finished {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                "all of the cards have been played".into(),
+            ));
+        }
+        if self.turn != player {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!("it is player {}'s turn, not player {}'s", self.turn, player),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{Rank::*, Suit as Of};
+    use crate::new_card as c;
+
+    #[test]
+    fn go_gives_a_point_to_the_last_player() {
+        let mut round = PeggingRound::new(
+            vec![
+                vec![c!(King, Of::Hearts), c!(Five, Of::Hearts)],
+                vec![c!(Ten, Of::Clubs), c!(Jack, Of::Clubs)],
+            ],
+            1,
+        );
+        round.play(1, c!(Ten, Of::Clubs)).unwrap();
+        round.play(0, c!(King, Of::Hearts)).unwrap();
+        let score = round.play(1, c!(Jack, Of::Clubs)).unwrap();
+        assert_eq!(score.total_score, 0);
+        assert_eq!(round.count(), 30);
+
+        let (player, score) = round.go(0).unwrap().unwrap();
+        assert_eq!(player, 1);
+        assert_eq!(score.total_score, 1);
+        assert_eq!(score.combinations[0].name, CombinationName::Go);
+        assert_eq!(round.count(), 0);
+        assert_eq!(round.turn(), 0);
+
+        let score = round.play(0, c!(Five, Of::Hearts)).unwrap();
+        assert_eq!(score.total_score, 1);
+        assert_eq!(score.combinations[0].name, CombinationName::LastCard);
+        assert!(round.is_finished());
+    }
+
+    #[test]
+    fn player_keeps_playing_after_opponent_says_go() {
+        let mut round = PeggingRound::new(
+            vec![
+                vec![
+                    c!(King, Of::Hearts),
+                    c!(Two, Of::Hearts),
+                    c!(Three, Of::Hearts),
+                ],
+                vec![
+                    c!(Ten, Of::Clubs),
+                    c!(Jack, Of::Clubs),
+                    c!(Queen, Of::Clubs),
+                ],
+            ],
+            1,
+        );
+        round.play(1, c!(Ten, Of::Clubs)).unwrap();
+        round.play(0, c!(King, Of::Hearts)).unwrap();
+        round.play(1, c!(Jack, Of::Clubs)).unwrap();
+        assert!(round.go(0).unwrap().is_none()); // the pone can't play either, but has to say so
+        assert_eq!(round.turn(), 1);
+        let (player, _) = round.go(1).unwrap().unwrap();
+        assert_eq!(player, 1);
+        assert_eq!(round.turn(), 0);
+        round.play(0, c!(Two, Of::Hearts)).unwrap();
+        round.play(1, c!(Queen, Of::Clubs)).unwrap();
+        let score = round.play(0, c!(Three, Of::Hearts)).unwrap();
+        assert_eq!(score.total_score, 3); // fifteen and the last card
+        assert!(round.is_finished());
+    }
+
+    #[test]
+    fn opponent_out_of_cards_lets_player_continue() {
+        let mut round = PeggingRound::new(
+            vec![
+                vec![c!(Four, Of::Hearts), c!(Four, Of::Spades)],
+                vec![c!(Four, Of::Clubs)],
+            ],
+            1,
+        );
+        round.play(1, c!(Four, Of::Clubs)).unwrap();
+        round.play(0, c!(Four, Of::Hearts)).unwrap();
+        assert_eq!(round.turn(), 0);
+        let score = round.play(0, c!(Four, Of::Spades)).unwrap();
+        assert_eq!(score.total_score, 7); // three of a kind and the last card
+        assert!(round.is_finished());
+    }
+
+    #[test]
+    fn thirty_one_resets_the_count() {
+        let mut round = PeggingRound::new(
+            vec![
+                vec![
+                    c!(Ten, Of::Hearts),
+                    c!(Ace, Of::Hearts),
+                    c!(Two, Of::Hearts),
+                ],
+                vec![
+                    c!(Ten, Of::Clubs),
+                    c!(Jack, Of::Clubs),
+                    c!(Three, Of::Clubs),
+                ],
+            ],
+            1,
+        );
+        round.play(1, c!(Ten, Of::Clubs)).unwrap();
+        round.play(0, c!(Ten, Of::Hearts)).unwrap();
+        round.play(1, c!(Jack, Of::Clubs)).unwrap();
+        let score = round.play(0, c!(Ace, Of::Hearts)).unwrap();
+        assert_eq!(score.total_score, 2);
+        assert_eq!(round.count(), 0);
+        assert_eq!(round.turn(), 1); // the player after the 31 leads the new count
+        round.play(1, c!(Three, Of::Clubs)).unwrap();
+        round.play(0, c!(Two, Of::Hearts)).unwrap();
+        assert!(round.is_finished());
+        assert_eq!(round.played_cards().len(), 6);
+    }
+
+    #[test]
+    fn pairs_across_thirty_one() {
+        let hands = vec![
+            vec![
+                c!(Ten, Of::Hearts),
+                c!(Ace, Of::Hearts),
+                c!(Five, Of::Diamonds),
+            ],
+            vec![c!(Ten, Of::Clubs), c!(Jack, Of::Clubs), c!(Ace, Of::Clubs)],
+        ];
+        let rules = RuleSet {
+            pairs_across_thirty_one: true,
+            ..RuleSet::default()
+        };
+        for (rules, points) in [(RuleSet::default(), 0), (rules, 2)] {
+            let mut round = PeggingRound::with_rules(hands.clone(), 1, rules);
+            round.play(1, c!(Ten, Of::Clubs)).unwrap();
+            round.play(0, c!(Ten, Of::Hearts)).unwrap();
+            round.play(1, c!(Jack, Of::Clubs)).unwrap();
+            round.play(0, c!(Ace, Of::Hearts)).unwrap(); // 31
+            let score = round.play(1, c!(Ace, Of::Clubs)).unwrap();
+            assert_eq!(score.total_score, points);
+            let score = round.play(0, c!(Five, Of::Diamonds)).unwrap();
+            assert_eq!(score.total_score, 1); // the last card, not a pair
+        }
+    }
+
+    #[test]
+    fn wrapped_runs_while_pegging() {
+        let rules = RuleSet {
+            wrap_runs: true,
+            ..RuleSet::default()
+        };
+        let mut round = PeggingRound::with_rules(
+            vec![
+                vec![c!(King, Of::Hearts), c!(Two, Of::Hearts)],
+                vec![c!(Ace, Of::Clubs), c!(Queen, Of::Clubs)],
+            ],
+            1,
+            rules,
+        );
+        round.play(1, c!(Queen, Of::Clubs)).unwrap();
+        round.play(0, c!(King, Of::Hearts)).unwrap();
+        let score = round.play(1, c!(Ace, Of::Clubs)).unwrap();
+        assert_eq!(score.total_score, 3);
+        let score = round.play(0, c!(Two, Of::Hearts)).unwrap();
+        assert_eq!(score.total_score, 4 + 1); // a run of four and the last card
+    }
+
+    #[test]
+    fn illegal_actions_are_rejected() {
+        let mut round =
+            PeggingRound::new(vec![vec![c!(Ten, Of::Hearts)], vec![c!(Ten, Of::Clubs)]], 1);
+        assert_eq!(
+            round.play(0, c!(Ten, Of::Hearts)).unwrap_err().error_kind,
+            CribbageErrorKind::BadAction
+        );
+        assert_eq!(
+            round.play(1, c!(Ten, Of::Hearts)).unwrap_err().error_kind,
+            CribbageErrorKind::BadCard
+        );
+        assert_eq!(
+            round.go(1).unwrap_err().error_kind,
+            CribbageErrorKind::BadAction
+        );
+    }
+}
@@ -0,0 +1,823 @@
+//! `pegging_search` chooses the card to play by looking ahead to the end of
+//! the round of pegging, instead of only at the next play like
+//! `get_next_counted_card`.
+//!
+//! `choose_card_monte_carlo` can't see the other hands, so it deals the
+//! unseen cards to the other players at random, many times over.  For each
+//! of these samples it plays out the rest of the round after each card it
+//! could play, with everybody then playing like `get_next_counted_card`,
+//! and picks the card with the best average point difference.
+//!
+//! `solve_pegging` searches every way the rest of the round can be played,
+//! so its play is the best possible when every hand is known.  Positions
+//! are cached by `PeggingPosition`, as the same cards left in the same
+//! hands can be reached in many orders.  `solve_pegging_unknown` averages
+//! the solution over every holding the other players could have, which is
+//! only practical near the end of the round.  Both give the value of every
+//! card that could be played, so they can grade other ways of choosing.
+//!
+//! `choose_card_to_win` samples like `choose_card_monte_carlo`, but values
+//! each sample by the chance of winning the game from the scores it ends
+//! at, which `win_probability` looks up.
+
+use crate::{
+    card_set::CardSet,
+    cards::{Card, Hand},
+    cribbage_errors::{CribbageError, CribbageErrorKind},
+    discard_analysis::unseen_cards,
+    pegging::{PeggingAction, PeggingPosition, PeggingRound, MAX_COUNT},
+    rules::GAME_LENGTH,
+    select_cards::{discard_table_value, get_next_counted_card},
+    strategy::StrategyContext,
+    variant::Variant,
+    win_probability::{hand_distribution, mean, WinProbabilities},
+};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// How much work `choose_card_monte_carlo` does for one play.  It stops at
+/// `samples` samples or after `time_limit_ms`, whichever comes first, and
+/// always takes at least one sample.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchBudget {
+    pub samples: usize,
+    /// `None` for no time limit, which makes the choice repeatable
+    pub time_limit_ms: Option<u64>,
+    pub seed: u64,
+}
+
+impl Default for SearchBudget {
+    fn default() -> Self {
+        SearchBudget {
+            samples: 200,
+            time_limit_ms: None,
+            seed: 0,
+        }
+    }
+}
+
+/// points pegged during a round, as the player who pegged them and the
+/// points
+pub type Peg = (usize, u32);
+
+/// a sample that can't be replayed, because an opponent is dealt a card they
+/// could have played when they said "Go", is thrown away.  this is how many
+/// samples can be thrown away for each one kept before giving up.
+const ATTEMPTS_PER_SAMPLE: usize = 10;
+
+/// `choose_card_exact` only solves exactly when the other players can hold
+/// no more than this many different sets of cards
+pub const MAX_EXACT_HOLDINGS: usize = 2_000;
+
+/// The value of each card a player could play
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PeggingSolution {
+    /// the best card, or `None` if the player has to say "Go"
+    pub best: Option<Card>,
+    /// each card that can be played and the points that the player's side
+    /// will peg from here to the end of the round minus the points the
+    /// other side will peg, from best to worst
+    pub values: Vec<(Card, f64)>,
+    /// the number of holdings of the other players that were averaged over.
+    /// 1 when every hand is known.
+    pub holdings: usize,
+}
+
+impl PeggingSolution {
+    /// how many points playing `card` gives up compared with the best card,
+    /// or `None` if it can't be played
+    pub fn points_lost(&self, card: Card) -> Option<f64> {
+        let best = self.values.first()?.1;
+        self.values
+            .iter()
+            .find(|(c, _)| *c == card)
+            .map(|(_, value)| best - value)
+    }
+}
+
+/// Chooses the card for `context.player` to play by sampling the hands of
+/// the other players from the unseen cards and playing out the round
+///
+/// # Returns
+///
+/// the card to play, or `None` to say "Go"
+pub fn choose_card_monte_carlo(
+    context: &StrategyContext,
+    budget: SearchBudget,
+) -> Result<Option<Card>, CribbageError> {
+    let side = context.variant.team_of(context.player);
+    choose_card_monte_carlo_by(context, budget, &|pegs| {
+        pegs.iter()
+            .map(|(player, points)| {
+                if context.variant.team_of(*player) == side {
+                    *points as f64
+                } else {
+                    -(*points as f64)
+                }
+            })
+            .sum()
+    })
+}
+
+/// `choose_card_monte_carlo`, but picking the card with the best average
+/// `value` of the rest of the round instead of the best point difference.
+/// `value` is given the points pegged from the card played on, in order.
+pub fn choose_card_monte_carlo_by(
+    context: &StrategyContext,
+    budget: SearchBudget,
+    value: &dyn Fn(&[Peg]) -> f64,
+) -> Result<Option<Card>, CribbageError> {
+    let count: i32 = context.pile.iter().map(|c| c.value).sum();
+    let candidates: Hand = context
+        .hand
+        .iter()
+        .filter(|c| count + c.value <= MAX_COUNT)
+        .copied()
+        .collect();
+    if candidates.len() < 2 {
+        return Ok(candidates.first().copied());
+    }
+
+    let knowledge = Knowledge::new(context);
+    let mut unseen = knowledge.unseen.clone();
+
+    let mut rng = StdRng::seed_from_u64(budget.seed);
+    let deadline = budget
+        .time_limit_ms
+        .map(|ms| Instant::now() + Duration::from_millis(ms));
+    let wanted = budget.samples.max(1);
+    let mut totals = vec![0.0; candidates.len()];
+    let mut samples = 0;
+    let mut attempts = 0;
+    while samples < wanted && attempts < wanted * ATTEMPTS_PER_SAMPLE {
+        if samples > 0 && matches!(deadline, Some(d) if Instant::now() >= d) {
+            break;
+        }
+        attempts += 1;
+
+        unseen.shuffle(&mut rng);
+        let mut deal = unseen.iter().copied();
+        let hands = knowledge.hands(context, &mut deal);
+        let round = match knowledge.replay(hands, context) {
+            Some(round) => round,
+            None => continue,
+        };
+
+        samples += 1;
+        for (i, card) in candidates.iter().enumerate() {
+            totals[i] += value(&play_out(round.clone(), *card, context)?);
+        }
+    }
+
+    if samples == 0 {
+        // every sample contradicted the history
+        return get_next_counted_card(context.pile.to_vec(), context.hand.to_vec());
+    }
+    let best = (0..candidates.len())
+        .max_by(|a, b| totals[*a].partial_cmp(&totals[*b]).unwrap().then(b.cmp(a)))
+        .unwrap(); // there are at least two candidates
+    Ok(Some(candidates[best]))
+}
+
+/// Solves the rest of `round` for the player whose turn it is, with every
+/// hand known.  The other side is taken to play as well as they can, which
+/// with three players means both of the others play against this one.
+pub fn solve_pegging(
+    round: &PeggingRound,
+    variant: Variant,
+) -> Result<PeggingSolution, CribbageError> {
+    if round.is_finished() {
+        return Err(CribbageError::new(
+            CribbageErrorKind::BadAction,
+            "all of the cards have been played".to_string(),
+        ));
+    }
+    let mut search = Search::new(variant, round.turn());
+    let values = search.card_values(round)?;
+    Ok(solution(values, 1))
+}
+
+/// Solves the rest of the round for `context.player` by averaging
+/// `solve_pegging` over every set of cards the other players could be
+/// holding.  Holdings where a player said "Go" while holding a card they
+/// could have played are left out.
+pub fn solve_pegging_unknown(context: &StrategyContext) -> Result<PeggingSolution, CribbageError> {
+    let knowledge = Knowledge::new(context);
+    let mut search = Search::new(context.variant, context.player);
+    let mut totals: Vec<(Card, f64)> = Vec::new();
+    let mut holdings = 0;
+    let mut error = None;
+    for_each_holding(&knowledge.unseen, &knowledge.needs(context), &mut |dealt| {
+        if error.is_some() {
+            return;
+        }
+        let hands = knowledge.hands(context, &mut dealt.iter().copied());
+        let round = match knowledge.replay(hands, context) {
+            Some(round) => round,
+            None => return,
+        };
+        match search.card_values(&round) {
+            Ok(values) => {
+                if totals.is_empty() {
+                    totals = values.iter().map(|(card, _)| (*card, 0.0)).collect();
+                }
+                for (total, (_, value)) in totals.iter_mut().zip(values) {
+                    total.1 += value as f64;
+                }
+                holdings += 1;
+            }
+            Err(e) => error = Some(e),
+        }
+    });
+    if let Some(e) = error {
+        return Err(e);
+    }
+    if holdings == 0 {
+        return Err(CribbageError::new(
+            CribbageErrorKind::BadAction,
+            "no holding of the other players agrees with the plays so far".to_string(),
+        ));
+    }
+    for total in totals.iter_mut() {
+        total.1 /= holdings as f64;
+    }
+    Ok(solution(totals, holdings))
+}
+
+/// Chooses the card that gives the best chance of winning a two player game
+/// to 121 from `context.scores`.  Samples are played out like
+/// `choose_card_monte_carlo`, and each is worth the chance of winning from
+/// the scores at the end of the round, with the hands still to be counted.
+/// A sample where a player pegs out is won or lost there, so near the end
+/// of the game the card that pegs out wins over the card that pegs most.
+pub fn choose_card_to_win(
+    context: &StrategyContext,
+    budget: SearchBudget,
+) -> Result<Option<Card>, CribbageError> {
+    let win = WinProbabilities::standard();
+    let side = context.variant.team_of(context.player);
+    let (me, opponent) = (context.scores[side], context.scores[1 - side]);
+    let my_deal = context.is_my_crib();
+
+    // the cards kept are the ones in hand and the ones played from it
+    let mut kept = context.hand.to_vec();
+    kept.extend(context.history.iter().filter_map(|action| match action {
+        PeggingAction::Play { player, card } if *player == context.player => Some(*card),
+        _ => None,
+    }));
+    let my_hand = match context.starter {
+        Some(starter) => hand_distribution(&kept, &[starter]),
+        None => hand_distribution(&kept, &unseen_cards(&context.known_cards())),
+    };
+    let crib = if context.discarded.is_empty() {
+        mean(&win.distribution().crib)
+    } else {
+        discard_table_value(context.discarded, my_deal) as f64
+    };
+    let (my_count, their_count) = win.counts(my_deal, &my_hand, crib);
+
+    choose_card_monte_carlo_by(context, budget, &|pegs| {
+        let (mut me, mut opponent) = (me, opponent);
+        for (player, points) in pegs {
+            if context.variant.team_of(*player) == side {
+                me += points;
+            } else {
+                opponent += points;
+            }
+            if me >= GAME_LENGTH {
+                return 1.0;
+            }
+            if opponent >= GAME_LENGTH {
+                return 0.0;
+            }
+        }
+        win.after_pegging(me, opponent, my_deal, &my_count, &their_count)
+    })
+}
+
+/// Chooses the card to play with `solve_pegging_unknown` when there are no
+/// more than `MAX_EXACT_HOLDINGS` holdings to average over, and with
+/// `choose_card_monte_carlo` and `budget` when there are
+pub fn choose_card_exact(
+    context: &StrategyContext,
+    budget: SearchBudget,
+) -> Result<Option<Card>, CribbageError> {
+    let count: i32 = context.pile.iter().map(|c| c.value).sum();
+    if !context.hand.iter().any(|c| count + c.value <= MAX_COUNT) {
+        return Ok(None);
+    }
+    let knowledge = Knowledge::new(context);
+    if holding_count(knowledge.unseen.len(), &knowledge.needs(context)) > MAX_EXACT_HOLDINGS {
+        return choose_card_monte_carlo(context, budget);
+    }
+    match solve_pegging_unknown(context) {
+        Ok(solution) => Ok(solution.best),
+        // the plays so far don't fit any holding, so fall back to sampling
+        Err(_) => choose_card_monte_carlo(context, budget),
+    }
+}
+
+/// sorts `values` from best to worst
+fn solution<T: Into<f64>>(values: Vec<(Card, T)>, holdings: usize) -> PeggingSolution {
+    let mut values: Vec<(Card, f64)> = values.into_iter().map(|(c, v)| (c, v.into())).collect();
+    // a stable sort keeps the cards in hand order when they are worth the same
+    values.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    PeggingSolution {
+        best: values.first().map(|(card, _)| *card),
+        values,
+        holdings,
+    }
+}
+
+/// a minimax search of the rest of a round for the side of `player`
+struct Search {
+    variant: Variant,
+    side: usize,
+    cache: HashMap<PeggingPosition, i32>,
+}
+
+impl Search {
+    fn new(variant: Variant, player: usize) -> Search {
+        Search {
+            variant,
+            side: variant.team_of(player),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// `points` pegged by `player`, from the point of view of `side`
+    fn signed(&self, player: usize, points: u32) -> i32 {
+        if self.variant.team_of(player) == self.side {
+            points as i32
+        } else {
+            -(points as i32)
+        }
+    }
+
+    /// the value of each card the player whose turn it is can play, or an
+    /// empty list if they have to say "Go"
+    fn card_values(&mut self, round: &PeggingRound) -> Result<Vec<(Card, i32)>, CribbageError> {
+        let player = round.turn();
+        let count = round.count();
+        let mut values = Vec::new();
+        for card in round.hand(player).iter() {
+            if count + card.value > MAX_COUNT {
+                continue;
+            }
+            let mut next = round.clone();
+            let score = next.play(player, *card)?;
+            let value = self.signed(player, score.total_score) + self.value(&next)?;
+            values.push((*card, value));
+        }
+        Ok(values)
+    }
+
+    /// the value of the rest of the round with every player playing their
+    /// best
+    fn value(&mut self, round: &PeggingRound) -> Result<i32, CribbageError> {
+        if round.is_finished() {
+            return Ok(0);
+        }
+        let position = round.position();
+        if let Some(value) = self.cache.get(&position) {
+            return Ok(*value);
+        }
+
+        let player = round.turn();
+        let value = if round.can_play(player) {
+            let values = self.card_values(round)?.into_iter().map(|(_, v)| v);
+            if self.variant.team_of(player) == self.side {
+                values.max()
+            } else {
+                values.min()
+            }
+            .unwrap() // the player can play
+        } else {
+            let mut next = round.clone();
+            let go = match next.go(player)? {
+                Some((scorer, score)) => self.signed(scorer, score.total_score),
+                None => 0,
+            };
+            go + self.value(&next)?
+        };
+        self.cache.insert(position, value);
+        Ok(value)
+    }
+}
+
+/// What a player knows about the round so far
+struct Knowledge {
+    /// the plays so far.  the web api only knows the pile, so then the cards
+    /// in it are taken to have been played in turn by the players before
+    /// this one.
+    history: Vec<PeggingAction>,
+    /// the player who led
+    first: usize,
+    /// the cards each player has played
+    played: Vec<Hand>,
+    /// the cards that the other players could be holding
+    unseen: Hand,
+}
+
+impl Knowledge {
+    fn new(context: &StrategyContext) -> Knowledge {
+        let players = context.variant.players();
+        let history = if !context.history.is_empty() || context.pile.is_empty() {
+            context.history.to_vec()
+        } else {
+            let n = context.pile.len();
+            context
+                .pile
+                .iter()
+                .enumerate()
+                .map(|(i, card)| PeggingAction::Play {
+                    player: (context.player + players * n - (n - i)) % players,
+                    card: *card,
+                })
+                .collect()
+        };
+        let first = match history.first() {
+            Some(PeggingAction::Play { player, .. }) | Some(PeggingAction::Go { player }) => {
+                *player
+            }
+            None => context.player,
+        };
+
+        let mut played = vec![Hand::new(); players];
+        let mut known = CardSet::from(context.known_cards().as_slice());
+        for action in history.iter() {
+            if let PeggingAction::Play { player, card } = action {
+                played[*player].push(*card);
+                known.insert(*card);
+            }
+        }
+        Knowledge {
+            history,
+            first,
+            played,
+            unseen: CardSet::full().difference(known).to_hand(),
+        }
+    }
+
+    /// the number of unplayed cards each other player holds, by seat
+    fn needs(&self, context: &StrategyContext) -> Vec<usize> {
+        (0..self.played.len())
+            .map(|seat| {
+                if seat == context.player {
+                    0
+                } else {
+                    context
+                        .variant
+                        .cards_kept()
+                        .saturating_sub(self.played[seat].len())
+                }
+            })
+            .collect()
+    }
+
+    /// the hand each player started the round with, taking the unplayed
+    /// cards of the other players from `deal`
+    fn hands(&self, context: &StrategyContext, deal: &mut dyn Iterator<Item = Card>) -> Vec<Hand> {
+        let needs = self.needs(context);
+        (0..self.played.len())
+            .map(|seat| {
+                let mut hand = self.played[seat].clone();
+                if seat == context.player {
+                    hand.extend_from_slice(context.hand);
+                } else {
+                    hand.extend(deal.take(needs[seat]));
+                }
+                hand
+            })
+            .collect()
+    }
+
+    /// rebuilds the round from the start with `hands`, or `None` if the plays
+    /// so far couldn't have happened with them
+    fn replay(&self, hands: Vec<Hand>, context: &StrategyContext) -> Option<PeggingRound> {
+        let mut round = PeggingRound::with_rules(hands, self.first, context.rules);
+        for action in self.history.iter() {
+            let replayed = match action {
+                PeggingAction::Play { player, card } => round.play(*player, *card).map(|_| ()),
+                PeggingAction::Go { player } => round.go(*player).map(|_| ()),
+            };
+            replayed.ok()?;
+        }
+        if round.is_finished() || round.turn() != context.player {
+            return None;
+        }
+        Some(round)
+    }
+}
+
+/// the number of ways to deal `needs[seat]` of `unseen` cards to each seat
+fn holding_count(unseen: usize, needs: &[usize]) -> usize {
+    let mut left = unseen;
+    let mut count: usize = 1;
+    for need in needs.iter().copied() {
+        if need > left {
+            return 0;
+        }
+        // n choose k, one factor at a time so every step divides exactly
+        let mut ways: usize = 1;
+        for i in 0..need {
+            ways = ways.saturating_mul(left - i) / (i + 1);
+        }
+        count = count.saturating_mul(ways);
+        left -= need;
+    }
+    count
+}
+
+/// calls `f` with every way of dealing `needs[seat]` of `unseen` to each
+/// seat, as the cards for each seat one after the other
+fn for_each_holding(unseen: &[Card], needs: &[usize], f: &mut dyn FnMut(&[Card])) {
+    let total: usize = needs.iter().sum();
+    let mut dealt = Vec::with_capacity(total);
+    deal_seat(unseen, needs, CardSet::new(), &mut dealt, f);
+}
+
+fn deal_seat(
+    unseen: &[Card],
+    needs: &[usize],
+    used: CardSet,
+    dealt: &mut Hand,
+    f: &mut dyn FnMut(&[Card]),
+) {
+    match needs.split_first() {
+        None => f(dealt),
+        Some((need, rest)) => {
+            let available: Hand = unseen
+                .iter()
+                .filter(|c| !used.contains(**c))
+                .copied()
+                .collect();
+            choose(&available, *need, &mut |chosen| {
+                let start = dealt.len();
+                dealt.extend_from_slice(chosen);
+                let used = chosen.iter().fold(used, |used, card| used.with(*card));
+                deal_seat(unseen, rest, used, dealt, f);
+                dealt.truncate(start);
+            });
+        }
+    }
+}
+
+/// calls `f` with each way of choosing `k` of `cards`
+fn choose(cards: &[Card], k: usize, f: &mut dyn FnMut(&[Card])) {
+    fn recurse(cards: &[Card], k: usize, chosen: &mut Hand, f: &mut dyn FnMut(&[Card])) {
+        if chosen.len() == k {
+            f(chosen);
+            return;
+        }
+        let needed = k - chosen.len();
+        for i in 0..cards.len() {
+            if cards.len() - i < needed {
+                break;
+            }
+            chosen.push(cards[i]);
+            recurse(&cards[i + 1..], k, chosen, f);
+            chosen.pop();
+        }
+    }
+    recurse(cards, k, &mut Vec::with_capacity(k), f);
+}
+
+/// plays `card` and then the rest of the round, with every player playing
+/// like `get_next_counted_card`
+///
+/// # Returns
+///
+/// the points pegged, in order, as the player who pegged them and the
+/// points
+fn play_out(
+    mut round: PeggingRound,
+    card: Card,
+    context: &StrategyContext,
+) -> Result<Vec<Peg>, CribbageError> {
+    let mut pegs = Vec::new();
+    let mut peg = |player: usize, points: u32| {
+        if points > 0 {
+            pegs.push((player, points));
+        }
+    };
+
+    let score = round.play(context.player, card)?;
+    peg(context.player, score.total_score);
+    while !round.is_finished() {
+        let player = round.turn();
+        if !round.can_play(player) {
+            if let Some((scorer, score)) = round.go(player)? {
+                peg(scorer, score.total_score);
+            }
+            continue;
+        }
+        let count = round.count();
+        let card = get_next_counted_card(round.pile().to_vec(), round.hand(player).to_vec())?
+            .filter(|c| count + c.value <= MAX_COUNT)
+            .or_else(|| {
+                round
+                    .hand(player)
+                    .iter()
+                    .find(|c| count + c.value <= MAX_COUNT)
+                    .copied()
+            })
+            .unwrap(); // the player can play
+        let score = round.play(player, card)?;
+        peg(player, score.total_score);
+    }
+    Ok(pegs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{Rank::*, Suit as Of};
+    use crate::game::Game;
+    use crate::game::GamePhase;
+    use crate::new_card as c;
+    use crate::rules::RuleSet;
+    use crate::strategy::{
+        DefaultStrategy, ExactStrategy, MonteCarloStrategy, ScoreAwareStrategy, Strategy,
+    };
+    use crate::variant::Variant;
+
+    fn budget() -> SearchBudget {
+        SearchBudget {
+            samples: 50,
+            time_limit_ms: None,
+            seed: 1,
+        }
+    }
+
+    #[test]
+    fn takes_the_fifteen() {
+        let hand = [
+            c!(Two, Of::Clubs),
+            c!(King, Of::Spades),
+            c!(Seven, Of::Hearts),
+        ];
+        let pile = [c!(Five, Of::Hearts)];
+        let mut context = StrategyContext::from_hand(&hand, true);
+        context.pile = &pile;
+        let card = choose_card_monte_carlo(&context, budget()).unwrap();
+        assert_eq!(card, Some(c!(King, Of::Spades)));
+    }
+
+    #[test]
+    fn says_go_or_plays_the_only_card() {
+        let hand = [c!(Nine, Of::Clubs), c!(Two, Of::Spades)];
+        let pile = [
+            c!(King, Of::Hearts),
+            c!(Queen, Of::Hearts),
+            c!(Eight, Of::Hearts),
+        ];
+        let mut context = StrategyContext::from_hand(&hand, true);
+        context.pile = &pile;
+        assert_eq!(
+            choose_card_monte_carlo(&context, budget()).unwrap(),
+            Some(c!(Two, Of::Spades))
+        );
+        context.hand = &hand[..1];
+        assert_eq!(choose_card_monte_carlo(&context, budget()).unwrap(), None);
+    }
+
+    #[test]
+    fn repeatable_without_a_time_limit() {
+        let hand = [
+            c!(Four, Of::Clubs),
+            c!(Six, Of::Spades),
+            c!(Nine, Of::Hearts),
+            c!(Jack, Of::Diamonds),
+        ];
+        let context = StrategyContext::from_hand(&hand, false);
+        let first = choose_card_monte_carlo(&context, budget()).unwrap();
+        assert_eq!(first, choose_card_monte_carlo(&context, budget()).unwrap());
+        assert!(hand.contains(&first.unwrap()));
+
+        // a time limit still takes at least one sample
+        let quick = SearchBudget {
+            time_limit_ms: Some(0),
+            ..budget()
+        };
+        assert!(choose_card_monte_carlo(&context, quick).unwrap().is_some());
+    }
+
+    #[test]
+    fn plays_whole_games() {
+        let monte_carlo = MonteCarloStrategy {
+            budget: SearchBudget {
+                samples: 10,
+                ..budget()
+            },
+        };
+        let mut game = Game::from_seed(3);
+        game.play_to_end(&[&monte_carlo, &DefaultStrategy]).unwrap();
+
+        let mut game = Game::with_variant(4, Variant::ThreePlayer, RuleSet::default()).unwrap();
+        let strategies: [&dyn Strategy; 3] = [&DefaultStrategy, &monte_carlo, &DefaultStrategy];
+        game.play_to_end(&strategies).unwrap();
+    }
+
+    #[test]
+    fn solves_known_hands() {
+        let round = PeggingRound::new(
+            vec![
+                vec![c!(Four, Of::Hearts), c!(Five, Of::Hearts)],
+                vec![c!(Ten, Of::Clubs)],
+            ],
+            0,
+        );
+        // the 4 then the 5 pegs the last card.  the 5 first gives away a 15.
+        let solution = solve_pegging(&round, Variant::TwoPlayer).unwrap();
+        assert_eq!(solution.best, Some(c!(Four, Of::Hearts)));
+        assert_eq!(
+            solution.values,
+            vec![(c!(Four, Of::Hearts), 1.0), (c!(Five, Of::Hearts), -1.0)]
+        );
+        assert_eq!(solution.points_lost(c!(Five, Of::Hearts)), Some(2.0));
+        assert_eq!(solution.points_lost(c!(Ten, Of::Clubs)), None);
+
+        // grading the heuristic
+        let heuristic = get_next_counted_card(vec![], round.hand(0).to_vec())
+            .unwrap()
+            .unwrap();
+        assert!(solution.points_lost(heuristic).unwrap() >= 0.0);
+    }
+
+    #[test]
+    fn averages_over_unknown_holdings() {
+        let mut game = Game::from_seed(12);
+        let strategies: [&dyn Strategy; 2] = [&DefaultStrategy, &DefaultStrategy];
+        while game.phase() != GamePhase::Pegging || game.pile().len() < 4 {
+            game.step(&strategies).unwrap();
+        }
+        let player = game.turn();
+        let context = game.context(player);
+        let knowledge = Knowledge::new(&context);
+        let needs = knowledge.needs(&context);
+        let solution = solve_pegging_unknown(&context).unwrap();
+        assert!(solution.holdings > 0);
+        assert!(solution.holdings <= holding_count(knowledge.unseen.len(), &needs));
+        if let Some(best) = solution.best {
+            assert!(context.hand.contains(&best));
+            assert_eq!(solution.points_lost(best), Some(0.0));
+        }
+        assert_eq!(
+            choose_card_exact(&context, budget()).unwrap(),
+            solution.best
+        );
+    }
+
+    #[test]
+    fn counts_holdings() {
+        let unseen: Hand = (0..6).map(Card::from_index).collect();
+        let mut count = 0;
+        for_each_holding(&unseen, &[0, 2, 1], &mut |dealt| {
+            assert_eq!(dealt.len(), 3);
+            assert_eq!(CardSet::from(dealt).len(), 3);
+            count += 1;
+        });
+        assert_eq!(count, 15 * 4);
+        assert_eq!(holding_count(6, &[0, 2, 1]), 60);
+        assert_eq!(holding_count(40, &[4]), 91_390);
+        assert_eq!(holding_count(2, &[3]), 0);
+    }
+
+    #[test]
+    fn pegs_out_to_win() {
+        let hand = [
+            c!(Two, Of::Clubs),
+            c!(Nine, Of::Spades),
+            c!(Queen, Of::Hearts),
+        ];
+        let pile = [c!(Ten, Of::Hearts), c!(Three, Of::Diamonds)];
+        let mut context = StrategyContext::from_hand(&hand, false);
+        context.pile = &pile;
+        context.scores = &[119, 119];
+        let card = choose_card_to_win(&context, budget()).unwrap();
+        assert_eq!(card, Some(c!(Two, Of::Clubs)));
+    }
+
+    #[test]
+    fn score_aware_strategy_plays_whole_games() {
+        let score_aware = ScoreAwareStrategy { budget: budget() };
+        let mut game = Game::from_seed(6);
+        game.play_to_end(&[&score_aware, &DefaultStrategy]).unwrap();
+
+        // without win probabilities for the short game it plays for points
+        let mut game = Game::with_rules(7, RuleSet::short_game()).unwrap();
+        game.play_to_end(&[&DefaultStrategy, &score_aware]).unwrap();
+    }
+
+    #[test]
+    fn exact_strategy_plays_whole_games() {
+        let exact = ExactStrategy::default();
+        let mut game = Game::from_seed(5);
+        game.play_to_end(&[&DefaultStrategy, &exact]).unwrap();
+    }
+}
@@ -0,0 +1,634 @@
+//! `record` saves a game so that it can be loaded and replayed.  Every
+//! `Game` keeps a `GameRecord` of the seed its shuffles come from, its
+//! variant and rules, and each move made, with the points the move scored
+//! and what the player claimed.  The seed decides every shuffle and cut, so
+//! the moves are enough to rebuild the game.
+//!
+//! A `Replayer` rebuilds the game at any move and checks that each recorded
+//! score is what `Game` scores, which is `score_hand` for the show and the
+//! crib and `score_counting_cards_played` for pegging.
+//!
+//! A record serializes with serde, and it also has a line-oriented text form
+//! from `Display` and `FromStr` that is easy to read and diff:
+//!
+//! ```text
+//! variant TwoPlayer
+//! rules {"crib_flush":"FiveCards","nobs":1,...}
+//! seed 42
+//! cut 7H 2C
+//! deal
+//! discard 0 5H 6D
+//! starter JC
+//! play 1 5S 0
+//! play 0 10H 2 claim 1
+//! go 1 0
+//! show 1 12
+//! crib 0 4
+//! result 1 121 97
+//! ```
+
+use crate::{
+    cards::{Card, Hand},
+    cribbage_errors::{CribbageError, CribbageErrorKind},
+    game::Game,
+    muggins::Claim,
+    rules::RuleSet,
+    variant::Variant,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// One move in a game, in the order `Game` expects them.  `points` is what
+/// the move really scored, whatever the player claimed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameMove {
+    /// the card each player cut, in seat order
+    CutForDeal {
+        cuts: Hand,
+    },
+    /// the first dealer was chosen instead of cut for
+    FirstDealer {
+        dealer: usize,
+    },
+    Deal,
+    Discard {
+        player: usize,
+        cards: Hand,
+    },
+    Starter {
+        card: Card,
+    },
+    Play {
+        player: usize,
+        card: Card,
+        claim: Option<Claim>,
+        points: u32,
+    },
+    /// `points` is the point for the go, whoever pegged it
+    Go {
+        player: usize,
+        points: u32,
+    },
+    Show {
+        player: usize,
+        claim: Option<Claim>,
+        points: u32,
+    },
+    Crib {
+        player: usize,
+        claim: Option<Claim>,
+        points: u32,
+    },
+}
+
+/// How a game ended
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameResult {
+    /// the side that won
+    pub winner: usize,
+    /// the final score of each side
+    pub scores: Vec<u32>,
+}
+
+/// Everything needed to replay a game
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub variant: Variant,
+    pub rules: RuleSet,
+    pub seed: u64,
+    pub moves: Vec<GameMove>,
+    /// `None` until the game is over
+    pub result: Option<GameResult>,
+}
+
+impl GameRecord {
+    /// a record of a game with no moves yet
+    pub fn new(seed: u64, variant: Variant, rules: RuleSet) -> GameRecord {
+        GameRecord {
+            variant,
+            rules,
+            seed,
+            moves: Vec::new(),
+            result: None,
+        }
+    }
+
+    /// Replays every move and checks the result
+    ///
+    /// # Returns
+    ///
+    /// the game as it was after the last move
+    pub fn replay(&self) -> Result<Game, CribbageError> {
+        Replayer::new(self)?.finish()
+    }
+}
+
+/// Steps through the moves of a `GameRecord`, checking each one
+#[derive(Debug, Clone)]
+pub struct Replayer<'a> {
+    record: &'a GameRecord,
+    game: Game,
+    position: usize,
+}
+
+impl<'a> Replayer<'a> {
+    /// a replayer positioned before the first move
+    pub fn new(record: &'a GameRecord) -> Result<Replayer<'a>, CribbageError> {
+        Ok(Replayer {
+            record,
+            game: Game::with_variant(record.seed, record.variant, record.rules)?,
+            position: 0,
+        })
+    }
+
+    /// the game after the moves replayed so far
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// the number of moves replayed so far
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position == self.record.moves.len()
+    }
+
+    /// Replays the next move.  It is an error if the move is illegal or
+    /// doesn't score what was recorded.  After the last move the result is
+    /// checked too.
+    ///
+    /// # Returns
+    ///
+    /// the move replayed, or `None` if there are no moves left
+    pub fn step(&mut self) -> Result<Option<&'a GameMove>, CribbageError> {
+        let record = self.record;
+        let next = match record.moves.get(self.position) {
+            Some(next) => next,
+            None => return Ok(None),
+        };
+        let number = self.position + 1;
+        self.apply(next).map_err(|e| {
+            CribbageError::new(e.error_kind, format!("move {}: {}", number, e.message))
+        })?;
+        self.position += 1;
+
+        if self.is_finished() && self.game.record().result != record.result {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!(
+                    "the game ended with {:?} but {:?} was recorded",
+                    self.game.record().result,
+                    record.result
+                ),
+            ));
+        }
+        Ok(Some(next))
+    }
+
+    /// Moves to the state after the first `position` moves.  Going back
+    /// replays the game from the start.
+    pub fn seek(&mut self, position: usize) -> Result<&Game, CribbageError> {
+        if position > self.record.moves.len() {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!(
+                    "there are only {} moves in the record",
+                    self.record.moves.len()
+                ),
+            ));
+        }
+        if position < self.position {
+            *self = Replayer::new(self.record)?;
+        }
+        while self.position < position {
+            self.step()?;
+        }
+        Ok(&self.game)
+    }
+
+    /// Replays the rest of the moves
+    ///
+    /// # Returns
+    ///
+    /// the game as it was after the last move
+    pub fn finish(mut self) -> Result<Game, CribbageError> {
+        while self.step()?.is_some() {}
+        Ok(self.game)
+    }
+
+    fn apply(&mut self, next: &GameMove) -> Result<(), CribbageError> {
+        match next {
+            GameMove::CutForDeal { cuts } => {
+                let actual = self.game.cut_for_deal()?;
+                expect("the cut for deal", &actual, cuts)
+            }
+            GameMove::FirstDealer { dealer } => self.game.choose_dealer(*dealer),
+            GameMove::Deal => self.game.deal(),
+            GameMove::Discard { player, cards } => self.game.discard(*player, cards),
+            GameMove::Starter { card } => {
+                let actual = self.game.cut_starter()?;
+                expect("the starter", &actual, card)
+            }
+            GameMove::Play {
+                player,
+                card,
+                claim,
+                points,
+            } => {
+                let (score, _) = self
+                    .game
+                    .play_card_claiming(*player, *card, claim.as_ref())?;
+                expect("the points pegged", &score.total_score, points)
+            }
+            GameMove::Go { player, points } => {
+                let go = self.game.go(*player)?;
+                let actual = go.map_or(0, |(_, score)| score.total_score);
+                expect("the points for the go", &actual, points)
+            }
+            GameMove::Show {
+                player,
+                claim,
+                points,
+            } => {
+                let (shown, score, _) = self.game.show_claiming(claim.as_ref())?;
+                expect("the player counting their hand", &shown, player)?;
+                expect("the points in the hand", &score.total_score, points)
+            }
+            GameMove::Crib {
+                player,
+                claim,
+                points,
+            } => {
+                expect("the dealer", &self.game.dealer(), player)?;
+                let (score, _) = self.game.count_crib_claiming(claim.as_ref())?;
+                expect("the points in the crib", &score.total_score, points)
+            }
+        }
+    }
+}
+
+/// an error unless what happened is what was recorded
+fn expect<T: PartialEq + fmt::Debug>(
+    what: &str,
+    actual: &T,
+    recorded: &T,
+) -> Result<(), CribbageError> {
+    if actual != recorded {
+        return Err(CribbageError::new(
+            CribbageErrorKind::BadAction,
+            format!("{} was {:?} but {:?} was recorded", what, actual, recorded),
+        ));
+    }
+    Ok(())
+}
+
+/// writes the text form of the record, one line per move
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "variant {:?}", self.variant)?;
+        writeln!(f, "rules {}", to_json(&self.rules)?)?;
+        writeln!(f, "seed {}", self.seed)?;
+        for next in self.moves.iter() {
+            match next {
+                GameMove::CutForDeal { cuts } => write!(f, "cut {}", cards_text(cuts))?,
+                GameMove::FirstDealer { dealer } => write!(f, "dealer {}", dealer)?,
+                GameMove::Deal => write!(f, "deal")?,
+                GameMove::Discard { player, cards } => {
+                    write!(f, "discard {} {}", player, cards_text(cards))?
+                }
+                GameMove::Starter { card } => write!(f, "starter {}", card)?,
+                GameMove::Play {
+                    player,
+                    card,
+                    claim,
+                    points,
+                } => {
+                    write!(f, "play {} {} {}", player, card, points)?;
+                    write_claim(f, claim)?;
+                }
+                GameMove::Go { player, points } => write!(f, "go {} {}", player, points)?,
+                GameMove::Show {
+                    player,
+                    claim,
+                    points,
+                } => {
+                    write!(f, "show {} {}", player, points)?;
+                    write_claim(f, claim)?;
+                }
+                GameMove::Crib {
+                    player,
+                    claim,
+                    points,
+                } => {
+                    write!(f, "crib {} {}", player, points)?;
+                    write_claim(f, claim)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        if let Some(result) = &self.result {
+            writeln!(
+                f,
+                "result {} {}",
+                result.winner,
+                result
+                    .scores
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, fmt::Error> {
+    serde_json::to_string(value).map_err(|_| fmt::Error)
+}
+
+fn cards_text(cards: &[Card]) -> String {
+    cards
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// a claim of just a total is written as the number, and a claim that lists
+/// its combinations as json
+fn write_claim(f: &mut fmt::Formatter<'_>, claim: &Option<Claim>) -> fmt::Result {
+    match claim {
+        None => Ok(()),
+        Some(claim) if claim.combinations.is_none() => write!(f, " claim {}", claim.total),
+        Some(claim) => write!(f, " claim {}", to_json(claim)?),
+    }
+}
+
+/// parses the text form written by `Display`.  blank lines and lines that
+/// start with `#` are skipped.  if a line can't be parsed the error has the
+/// position of the first character of that line.
+impl FromStr for GameRecord {
+    type Err = CribbageError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut record = GameRecord::new(0, Variant::default(), RuleSet::default());
+        let mut seed = None;
+        let mut start = 0;
+        for (number, line) in text.lines().enumerate() {
+            let position = start;
+            start += line.chars().count() + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            parse_line(&mut record, &mut seed, line).map_err(|message| {
+                CribbageError::at(
+                    CribbageErrorKind::ParseError,
+                    format!("line {}: {}", number + 1, message),
+                    position,
+                )
+            })?;
+        }
+        record.seed = seed.ok_or_else(|| {
+            CribbageError::new(
+                CribbageErrorKind::ParseError,
+                "a game record needs a seed".to_string(),
+            )
+        })?;
+        Ok(record)
+    }
+}
+
+fn parse_line(record: &mut GameRecord, seed: &mut Option<u64>, line: &str) -> Result<(), String> {
+    let (keyword, rest) = match line.split_once(char::is_whitespace) {
+        Some((keyword, rest)) => (keyword, rest.trim()),
+        None => (line, ""),
+    };
+    match keyword {
+        "variant" => {
+            record.variant = serde_json::from_value(serde_json::Value::String(rest.to_string()))
+                .map_err(|_| format!("{:?} is not a variant", rest))?;
+            return Ok(());
+        }
+        "rules" => {
+            record.rules = serde_json::from_str(rest).map_err(|e| e.to_string())?;
+            return Ok(());
+        }
+        "seed" => {
+            *seed = Some(
+                rest.parse()
+                    .map_err(|_| format!("{:?} is not a seed", rest))?,
+            );
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    // the claim, if there is one, is the rest of the line
+    let (rest, claim) = match rest.split_once("claim") {
+        Some((rest, claim)) => (rest.trim(), Some(parse_claim(claim.trim())?)),
+        None => (rest, None),
+    };
+    let words: Vec<&str> = rest.split_whitespace().collect();
+    let number = |i: usize| -> Result<u32, String> {
+        let word = words
+            .get(i)
+            .ok_or(format!("{} is missing a number", keyword))?;
+        word.parse()
+            .map_err(|_| format!("{:?} is not a number", word))
+    };
+    let player = |i: usize| number(i).map(|n| n as usize);
+    let cards = |from: usize| -> Result<Hand, String> {
+        words
+            .iter()
+            .skip(from)
+            .map(|word| Card::from_string(word).map_err(|e| e.message))
+            .collect()
+    };
+    let card = |i: usize| -> Result<Card, String> {
+        let word = words
+            .get(i)
+            .ok_or(format!("{} is missing a card", keyword))?;
+        Card::from_string(word).map_err(|e| e.message)
+    };
+
+    let next = match keyword {
+        "result" => {
+            record.result = Some(GameResult {
+                winner: player(0)?,
+                scores: (1..words.len()).map(number).collect::<Result<_, _>>()?,
+            });
+            return Ok(());
+        }
+        "cut" => GameMove::CutForDeal { cuts: cards(0)? },
+        "dealer" => GameMove::FirstDealer { dealer: player(0)? },
+        "deal" => GameMove::Deal,
+        "discard" => GameMove::Discard {
+            player: player(0)?,
+            cards: cards(1)?,
+        },
+        "starter" => GameMove::Starter { card: card(0)? },
+        "play" => GameMove::Play {
+            player: player(0)?,
+            card: card(1)?,
+            claim,
+            points: number(2)?,
+        },
+        "go" => GameMove::Go {
+            player: player(0)?,
+            points: number(1)?,
+        },
+        "show" => GameMove::Show {
+            player: player(0)?,
+            claim,
+            points: number(1)?,
+        },
+        "crib" => GameMove::Crib {
+            player: player(0)?,
+            claim,
+            points: number(1)?,
+        },
+        _ => return Err(format!("{:?} is not a move", keyword)),
+    };
+    record.moves.push(next);
+    Ok(())
+}
+
+fn parse_claim(claim: &str) -> Result<Claim, String> {
+    if claim.starts_with('{') {
+        serde_json::from_str(claim).map_err(|e| e.to_string())
+    } else {
+        claim
+            .parse()
+            .map(Claim::total)
+            .map_err(|_| format!("{:?} is not a claim", claim))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{Rank::*, Suit as Of};
+    use crate::game::GamePhase;
+    use crate::new_card as c;
+    use crate::strategy::{DefaultStrategy, Strategy};
+
+    fn played_game(seed: u64) -> Game {
+        let mut game = Game::from_seed(seed);
+        game.play_to_end(&[&DefaultStrategy, &DefaultStrategy])
+            .unwrap();
+        game
+    }
+
+    #[test]
+    fn replays_a_whole_game() {
+        let game = played_game(7);
+        let record = game.record();
+        let result = record.result.as_ref().unwrap();
+        assert_eq!(Some(result.winner), game.winner());
+        assert_eq!(result.scores, game.scores());
+
+        let replayed = record.replay().unwrap();
+        assert_eq!(replayed.scores(), game.scores());
+        assert_eq!(replayed.record(), record);
+    }
+
+    #[test]
+    fn text_and_json_round_trip() {
+        let record = played_game(8).record().clone();
+        let text = record.to_string();
+        assert!(text.starts_with("variant TwoPlayer\nrules {"));
+        assert_eq!(text.parse::<GameRecord>().unwrap(), record);
+
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(serde_json::from_str::<GameRecord>(&json).unwrap(), record);
+    }
+
+    #[test]
+    fn claims_are_recorded() {
+        let mut game = Game::from_seed(9);
+        let strategies: [&dyn Strategy; 2] = [&DefaultStrategy, &DefaultStrategy];
+        while game.phase() != GamePhase::Show {
+            game.step(&strategies).unwrap();
+        }
+        game.show_claiming(Some(&Claim::total(0))).unwrap();
+        let (_, score) = game.show().unwrap();
+        game.count_crib_claiming(Some(&Claim::all_of(&score)))
+            .unwrap();
+
+        let record = game.record();
+        let text = record.to_string();
+        assert!(text.contains(" claim 0\n"));
+        assert!(text.contains(" claim {"));
+        let parsed: GameRecord = text.parse().unwrap();
+        assert_eq!(&parsed, record);
+        assert_eq!(parsed.replay().unwrap().scores(), game.scores());
+    }
+
+    #[test]
+    fn seek_rebuilds_any_move() {
+        let record = played_game(10).record().clone();
+        let mut replayer = Replayer::new(&record).unwrap();
+        let starter = record
+            .moves
+            .iter()
+            .position(|m| matches!(m, GameMove::Starter { .. }))
+            .unwrap();
+
+        let game = replayer.seek(starter + 1).unwrap();
+        assert_eq!(game.phase(), GamePhase::Pegging);
+        assert!(game.starter().is_some());
+        let game = replayer.seek(2).unwrap();
+        assert_eq!(game.phase(), GamePhase::Discard);
+        assert_eq!(replayer.position(), 2);
+        assert!(replayer.seek(record.moves.len() + 1).is_err());
+    }
+
+    #[test]
+    fn wrong_scores_are_caught() {
+        let mut record = played_game(11).record().clone();
+        let show = record
+            .moves
+            .iter()
+            .position(|m| matches!(m, GameMove::Show { .. }))
+            .unwrap();
+        if let GameMove::Show { points, .. } = &mut record.moves[show] {
+            *points += 1;
+        }
+        let err = record.replay().unwrap_err();
+        assert!(err
+            .message
+            .starts_with(&format!("move {}: the points in the hand", show + 1)));
+
+        let mut record = played_game(11).record().clone();
+        record.result.as_mut().unwrap().scores[0] += 1;
+        assert!(record.replay().is_err());
+    }
+
+    #[test]
+    fn parse_errors() {
+        let text = "variant TwoPlayer\nseed 3\nplay 0 XX 2\n";
+        let err = text.parse::<GameRecord>().unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::ParseError);
+        assert_eq!(err.position, Some(25));
+        assert!(err.message.starts_with("line 3"));
+
+        assert!("deal\n".parse::<GameRecord>().is_err());
+        assert!("seed 3\nshuffle\n".parse::<GameRecord>().is_err());
+
+        let record: GameRecord = "# a comment\n\nseed 3\ndeal\nstarter 5H\n".parse().unwrap();
+        assert_eq!(record.variant, Variant::TwoPlayer);
+        assert_eq!(
+            record.moves,
+            vec![
+                GameMove::Deal,
+                GameMove::Starter {
+                    card: c!(Five, Of::Hearts)
+                }
+            ]
+        );
+    }
+}
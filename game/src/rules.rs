@@ -0,0 +1,169 @@
+//! `rules` collects the house rules that vary from table to table.  A
+//! `RuleSet` is passed to scoring, pegging and `Game`, and it is
+//! serializable so that the API and saved games can record which rules
+//! were used.  `RuleSet::default()` is the standard game to 121.
+
+use crate::cribbage_errors::{CribbageError, CribbageErrorKind};
+use serde::{Deserialize, Serialize};
+
+/// the number of points needed to win the standard game
+pub const GAME_LENGTH: u32 = 121;
+
+/// a player who loses without passing this many points has been skunked
+pub const SKUNK_LINE: u32 = 91;
+
+/// a player who loses without passing this many points has been double skunked
+pub const DOUBLE_SKUNK_LINE: u32 = 61;
+
+/// the number of points the dealer gets when the starter is a Jack
+pub const HIS_HEELS: u32 = 2;
+
+/// the number of points for the Jack of the starter's suit
+pub const NOBS: u32 = 1;
+
+/// the length of the short game, usually played with five cards
+pub const SHORT_GAME_LENGTH: u32 = 61;
+
+/// the skunk line of the short game.  there is no double skunk.
+pub const SHORT_SKUNK_LINE: u32 = 31;
+
+/// How many cards of the crib have to share a suit for a flush
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CribFlush {
+    /// all four crib cards and the starter, the standard rule
+    FiveCards,
+    /// four cards, the same as a hand
+    FourCards,
+}
+
+/// The house rules for a game
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleSet {
+    pub crib_flush: CribFlush,
+    /// the points for nobs.  0 turns it off.
+    pub nobs: u32,
+    /// the points the dealer pegs for his heels.  0 turns it off.
+    pub his_heels: u32,
+    /// whether a run can go round the corner from King to Ace, e.g.
+    /// Queen, King, Ace
+    pub wrap_runs: bool,
+    /// whether a card played after a 31 pairs with the last cards of the
+    /// count before it
+    pub pairs_across_thirty_one: bool,
+    pub game_length: u32,
+    /// a loser who doesn't pass this many points is skunked.  0 turns off
+    /// skunks.
+    pub skunk_line: u32,
+    /// a loser who doesn't pass this many points is double skunked.  0
+    /// turns off double skunks.
+    pub double_skunk_line: u32,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet::standard()
+    }
+}
+
+impl RuleSet {
+    /// the standard rules for a game to 121
+    pub fn standard() -> RuleSet {
+        RuleSet {
+            crib_flush: CribFlush::FiveCards,
+            nobs: NOBS,
+            his_heels: HIS_HEELS,
+            wrap_runs: false,
+            pairs_across_thirty_one: false,
+            game_length: GAME_LENGTH,
+            skunk_line: SKUNK_LINE,
+            double_skunk_line: DOUBLE_SKUNK_LINE,
+        }
+    }
+
+    /// the standard rules for a game to 61
+    pub fn short_game() -> RuleSet {
+        RuleSet {
+            game_length: SHORT_GAME_LENGTH,
+            skunk_line: SHORT_SKUNK_LINE,
+            double_skunk_line: 0,
+            ..RuleSet::standard()
+        }
+    }
+
+    /// checks that the game can be won and the skunk lines are in order
+    pub fn validate(&self) -> Result<(), CribbageError> {
+        if self.game_length == 0 {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                "the game length must be more than 0".to_string(),
+            ));
+        }
+        if self.skunk_line > self.game_length || self.double_skunk_line > self.skunk_line {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadAction,
+                format!(
+                    "the skunk lines {} and {} must be in order and no more than the game length {}",
+                    self.double_skunk_line, self.skunk_line, self.game_length
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// true if a loser with `score` points has been skunked
+    pub fn is_skunk(&self, score: u32) -> bool {
+        score < self.skunk_line
+    }
+
+    /// true if a loser with `score` points has been double skunked
+    pub fn is_double_skunk(&self, score: u32) -> bool {
+        score < self.double_skunk_line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skunks() {
+        let rules = RuleSet::standard();
+        assert!(rules.is_skunk(90));
+        assert!(!rules.is_skunk(91));
+        assert!(rules.is_double_skunk(60));
+        assert!(!rules.is_double_skunk(61));
+
+        let rules = RuleSet::short_game();
+        assert!(rules.is_skunk(30));
+        assert!(!rules.is_double_skunk(0));
+        assert!(rules.validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let rules = RuleSet {
+            game_length: 61,
+            ..RuleSet::standard()
+        };
+        assert!(rules.validate().is_err());
+        let rules = RuleSet {
+            game_length: 0,
+            skunk_line: 0,
+            double_skunk_line: 0,
+            ..RuleSet::standard()
+        };
+        assert!(rules.validate().is_err());
+    }
+
+    #[test]
+    fn missing_fields_are_standard() {
+        let rules: RuleSet =
+            serde_json::from_str(r#"{"wrap_runs": true, "crib_flush": "FourCards"}"#).unwrap();
+        assert!(rules.wrap_runs);
+        assert_eq!(rules.crib_flush, CribFlush::FourCards);
+        assert_eq!(rules.game_length, GAME_LENGTH);
+        let json = serde_json::to_string(&rules).unwrap();
+        assert_eq!(serde_json::from_str::<RuleSet>(&json).unwrap(), rules);
+    }
+}
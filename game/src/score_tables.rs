@@ -0,0 +1,218 @@
+//! `score_tables` precomputes the points for fifteens, pairs and runs, which
+//! only depend on the ranks of the cards.  Every multiset of up to five ranks
+//! gets an entry, indexed by its rank in colexicographic order, so scoring
+//! a hand is a table lookup plus the flush and nobs checks.
+
+use crate::card_set::CardSet;
+use lazy_static::lazy_static;
+
+const RANKS: usize = 13;
+
+/// the largest number of cards that are looked up.  bigger sets are
+/// counted directly.
+const MAX_TABLE_CARDS: usize = 5;
+
+/// BINOMIAL[n][k] is n choose k
+const BINOMIAL: [[usize; MAX_TABLE_CARDS + 1]; RANKS + MAX_TABLE_CARDS] = binomials();
+
+lazy_static! {
+    /// RANK_POINTS[k] has the points for every multiset of k ranks
+    static ref RANK_POINTS: Vec<Vec<u8>> =
+        (0..=MAX_TABLE_CARDS).map(|k| build_table(k, false)).collect();
+
+    /// the same as RANK_POINTS when runs can wrap from King to Ace
+    static ref WRAPPED_RANK_POINTS: Vec<Vec<u8>> =
+        (0..=MAX_TABLE_CARDS).map(|k| build_table(k, true)).collect();
+}
+
+/// the points for the fifteens, pairs and runs in `cards`.  when
+/// `wrap_runs` is set, runs can go round the corner from King to Ace.
+pub(crate) fn rank_points(cards: CardSet, wrap_runs: bool) -> u32 {
+    let counts = cards.rank_counts();
+    let size = cards.len();
+    if size > MAX_TABLE_CARDS {
+        return count_rank_points(&counts, wrap_runs);
+    }
+    let table: &[Vec<u8>] = if wrap_runs {
+        &WRAPPED_RANK_POINTS
+    } else {
+        &RANK_POINTS
+    };
+    table[size][multiset_index(&counts)] as u32
+}
+
+/// the position of the multiset of ranks with `counts` among all of the
+/// multisets of the same size.  with the ranks in order r0 <= r1 <= ...,
+/// the numbers r0 < r1 + 1 < r2 + 2 < ... are a combination, and this is the
+/// colexicographic rank of that combination.
+fn multiset_index(counts: &[u8; RANKS]) -> usize {
+    let mut index = 0;
+    let mut position = 0;
+    for (rank, count) in counts.iter().enumerate() {
+        for _ in 0..*count {
+            index += BINOMIAL[rank + position][position + 1];
+            position += 1;
+        }
+    }
+    index
+}
+
+fn build_table(size: usize, wrap_runs: bool) -> Vec<u8> {
+    let mut table = vec![0; BINOMIAL[RANKS + size - 1][size]];
+    let mut counts = [0; RANKS];
+    fill_table(&mut table, &mut counts, 0, size, wrap_runs);
+    table
+}
+
+/// adds an entry for every way to put `remaining` more cards in the ranks
+/// from `rank` up
+fn fill_table(
+    table: &mut [u8],
+    counts: &mut [u8; RANKS],
+    rank: usize,
+    remaining: usize,
+    wrap_runs: bool,
+) {
+    if remaining == 0 {
+        table[multiset_index(counts)] = count_rank_points(counts, wrap_runs) as u8;
+        return;
+    }
+    if rank == RANKS {
+        return;
+    }
+    for count in 0..=remaining.min(4) {
+        counts[rank] = count as u8;
+        fill_table(table, counts, rank + 1, remaining - count, wrap_runs);
+    }
+    counts[rank] = 0;
+}
+
+/// counts the points for fifteens, pairs and runs from the number of cards
+/// of each rank
+fn count_rank_points(counts: &[u8; RANKS], wrap_runs: bool) -> u32 {
+    let mut points = 0;
+
+    // ways[n] is the number of ways to make a count of n from the ranks seen so far
+    let mut ways = [0u32; 16];
+    ways[0] = 1;
+    for (rank, count) in counts.iter().enumerate() {
+        let value = (rank + 1).min(10);
+        for total in (value..16).rev() {
+            let mut choose = 1;
+            for used in 1..=*count as usize {
+                if used * value > total {
+                    break;
+                }
+                choose = choose * (*count as u32 + 1 - used as u32) / used as u32;
+                ways[total] += choose * ways[total - used * value];
+            }
+        }
+    }
+    points += 2 * ways[15];
+
+    // each pair of cards of the same rank is 2 points
+    points += counts
+        .iter()
+        .map(|n| *n as u32 * (*n as u32).saturating_sub(1))
+        .sum::<u32>();
+
+    // every run is counted once for each way to pick one card of each rank.
+    // wrapped runs are found by starting from a missing rank instead of the Ace.
+    let start = match wrap_runs {
+        true => counts.iter().position(|n| *n == 0).unwrap_or(0),
+        false => 0,
+    };
+    let mut length = 0;
+    let mut ways = 1;
+    for i in 0..=RANKS {
+        // the extra step past the last rank ends the last run
+        let count = match i {
+            RANKS => 0,
+            _ => counts[(start + i) % RANKS] as u32,
+        };
+        if count > 0 {
+            length += 1;
+            ways *= count;
+        } else {
+            if length >= 3 {
+                points += length * ways;
+            }
+            length = 0;
+            ways = 1;
+        }
+    }
+    points
+}
+
+const fn binomials() -> [[usize; MAX_TABLE_CARDS + 1]; RANKS + MAX_TABLE_CARDS] {
+    let mut table = [[0; MAX_TABLE_CARDS + 1]; RANKS + MAX_TABLE_CARDS];
+    let mut n = 0;
+    while n < RANKS + MAX_TABLE_CARDS {
+        table[n][0] = 1;
+        let mut k = 1;
+        while k <= MAX_TABLE_CARDS && n > 0 {
+            table[n][k] = table[n - 1][k - 1] + table[n - 1][k];
+            k += 1;
+        }
+        n += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_sizes() {
+        // multisets of k ranks from 13 is 13 + k - 1 choose k
+        let sizes: Vec<usize> = RANK_POINTS.iter().map(|t| t.len()).collect();
+        assert_eq!(sizes, vec![1, 13, 91, 455, 1820, 6188]);
+    }
+
+    #[test]
+    fn indices_are_unique() {
+        let mut seen = vec![false; 1820];
+        let mut counts = [0u8; RANKS];
+        for a in 0..RANKS {
+            for b in a..RANKS {
+                for c in b..RANKS {
+                    for d in c..RANKS {
+                        counts.iter_mut().for_each(|n| *n = 0);
+                        for rank in [a, b, c, d] {
+                            counts[rank] += 1;
+                        }
+                        let index = multiset_index(&counts);
+                        assert!(!seen[index]);
+                        seen[index] = true;
+                    }
+                }
+            }
+        }
+        assert!(seen.iter().all(|s| *s));
+    }
+
+    #[test]
+    fn twenty_nine() {
+        // 5, 5, 5, 5, Jack: eight fifteens and four of a kind
+        let mut counts = [0u8; RANKS];
+        counts[4] = 4;
+        counts[10] = 1;
+        assert_eq!(RANK_POINTS[5][multiset_index(&counts)], 28);
+        assert_eq!(count_rank_points(&counts, false), 28);
+    }
+
+    #[test]
+    fn wrapped_runs() {
+        // Queen, King, King, Ace, Two is a double run of four
+        let mut counts = [0u8; RANKS];
+        counts[11] = 1;
+        counts[12] = 2;
+        counts[0] = 1;
+        counts[1] = 1;
+        assert_eq!(count_rank_points(&counts, false), 2);
+        assert_eq!(count_rank_points(&counts, true), 2 + 8);
+        assert_eq!(RANK_POINTS[5][multiset_index(&counts)], 2);
+        assert_eq!(WRAPPED_RANK_POINTS[5][multiset_index(&counts)], 10);
+    }
+}
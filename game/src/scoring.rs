@@ -2,8 +2,11 @@
 //! cribbage hand.
 
 use crate::{
+    card_set::CardSet,
     cards::{Card, Hand, Rank, Suit},
     combinator::all_combinations_of_min_size,
+    rules::{CribFlush, RuleSet, HIS_HEELS, NOBS},
+    score_tables::rank_points,
 };
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
@@ -17,6 +20,9 @@ pub enum CombinationKind {
     Run,
     SuitMatch,
     ThirtyOne,
+    Go,
+    LastCard,
+    HisHeels,
 }
 
 /// Some cribbage scoring combinations have specific names
@@ -36,6 +42,10 @@ pub enum CombinationName {
     RunOfSix,
     RunOfSeven,
     ThirtyOne,
+    Go,
+    LastCard,
+    HisHeels,
+    FlushOfThree,
 }
 
 /// `Combination` is a record of a single scoring combination of cards.
@@ -61,45 +71,137 @@ pub struct Score {
 ///
 /// # Assumptions
 ///
-/// * `hand` has four unique and valid cards
+/// * `hand` has four unique and valid cards, or three in five card cribbage
 /// * `starter` is a valid `Card` that is unique when combined with `hand`
 /// * This is also used when picking a hand, in which case 4 cards are passed in without a starter
 //  *
 pub fn score_hand(hand: Hand, starter: Option<Card>, is_crib: bool) -> Score {
+    score_hand_with_rules(hand, starter, is_crib, &RuleSet::default())
+}
+
+/// `score_hand` with the house `rules` for flushes, nobs and runs
+pub fn score_hand_with_rules(
+    hand: Hand,
+    starter: Option<Card>,
+    is_crib: bool,
+    rules: &RuleSet,
+) -> Score {
     let mut vector = hand.clone();
     let mut s: Score = Score::new();
     if let Some(starter) = starter {
         vector.push(starter);
-        s = nob_score(hand, starter);
+        s = nob_score_with_rules(hand.clone(), starter, rules);
     }
     vector.sort(); // ordered by rank rirst
 
+    // a three card hand is a flush without the starter
+    if hand.len() == 3 && !is_crib {
+        if let Some(flush) = score_flush_of_three(&hand) {
+            s.tally(vec![flush]);
+        }
+    }
+
     all_combinations_of_min_size(vector, 2)
-        .filter_map(|cards| score(cards, is_crib))
+        .filter_map(|cards| score_with_rules(cards, is_crib, rules))
         .fold(&mut s, |score, combis| score.tally(combis))
         .clone()
 }
 
+/// Calculates the same points as `score_hand(...).total_score`, but only the
+/// total, so nothing is allocated.  Fifteens, pairs and runs come from
+/// precomputed tables.  This is the version to use when scoring many hands.
+pub fn score_points(hand: CardSet, starter: Option<Card>, is_crib: bool) -> u32 {
+    score_points_with_rules(hand, starter, is_crib, &RuleSet::default())
+}
+
+/// `score_points` with the house `rules` for flushes, nobs and runs
+pub fn score_points_with_rules(
+    hand: CardSet,
+    starter: Option<Card>,
+    is_crib: bool,
+    rules: &RuleSet,
+) -> u32 {
+    let cards = match starter {
+        Some(starter) => hand.with(starter),
+        None => hand,
+    };
+    let mut points = rank_points(cards, rules.wrap_runs);
+
+    let flush = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades]
+        .iter()
+        .map(|suit| cards.suit_mask(*suit).count_ones())
+        .max()
+        .unwrap_or(0);
+    if flush >= 5 || (flush == 4 && !needs_five_card_flush(is_crib, rules)) {
+        points += flush;
+    } else if hand.len() == 3 && !is_crib && hand_is_one_suit(hand) {
+        points += 3;
+    }
+
+    if let Some(starter) = starter {
+        if hand.contains(Card::new(Rank::Jack, starter.suit)) {
+            points += rules.nobs;
+        }
+    }
+    points
+}
+
 /// A Nob is scored if a Jack in the `hand` matches the suit of the `starter`.
 ///
 /// # Returns
 ///
 /// An empty `Score` or a `Score` that contains a single `Nob` `Combination`
 pub fn nob_score(hand: Hand, starter: Card) -> Score {
+    nob_score_with_rules(hand, starter, &RuleSet::default())
+}
+
+/// `nob_score` worth `rules.nobs` points.  nothing is scored when nobs is
+/// turned off.
+pub fn nob_score_with_rules(hand: Hand, starter: Card, rules: &RuleSet) -> Score {
     let mut score = Score::new();
+    if rules.nobs == 0 {
+        return score;
+    }
     for c in hand {
         if c.rank == Rank::Jack && c.suit == starter.suit {
-            score.add_combination(Combination::new(CombinationKind::Nob, vec![c, starter]));
+            let mut nob = Combination::new(CombinationKind::Nob, vec![c, starter]);
+            nob.points = rules.nobs;
+            score.tally(vec![nob]);
             return score;
         };
     }
     score
 }
 
+/// The dealer pegs for his heels when the `starter` is a Jack.
+///
+/// # Returns
+///
+/// An empty `Score` or a `Score` that contains a single `HisHeels`
+/// `Combination` worth `rules.his_heels`
+pub fn his_heels_score(starter: Card, rules: &RuleSet) -> Score {
+    let mut score = Score::new();
+    if starter.rank == Rank::Jack && rules.his_heels > 0 {
+        let mut heels = Combination::new(CombinationKind::HisHeels, vec![starter]);
+        heels.points = rules.his_heels;
+        score.tally(vec![heels]);
+    }
+    score
+}
+
 /// `score` evaluates `cards` against each of the cribbage hand scoring
 /// combinations and returns all scoring combinations that are
 /// identified.
 pub fn score(cards: Vec<Card>, is_crib: bool) -> Option<Vec<Combination>> {
+    score_with_rules(cards, is_crib, &RuleSet::default())
+}
+
+/// `score` with the house `rules` for flushes and runs
+pub fn score_with_rules(
+    cards: Vec<Card>,
+    is_crib: bool,
+    rules: &RuleSet,
+) -> Option<Vec<Combination>> {
     let mut combis = Vec::new();
 
     if let Some(c) = score_fifteen(cards.clone()) {
@@ -108,10 +210,10 @@ pub fn score(cards: Vec<Card>, is_crib: bool) -> Option<Vec<Combination>> {
     if let Some(c) = score_pair(cards.clone()) {
         combis.push(c)
     }
-    if let Some(c) = score_run(cards.clone()) {
+    if let Some(c) = score_run_with_rules(cards.clone(), rules) {
         combis.push(c)
     }
-    if let Some(c) = score_flush(cards, is_crib) {
+    if let Some(c) = score_flush(cards, needs_five_card_flush(is_crib, rules)) {
         combis.push(c)
     }
     match combis.len() {
@@ -161,10 +263,63 @@ pub fn score_run(cards: Vec<Card>) -> Option<Combination> {
     }
 }
 
+/// `score_run` that also allows runs that go round the corner from King to
+/// Ace when `rules.wrap_runs` is set.  `cards` are sorted by rank, and a
+/// wrapped run is returned starting from its lowest card, e.g. Queen,
+/// King, Ace.
+pub fn score_run_with_rules(cards: Vec<Card>, rules: &RuleSet) -> Option<Combination> {
+    if !rules.wrap_runs || cards.len() < 3 {
+        return score_run(cards);
+    }
+    let ranks = 13;
+    let len = cards.len();
+    // ranks in a circle are a run when there is exactly one gap between them
+    let gaps: Vec<usize> = (0..len)
+        .filter(|i| {
+            let step =
+                (cards[(i + 1) % len].rank as usize + ranks - cards[*i].rank as usize) % ranks;
+            step != 1
+        })
+        .collect();
+    match gaps.as_slice() {
+        [gap] if len < ranks => {
+            let start = (gap + 1) % len;
+            let run = cards[start..]
+                .iter()
+                .chain(&cards[..start])
+                .copied()
+                .collect();
+            Some(Combination::new(CombinationKind::Run, run))
+        }
+        _ => None,
+    }
+}
+
+/// the three cards of a hand in five card cribbage are a flush when they
+/// share a `Suit`, whatever the starter is
+fn score_flush_of_three(hand: &[Card]) -> Option<Combination> {
+    match hand.iter().all(|c| c.suit == hand[0].suit) {
+        true => Some(Combination::new(CombinationKind::SuitMatch, hand.to_vec())),
+        false => None,
+    }
+}
+
+/// true if every card in `hand` has the same suit
+fn hand_is_one_suit(hand: CardSet) -> bool {
+    [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades]
+        .iter()
+        .any(|suit| hand.suit_mask(*suit).count_ones() as usize == hand.len())
+}
+
+/// true if a flush needs all five cards
+fn needs_five_card_flush(is_crib: bool, rules: &RuleSet) -> bool {
+    is_crib && rules.crib_flush == CribFlush::FiveCards
+}
+
 /// If `cards` has at least four cards and they all share a `Suit`, returns
-/// a `Flush` `Combination`.  a crib has to have 5 cards of the same suit,
-/// a regular hand can have only 4
-fn score_flush(cards: Vec<Card>, is_crib: bool) -> Option<Combination> {
+/// a `Flush` `Combination`.  a crib has to have 5 cards of the same suit
+/// (under the standard rules), a regular hand can have only 4
+fn score_flush(cards: Vec<Card>, five_cards: bool) -> Option<Combination> {
     let len = cards.len();
 
     match len {
@@ -172,7 +327,7 @@ fn score_flush(cards: Vec<Card>, is_crib: bool) -> Option<Combination> {
             return None;
         }
         4 => {
-            if is_crib {
+            if five_cards {
                 return None;
             }
         }
@@ -204,6 +359,64 @@ impl Score {
         self.combinations.iter().fold(0, |p, c| p + c.points)
     }
 
+    /// Returns the count the way it is said at the table, in the traditional
+    /// order of fifteens, pairs, runs, flushes and nobs.  Each combination
+    /// is followed by its cards, e.g.
+    /// "fifteen 2 (FiveOfHearts, TenOfClubs), a pair is 4 (FiveOfHearts, FiveOfClubs)"
+    pub fn explain(&self) -> String {
+        let mut combinations: Vec<&Combination> = self.combinations.iter().collect();
+        combinations.sort_by_key(|c| Combination::count_order(c.kind));
+
+        let mut total = 0;
+        let mut previous: Option<CombinationName> = None;
+        let mut phrases = Vec::new();
+        for combi in combinations {
+            total += combi.points;
+            let phrase = match combi.name {
+                CombinationName::Fifteen => format!("fifteen {}", total),
+                CombinationName::Pair => format!("a pair is {}", total),
+                CombinationName::RoyalPair => format!("three of a kind is {}", total),
+                CombinationName::DoubleRoyalPair => format!("four of a kind is {}", total),
+                CombinationName::RunOfThree
+                | CombinationName::RunOfFour
+                | CombinationName::RunOfFive
+                | CombinationName::RunOfSix
+                | CombinationName::RunOfSeven => {
+                    // the runs of a double (or triple) run are counted one at a time
+                    let article = if previous == Some(combi.name) {
+                        "another"
+                    } else {
+                        "a"
+                    };
+                    format!(
+                        "{} run of {} is {}",
+                        article,
+                        number_name(combi.cards.len()),
+                        total
+                    )
+                }
+                CombinationName::FlushOfThree
+                | CombinationName::FlushOfFour
+                | CombinationName::FlushOfFive => {
+                    format!("a flush of {} is {}", number_name(combi.cards.len()), total)
+                }
+                CombinationName::Nob => format!("nobs is {}", total),
+                CombinationName::ThirtyOne => format!("thirty-one for {}", total),
+                CombinationName::Go => format!("one for the go is {}", total),
+                CombinationName::LastCard => format!("one for last card is {}", total),
+                CombinationName::HisHeels => format!("his heels is {}", total),
+            };
+            let cards: Vec<String> = combi.cards.iter().map(|c| c.name()).collect();
+            phrases.push(format!("{} ({})", phrase, cards.join(", ")));
+            previous = Some(combi.name);
+        }
+
+        match phrases.len() {
+            0 => "no points".to_string(),
+            _ => phrases.join(", "),
+        }
+    }
+
     /// `tally` adds the `Combination`s in `combis` to `score` one at a time,
     /// so that combinations that are subsumed by other combinations are
     /// handled correctly.
@@ -222,7 +435,11 @@ impl Score {
     /// replaces it.
     fn add_combination(&mut self, combi: Combination) {
         match combi.kind {
-            CombinationKind::Nob | CombinationKind::Fifteen => self.combinations.push(combi),
+            CombinationKind::Nob
+            | CombinationKind::Fifteen
+            | CombinationKind::Go
+            | CombinationKind::LastCard
+            | CombinationKind::HisHeels => self.combinations.push(combi),
             _ => {
                 let mut subsumed = false;
                 self.combinations = self
@@ -314,11 +531,30 @@ impl Combination {
                 _ => panic!("How did you get here?"),
             },
             CombinationKind::SuitMatch => match count {
+                3 => CombinationName::FlushOfThree,
                 4 => CombinationName::FlushOfFour,
                 5 => CombinationName::FlushOfFive,
                 _ => panic!("How did you get here?"),
             },
             CombinationKind::ThirtyOne => CombinationName::ThirtyOne,
+            CombinationKind::Go => CombinationName::Go,
+            CombinationKind::LastCard => CombinationName::LastCard,
+            CombinationKind::HisHeels => CombinationName::HisHeels,
+        }
+    }
+
+    /// the order combinations are counted in when a hand is shown
+    fn count_order(kind: CombinationKind) -> usize {
+        match kind {
+            CombinationKind::Fifteen => 0,
+            CombinationKind::RankMatch => 1,
+            CombinationKind::Run => 2,
+            CombinationKind::SuitMatch => 3,
+            CombinationKind::Nob => 4,
+            CombinationKind::ThirtyOne => 5,
+            CombinationKind::Go => 6,
+            CombinationKind::LastCard => 7,
+            CombinationKind::HisHeels => 8,
         }
     }
 
@@ -326,11 +562,12 @@ impl Combination {
     /// points.
     fn points(name: CombinationName) -> u32 {
         match name {
-            CombinationName::Nob => 1,
+            CombinationName::Nob => NOBS,
             CombinationName::Fifteen => 2,
             CombinationName::Pair => 2,
             CombinationName::RunOfThree => 3,
             CombinationName::RunOfFour => 4,
+            CombinationName::FlushOfThree => 3,
             CombinationName::FlushOfFour => 4,
             CombinationName::RunOfFive => 5,
             CombinationName::FlushOfFive => 5,
@@ -339,14 +576,314 @@ impl Combination {
             CombinationName::RunOfSix => 6,
             CombinationName::RunOfSeven => 7,
             CombinationName::ThirtyOne => 2,
+            CombinationName::Go => 1,
+            CombinationName::LastCard => 1,
+            CombinationName::HisHeels => HIS_HEELS,
         }
     }
 }
 
+impl std::fmt::Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.explain())
+    }
+}
+
+/// the word for the number of cards in a run or flush
+fn number_name(count: usize) -> &'static str {
+    match count {
+        3 => "three",
+        4 => "four",
+        5 => "five",
+        6 => "six",
+        7 => "seven",
+        _ => "many",
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::cards::{Card, Rank::*, Suit as Of};
+    use crate::card_set::CardSet;
+    use crate::cards::{Card, Deck, Hand, Rank::*, Suit as Of, DECK_SIZE, RANKS};
     use crate::new_card as c;
+    use crate::rules::{CribFlush, RuleSet};
+    use rayon::prelude::*;
+
+    #[test]
+    fn score_points_matches_score_hand() {
+        for seed in 0..5000 {
+            let mut deck = Deck::from_seed(seed);
+            let hand = deck.deal(1, 4).unwrap().remove(0);
+            let starter = deck.draw().unwrap();
+            let set = CardSet::from(hand.as_slice());
+            for is_crib in [false, true] {
+                assert_eq!(
+                    super::score_points(set, Some(starter), is_crib),
+                    super::score_hand(hand.clone(), Some(starter), is_crib).total_score,
+                    "{:?} {} {}",
+                    hand,
+                    starter,
+                    is_crib
+                );
+            }
+            assert_eq!(
+                super::score_points(set, None, false),
+                super::score_hand(hand.clone(), None, false).total_score
+            );
+        }
+        let hand = [
+            c!(Five, Of::Hearts),
+            c!(Five, Of::Clubs),
+            c!(Five, Of::Spades),
+            c!(Jack, Of::Diamonds),
+        ];
+        let points = super::score_points(
+            CardSet::from(&hand[..]),
+            Some(c!(Five, Of::Diamonds)),
+            false,
+        );
+        assert_eq!(points, 29);
+    }
+
+    /// the fifteens, pairs and runs come from tables indexed by the ranks, so
+    /// checking every multiset of five ranks covers every entry.  each rank
+    /// gets the next suit for each copy of it, and each card takes a turn as
+    /// the starter.
+    #[test]
+    fn score_points_matches_score_hand_for_every_rank_multiset() {
+        fn check(ranks: &mut Vec<usize>, checked: &mut usize) {
+            if ranks.len() == 5 {
+                let suits = [Of::Clubs, Of::Diamonds, Of::Hearts, Of::Spades];
+                let cards: Hand = ranks
+                    .iter()
+                    .enumerate()
+                    .map(|(i, rank)| {
+                        let copies = ranks[..i].iter().filter(|r| *r == rank).count();
+                        Card::new(RANKS[*rank], suits[copies])
+                    })
+                    .collect();
+                for (i, starter) in cards.iter().enumerate() {
+                    let mut hand = cards.clone();
+                    hand.remove(i);
+                    let set = CardSet::from(hand.as_slice());
+                    for is_crib in [false, true] {
+                        assert_eq!(
+                            super::score_points(set, Some(*starter), is_crib),
+                            super::score_hand(hand.clone(), Some(*starter), is_crib).total_score,
+                            "{:?} {} {}",
+                            hand,
+                            starter,
+                            is_crib
+                        );
+                    }
+                }
+                *checked += 1;
+                return;
+            }
+            let from = ranks.last().copied().unwrap_or(0);
+            for rank in from..RANKS.len() {
+                if ranks.iter().filter(|r| **r == rank).count() < 4 {
+                    ranks.push(rank);
+                    check(ranks, checked);
+                    ranks.pop();
+                }
+            }
+        }
+        let mut checked = 0;
+        check(&mut Vec::new(), &mut checked);
+        // 6,188 multisets of five ranks, less the 13 with five of a rank
+        assert_eq!(checked, 6_175);
+    }
+
+    /// this takes a couple of minutes in a release build, so it is only run
+    /// on request: `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn score_points_matches_score_hand_for_every_hand() {
+        // every four card hand with every starter, as a hand and as a crib
+        let checked: usize = (0..DECK_SIZE)
+            .into_par_iter()
+            .map(|a| {
+                let mut checked = 0;
+                for b in a + 1..DECK_SIZE {
+                    for c in b + 1..DECK_SIZE {
+                        for d in c + 1..DECK_SIZE {
+                            let hand: Hand =
+                                [a, b, c, d].iter().map(|i| Card::from_index(*i)).collect();
+                            let set = CardSet::from(hand.as_slice());
+                            for starter in CardSet::full().difference(set) {
+                                for is_crib in [false, true] {
+                                    assert_eq!(
+                                        super::score_points(set, Some(starter), is_crib),
+                                        super::score_hand(hand.clone(), Some(starter), is_crib)
+                                            .total_score,
+                                        "{:?} {} {}",
+                                        hand,
+                                        starter,
+                                        is_crib
+                                    );
+                                }
+                                checked += 1;
+                            }
+                        }
+                    }
+                }
+                checked
+            })
+            .sum();
+        assert_eq!(checked, 12_994_800);
+    }
+
+    #[test]
+    fn explain_counts_in_order() {
+        let hand = vec![
+            c!(Five, Of::Hearts),
+            c!(Six, Of::Clubs),
+            c!(Four, Of::Spades),
+            c!(Jack, Of::Diamonds),
+        ];
+        let score = super::score_hand(hand, Some(c!(Six, Of::Diamonds)), false);
+        assert_eq!(
+            score.explain(),
+            "fifteen 2 (FourOfSpades, FiveOfHearts, SixOfClubs), \
+             fifteen 4 (FourOfSpades, FiveOfHearts, SixOfDiamonds), \
+             fifteen 6 (FiveOfHearts, JackOfDiamonds), \
+             a pair is 8 (SixOfClubs, SixOfDiamonds), \
+             a run of three is 11 (FourOfSpades, FiveOfHearts, SixOfClubs), \
+             another run of three is 14 (FourOfSpades, FiveOfHearts, SixOfDiamonds), \
+             nobs is 15 (JackOfDiamonds, SixOfDiamonds)"
+        );
+        assert_eq!(score.to_string(), score.explain());
+        assert_eq!(score.total_score, 15);
+    }
+
+    #[test]
+    fn explain_flush_and_nothing() {
+        let hand = vec![
+            c!(Two, Of::Hearts),
+            c!(Four, Of::Hearts),
+            c!(Eight, Of::Hearts),
+            c!(Queen, Of::Hearts),
+        ];
+        let score = super::score_hand(hand.clone(), Some(c!(King, Of::Clubs)), false);
+        assert_eq!(
+            score.explain(),
+            "a flush of four is 4 (TwoOfHearts, FourOfHearts, EightOfHearts, QueenOfHearts)"
+        );
+        let score = super::score_hand(hand, Some(c!(King, Of::Clubs)), true);
+        assert_eq!(score.explain(), "no points");
+    }
+
+    #[test]
+    fn nob_alone_is_counted() {
+        let hand = vec![
+            c!(King, Of::Spades),
+            c!(Ten, Of::Hearts),
+            c!(Ace, Of::Hearts),
+            c!(Jack, Of::Diamonds),
+        ];
+        let score = super::score_hand(hand, Some(c!(Six, Of::Diamonds)), false);
+        assert_eq!(score.total_score, 1);
+    }
+
+    #[test]
+    fn house_rules() {
+        let rules = RuleSet {
+            crib_flush: CribFlush::FourCards,
+            nobs: 2,
+            wrap_runs: true,
+            ..RuleSet::default()
+        };
+        let crib = vec![
+            c!(Queen, Of::Hearts),
+            c!(King, Of::Hearts),
+            c!(Ace, Of::Hearts),
+            c!(Jack, Of::Hearts),
+        ];
+        let starter = Some(c!(Six, Of::Spades));
+        // a run of four from Jack to Ace, a flush of four and no nobs
+        let score = super::score_hand_with_rules(crib.clone(), starter, true, &rules);
+        assert_eq!(score.total_score, 8);
+        let run = score
+            .combinations
+            .iter()
+            .find(|c| c.name == super::CombinationName::RunOfFour)
+            .unwrap();
+        assert_eq!(run.cards[3], c!(Ace, Of::Hearts));
+        let set = CardSet::from(crib.as_slice());
+        assert_eq!(
+            super::score_points_with_rules(set, starter, true, &rules),
+            8
+        );
+        assert_eq!(
+            super::score_hand(crib.clone(), starter, true).total_score,
+            3
+        );
+        assert_eq!(super::score_points(set, starter, true), 3);
+
+        // nobs for 2
+        let starter = Some(c!(Six, Of::Hearts));
+        let score = super::score_hand_with_rules(crib, starter, true, &rules);
+        assert_eq!(score.total_score, 4 + 5 + 2);
+        assert_eq!(
+            super::score_points_with_rules(set, starter, true, &rules),
+            11
+        );
+    }
+
+    #[test]
+    fn his_heels() {
+        let rules = RuleSet::default();
+        let score = super::his_heels_score(c!(Jack, Of::Clubs), &rules);
+        assert_eq!(score.total_score, 2);
+        assert_eq!(score.explain(), "his heels is 2 (JackOfClubs)");
+        assert_eq!(
+            super::his_heels_score(c!(Queen, Of::Clubs), &rules).total_score,
+            0
+        );
+        let rules = RuleSet {
+            his_heels: 0,
+            ..rules
+        };
+        assert_eq!(
+            super::his_heels_score(c!(Jack, Of::Clubs), &rules).total_score,
+            0
+        );
+    }
+
+    #[test]
+    fn flush_of_three() {
+        let rules = RuleSet::short_game();
+        let hand = [
+            c!(Two, Of::Hearts),
+            c!(Eight, Of::Hearts),
+            c!(Queen, Of::Hearts),
+        ];
+        let score =
+            super::score_hand_with_rules(hand.to_vec(), Some(c!(Four, Of::Clubs)), false, &rules);
+        assert_eq!(score.total_score, 3);
+        let score =
+            super::score_hand_with_rules(hand.to_vec(), Some(c!(Four, Of::Hearts)), false, &rules);
+        assert_eq!(score.total_score, 4);
+        assert_eq!(
+            super::score_points_with_rules(
+                CardSet::from(&hand[..]),
+                Some(c!(Four, Of::Clubs)),
+                false,
+                &rules
+            ),
+            3
+        );
+        assert_eq!(
+            super::score_points_with_rules(
+                CardSet::from(&hand[..]),
+                Some(c!(Four, Of::Hearts)),
+                false,
+                &rules
+            ),
+            4
+        );
+    }
 
     macro_rules! test_case {
         (
@@ -1,117 +1,167 @@
 use crate::{   
-    cards::{Card, Rank, Suit},
-    combinator::all_combinations_of_size,
-    counting::score_counting_cards_played,
+    cards::Card,
+    crib_tables::CribTables,
     cribbage_errors::{CribbageError, CribbageErrorKind},
-    scoring::score_hand
+    discard_analysis::{
+        analyze_discards, analyze_discards_for, unseen_cards, CribEstimate, DiscardOption,
+    },
+    strategy::DefaultStrategy,
+    variant::Variant,
+    win_probability::{hand_distribution, WinProbabilities},
 };
 
 
-/// go through each of the 16 combinations looking for the hand
-/// that will perform best based on the value of the hand plus
-/// or minus the value of the crib
+/// go through each of the 15 combinations (or 5 for a five card hand in
+/// three player cribbage) looking for the hand that will perform best based
+/// on the value of the hand plus or minus the value of the crib
+///
+/// a five card hand is always taken to be three or four player cribbage,
+/// which discards one card.  the five card game deals five cards too but
+/// discards two, so it has to use `select_crib_cards_for` with
+/// `Variant::FiveCard`.
 /// 
 pub fn select_crib_cards(
     six_card_hand: &[Card],
     my_crib: bool,
 ) -> Result<Vec<Card>, CribbageError> {
-    // get all possible hands
-    let mut max_crib = Vec::<Card>::new();
-    let mut max_score: f32 = -1000.0;
-
-    if six_card_hand.len() != 6 {
-        return Err(CribbageError::new(
+    match Variant::from_cards_dealt(six_card_hand.len()) {
+        Some(variant) => select_crib_cards_for(six_card_hand, variant, my_crib),
+        None => Err(CribbageError::new(
             CribbageErrorKind::BadHand,
             format!(
-                "a hand should have 6 cards to select a crib.  you passed in {}",
+                "a hand should have 5 or 6 cards to select a crib.  you passed in {}",
                 six_card_hand.len()
             ),
-        ));
+        )),
     }
+}
 
-    let potential_hands = all_combinations_of_size(six_card_hand.to_vec(), 4, 4);
+/// `select_crib_cards` for a hand dealt in `variant`, which says how many
+/// cards to keep.  in five card cribbage that is 3 of the 5.
+pub fn select_crib_cards_for(
+    six_card_hand: &[Card],
+    variant: Variant,
+    my_crib: bool,
+) -> Result<Vec<Card>, CribbageError> {
+    DefaultStrategy::discard(six_card_hand, variant, my_crib)
+}
 
-    for hand_to_try in potential_hands {
-        if hand_to_try.len() != 4 {
-            panic!("while looking for a crib, the hand size should be 4");
-        }
+/// evaluates each of the 15 discards (or 5 for a five card hand) the way
+/// `select_crib_cards_by_expected_value` does, and returns all of them
+/// instead of only the best.  each option has the average, lowest and
+/// highest points of the kept hand over every starter, the expected crib,
+/// the net value and the starters that give the highest hand.  like
+/// `select_crib_cards`, a five card hand is taken to be three or four
+/// player cribbage, so the five card game has to use
+/// `evaluate_crib_cards_for`.
+///
+/// # Returns
+///
+/// the options ranked from the highest `expected_value` to the lowest
+///
+pub fn evaluate_crib_cards(
+    six_card_hand: &[Card],
+    my_crib: bool,
+) -> Result<Vec<DiscardOption>, CribbageError> {
+    analyze_discards(six_card_hand, my_crib, CribEstimate::Table)
+}
 
-        // get the score for the current hand we are evaluating
-        let mut score: f32 = score_hand(hand_to_try.clone(), None, false).total_score as f32;
-        let crib = get_crib_cards(&six_card_hand.to_vec(), &hand_to_try.clone());
+/// `evaluate_crib_cards` for a hand dealt in `variant`
+pub fn evaluate_crib_cards_for(
+    six_card_hand: &[Card],
+    variant: Variant,
+    my_crib: bool,
+) -> Result<Vec<DiscardOption>, CribbageError> {
+    analyze_discards_for(six_card_hand, variant, my_crib, CribEstimate::Table)
+}
 
-        let mut expected_value: f32;
-        if my_crib {
-            expected_value =
-                card_value_to_my_crib(crib[0].rank as usize - 1, crib[1].rank as usize - 1);
-            if crib[0].suit == crib[1].suit {
-                expected_value = expected_value + 0.01; // all things being equal, discard cards of the same suit
-            }
-            score = score + expected_value;
-        } else {
-            expected_value =
-                card_value_to_your_crib(crib[0].rank as usize - 1, crib[1].rank as usize - 1);
-            if crib[0].suit == crib[1].suit {
-                expected_value = expected_value + 0.01; // all things being equal, discard cards of the same suit
-            }
-            score = score - expected_value;
-        }
+/// like `select_crib_cards`, but scores each 4 card hand against all 46
+/// possible starters and picks the discard with the best expected value
+/// instead of the best score without a starter
+///
+pub fn select_crib_cards_by_expected_value(
+    six_card_hand: &[Card],
+    my_crib: bool,
+) -> Result<Vec<Card>, CribbageError> {
+    match Variant::from_cards_dealt(six_card_hand.len()) {
+        Some(variant) => select_crib_cards_by_expected_value_for(six_card_hand, variant, my_crib),
+        None => Err(CribbageError::new(
+            CribbageErrorKind::BadHand,
+            format!(
+                "a hand should have 5 or 6 cards to select a crib.  you passed in {}",
+                six_card_hand.len()
+            ),
+        )),
+    }
+}
+
+/// `select_crib_cards_by_expected_value` for a hand dealt in `variant`
+pub fn select_crib_cards_by_expected_value_for(
+    six_card_hand: &[Card],
+    variant: Variant,
+    my_crib: bool,
+) -> Result<Vec<Card>, CribbageError> {
+    let options = analyze_discards_for(six_card_hand, variant, my_crib, CribEstimate::Table)?;
+    Ok(options[0].discard.clone())
+}
 
-        if score > max_score {
-            max_score = score;
-            max_crib = crib.clone();
+/// like `select_crib_cards`, but for a two player game to 121 where the
+/// score is `my_score` to `opponent_score`.  it picks the discard with the
+/// best chance of winning from there, which near the end of the game can be
+/// a different one from the discard with the best expected value, and
+/// otherwise breaks ties by the expected value.
+///
+pub fn select_crib_cards_to_win(
+    six_card_hand: &[Card],
+    my_score: u32,
+    opponent_score: u32,
+    my_crib: bool,
+) -> Result<Vec<Card>, CribbageError> {
+    let options =
+        analyze_discards_for(six_card_hand, Variant::TwoPlayer, my_crib, CribEstimate::Table)?;
+    let win = WinProbabilities::standard();
+    let starters = unseen_cards(six_card_hand);
+    let mut best = (f64::MIN, 0);
+    for (i, option) in options.iter().enumerate() {
+        let hand = hand_distribution(&option.keep, &starters);
+        let (my_count, their_count) =
+            win.counts(my_crib, &hand, option.expected_crib_score as f64);
+        let chance = win.before_pegging(my_score, opponent_score, my_crib, &my_count, &their_count);
+        // the options are sorted by expected value, so the first is kept on a tie
+        if chance > best.0 {
+            best = (chance, i);
         }
     }
-
-    Ok(max_crib)
+    Ok(options[best.1].discard.clone())
 }
 
-static VALUE_MY_CRIB:[[f32;13];13] =
-               [[5.26, 4.18, 4.47, 5.45, 5.48, 3.80, 3.73, 3.70, 3.33, 3.37, 3.65, 3.39, 3.42],
-               [4.18, 5.67, 6.97, 4.51, 5.44, 3.87, 3.81, 3.58, 3.63, 3.51, 3.79, 3.52, 3.55] ,
-               [4.47, 6.97, 5.90, 4.88, 6.01, 3.72, 3.67, 3.84, 3.66, 3.61, 3.88, 3.62, 3.66] ,
-               [5.45, 4.51, 4.88, 5.65, 6.54, 3.87, 3.74, 3.84, 3.69, 3.62, 3.89, 3.63, 3.67] ,
-               [5.48, 5.44, 6.01, 6.54, 8.95, 6.65, 6.04, 5.49, 5.47, 6.68, 7.04, 6.71, 6.70] ,
-               [3.80, 3.87, 3.72, 3.87, 6.65, 5.74, 4.94, 4.70, 5.11, 3.15, 3.40, 3.08, 3.13] ,
-               [3.73, 3.81, 3.67, 3.74, 6.04, 4.94, 5.98, 6.58, 4.06, 3.10, 3.43, 3.17, 3.21] ,
-               [3.70, 3.58, 3.84, 3.84, 5.49, 4.70, 6.58, 5.42, 4.74, 3.86, 3.39, 3.16, 3.20] ,
-               [3.33, 3.63, 3.66, 3.69, 5.47, 5.11, 4.06, 4.74, 5.09, 4.27, 3.98, 2.97, 3.05] ,
-               [3.37, 3.51, 3.61, 3.62, 6.68, 3.15, 3.10, 3.86, 4.27, 4.73, 4.64, 3.36, 2.86] ,
-               [3.65, 3.79, 3.88, 3.89, 7.04, 3.40, 3.43, 3.39, 3.98, 4.64, 5.37, 4.90, 4.07] ,
-               [3.39, 3.52, 3.62, 3.63, 6.71, 3.08, 3.17, 3.16, 2.97, 3.36, 4.90, 4.66, 3.50] ,
-               [3.42, 3.55, 3.66, 3.67, 6.70, 3.13, 3.21, 3.20, 3.05, 2.86, 4.07, 3.50, 4.62] ];
-
-
-static VALUE_YOUR_CRIB:[[f32;13];13] = [
-                [6.02,  5.07,   5.07,   5.72,   6.01,   4.91,   4.89,   4.85,   4.55,   4.48,   4.68,   4.33,   4.30],
-                [5.07,  6.38,   7.33,   5.33,   6.11,   4.97,   4.97,   4.94,   4.70,   4.59,   4.81,   4.56,   4.45],
-                [5.07,  7.33,   6.68,   5.96,   6.78,   4.87,   5.01,   5.05,   4.87,   4.63,   4.86,   4.59,   4.48],
-                [5.72,  5.33,   5.96,   6.53,   7.26,   5.34,   4.88,   4.94,   4.68,   4.53,   4.85,   4.46,   4.36],
-                [6.01,  6.11,   6.78,   7.26,   9.37,   7.47,   7.00,   6.30,   6.15,   7.41,   7.76,   7.34,   7.25],
-                [4.91,  4.97,   4.87,   5.34,   7.47,   7.08,   6.42,   5.86,   6.26,   4.31,   4.57,   4.22,   4.14],
-                [4.89,  4.97,   5.01,   4.88,   7.00,   6.42,   7.14,   7.63,   5.26,   4.31,   4.68,   4.32,   4.27],
-                [4.85,  4.94,   5.05,   4.94,   6.30,   5.86,   7.63,   6.82,   5.83,   5.10,   4.59,   4.31,   4.20],
-                [4.55,  4.70,   4.87,   4.68,   6.15,   6.26,   5.26,   5.83,   6.39,   5.43,   4.96,   4.11,   4.03],
-                [4.48,  4.59,   4.63,   4.53,   7.41,   4.31,   4.31,   5.10,   5.43,   6.08,   5.63,   4.61,   3.88],
-                [4.68,  4.81,   4.86,   4.85,   7.76,   4.57,   4.68,   4.59,   4.96,   5.63,   6.42,   5.46,   4.77],
-                [4.33,  4.56,   4.59,   4.46,   7.34,   4.22,   4.32,   4.31,   4.11,   4.61,   5.46,   5.79,   4.49],
-                [4.30,  4.45,   4.48,   4.36,   7.25,   4.14,   4.27,   4.20,   4.03,   3.88,   4.77,   4.49,   5.65]]; 
-
-
-fn card_value_to_my_crib(rank1: usize, rank2: usize) -> f32 {    
-    VALUE_MY_CRIB[rank1][rank2]
+/// the expected points of a crib that `card1` and `card2` are discarded to
+/// according to the shipped crib tables
+pub(crate) fn crib_table_value(card1: Card, card2: Card, my_crib: bool) -> f32 {
+    CribTables::shipped().value(card1, card2, my_crib)
 }
 
-fn card_value_to_your_crib(rank1: usize, rank2: usize) -> f32 {    
-    return VALUE_YOUR_CRIB[rank1][rank2];
+/// the expected points of a crib that the cards in `discard` are put in.
+/// a single card is valued as the average over the rank of the card it
+/// could be paired with, so that five card hands can use the same tables.
+pub(crate) fn discard_table_value(discard: &[Card], my_crib: bool) -> f32 {
+    match discard {
+        [card1, card2] => crib_table_value(*card1, *card2, my_crib),
+        [card] => {
+            let table = CribTables::shipped().table(my_crib, false);
+            let total: f32 = table[card.rank as usize - 1].iter().sum();
+            total / 13.0
+        }
+        _ => 0.0,
+    }
 }
 
 
-/// hand has 6 cards and is passed in by the client
+/// hand has 6 (or 5) cards and is passed in by the client
 /// heldCards has 4 cards and is generated via permutation
-/// this returns the 2 cards that are in the hand but not the crib
+/// this returns the 2 (or 1) cards that are in the hand but not the crib
 /// 
-fn get_crib_cards(full_hand_of_six: &Vec<Card>, kept_cards: &Vec<Card>) -> Vec<Card> {
+pub(crate) fn get_crib_cards(full_hand_of_six: &Vec<Card>, kept_cards: &Vec<Card>) -> Vec<Card> {
     let mut send_to_crib = Vec::<Card>::new();
 
     for card in full_hand_of_six.iter() {
@@ -123,192 +173,14 @@ fn get_crib_cards(full_hand_of_six: &Vec<Card>, kept_cards: &Vec<Card>) -> Vec<C
 }
 
 /// called during counting phase
-/// this api looks at the cards that have already been played and then the cards that could be played and tries to pick the right one
+/// this api looks at the cards that have already been played and then the cards that could be played and tries to pick the right one.
+/// it plays like `DefaultStrategy`, which is where the logic lives.
 ///
-/// this is where "strategy" is implemented as selecting what cards to give to the crib is straightforward probability whereas counting
-/// cards is largely a function of anticipating what the other player is going to play -- e.g. if you have a 2 and a 3, you might play the
-/// 2 hoping that the other player has only cards with value = 10, so that you can play your 3 and get two points for the 15.  But the other
-/// player knows this is a normal thing to do, so by playing a 2 you imply you also have a 3.  So maybe the right card to play is a 4 so that the
-/// apponent plays the 3 to get the run (2, 4, 3) and 3 points - and then bam! -- you play an Ace to get 4 points.  and so on.
-///
-/// my strategy for this is to implement it the way I play the game.  by its nature it is a bunch of "if this then that" calls, so this function
-/// tends to be very long and very experimental.  I have added logic in the past and then tested it by playing millions (literally) of games of
-/// one algorithm against the other to see if it really works (it takes about 20 seconds to run a million games on a beefy PC...)
-///
-
 pub fn get_next_counted_card(
     played_cards: Vec<Card>,
-    mut available_cards: Vec<Card>, // needs to be mut because we .sort() it
+    available_cards: Vec<Card>,
 ) -> Result<Option<Card>, CribbageError> {
-    let current_count: i32 = played_cards.iter().map(|c| c.value).sum::<i32>();
-    //
-    //  if you only have one card left, play it if you can
-    if available_cards.len() == 1 {
-        if current_count + available_cards[0].value <= 31 {
-            return Ok(Some(available_cards[0]));
-        } else {
-            return Ok(None);
-        }
-    }
-
-    available_cards.sort(); // this is a sort by rank
-
-    //  
-    //  here is the first strategic decision - this algorithm will take points if they are available.  for example, when
-    //  humans play, they might decide to defer points (say a pair) in order to get the opponent to start a run.  e.g. if cards_played is something like
-    //  a 2, then it would be common for that person to also have a 3.  they played the 2 hoping that a card of value 10 would be played and then they
-    //  could play a 3 - 15 for 2 points!.  This algorithm will always play a 2 to pair the 2 if it is possible.  Another strategy would be to play a 4
-    //  or an Ace (*never* a 3 - because you don't want the count to be 5 because of how many cards are available with value=10).  in this case, the opponent
-    //  would play the 3 and get a run of 3 for 3 points.  But then you could build on the run and get 4 points (assuming you had the right cards)
-    //
-    //  one of the things I want to explore is defeating the "get a 15" strategy above. the other common strategy (which we use below) is to start with a card
-    //  that you have a pair...the hope is the apponent pairs the card, so you can get 3 of a kind (e.g. play a 4, opponent plays a 4, you play your second 4)
-    //  would it be better to not pair the card and give up the two points?  over the course of the game, stopping 3 of a kind might be worth it.
-    //
-    //  this is an area ripe for innovation!
-    //
-
-    //
-    //  Note: I need the card to play for the max score - i tried to do this in a variety of ways using map/fold and I could get the max score, but the card
-    //        that generated the max score was lost in the map.  i gave up and did it via iteration.
-
-    let mut max: i32 = -1;
-    let mut card_to_play = Card::new(Rank::Unknown, Suit::Unknown);
-    let mut playable_cards: Vec<Card> = Vec::new();
-    for potential_card in available_cards.as_slice() {
-        if let Ok(s) = score_counting_cards_played(played_cards.as_slice(), *potential_card) {
-            if max < s.total_score as i32 {
-                // innovation idea:  if you can play 2 cards and get the same score, which one should you play?
-                max = s.total_score as i32;
-                card_to_play = *potential_card;
-            }
-
-            playable_cards.push(*potential_card);
-        }
-    }
-
-    if playable_cards.is_empty() {
-        // this means we have no valid cards to play
-        return Ok(None);
-    }
-
-    //
-    //  if there is only one card that can be played, return it
-    if playable_cards.len() == 1 {
-        return Ok(Some(playable_cards[0]));
-    }
-
-    //
-    //  get the most points - innovate here and pick the *best* one, not necessarily the one with most points!
-    if max > 0 {
-        return Ok(Some(card_to_play));
-    }
-    //
-    //  we can play, but we can't get points.
-
-    //
-    //  find all combinations of 2 cards in the cards I have left...
-    let two_card_combi = all_combinations_of_size(playable_cards.clone(), 2, 2);
-    //
-    //  we only want to interate through the 2 card combinations once, so
-    //  we set a strategic weight to pick between the various scenarios and
-    //  set the card_to_play when we find one
-    //
-    let mut strategic_weight = 0;
-    let mut card_to_play: Card = Card::new(Rank::Unknown, Suit::Unknown);
-
-    for mut cards in two_card_combi {
-        if cards.len() != 2 {
-            panic!("all_combinations_of_min_size returned the wrong size Vec!");
-        }
-        cards.sort(); // this sort might not be needed, but i'm not sure if all_combinations_of_size guarantees to returns sorted if the input was sorted
-        if cards[0].rank == cards[1].rank
-            && current_count + 3 * cards[0].value <= 31
-            && strategic_weight < 10 && cards[1].value != 5
-        {
-            //
-            //  this means that we have a pair and if the opponent plays the same card to get a pair, we can play our second to get 6 points
-            //  an innovation to be tested here is to be careful with playing a 5 - which are typically held to try to get to a 15.
-            card_to_play = cards[1];
-            strategic_weight = 10;
-        }
-        //
-        // I have no pairs, try to start a run
-        match (cards[0].rank as i32 - cards[1].rank as i32).abs() {
-            1 => {
-                // there are 2 cards that the other person can play that help us - the values would be
-                // cards[0]-1 or cards[0]+1 - we only check one here, and we pick the optimistic one
-                // because if the card is bigger one, we might go over 31.  but we should run a test
-                // to see how much it matters over the course of a game
-                if cards[0].value - 1 + cards[0].value + cards[1].value + current_count <= 31
-                    && strategic_weight < 5
-                {
-                    card_to_play = cards[1]; // I like to play the pick one when possible
-                    strategic_weight = 5;
-                }
-            }
-            2 => {
-                // this means we are in a situation where we have something like a 7 and a 9, but no 8
-                // cards[0].value + 1 is the value of the card we want the opponent to play
-                //
-                // i want to use "31" here because it is the key rule number, but the linter complains about
-                //  the +1, so turn the linter rule off
-                #[allow(clippy::int_plus_one)]
-                if cards[0].value + cards[1].value + cards[0].value + 1 <= 31
-                    && strategic_weight < 5
-                {
-                    if cards[1].rank != Rank::Five {
-                        card_to_play = cards[1]; // I like to play the big one when possible
-                    } else {
-                        card_to_play = cards[0];
-                    }
-
-                    strategic_weight = 5;
-                }
-            }
-            _ => {
-                // we don't have cards within 1 rank of each other -- not worth trying to get a run
-            }
-        }
-        //  remember the cards in case we need  to pick a card assuming that the other player only has cards of rank 10 to play left
-        match cards[0].value + cards[1].value + 10 {
-            15 | 21 => {
-                if strategic_weight < 8 {
-                    card_to_play = cards[1];
-                    strategic_weight = 8;
-                }
-            }
-            _ => {}
-        }
-    }
-
-    //
-    //  if we get here - we have no cards that we can play that give points, no runs we can try to start, and no pairs to leverage.
-    //  so we will try to pick a card assuming that the other player only has cards of rank 10 to play left
-    //
-    //  Another innovation idea to test is that a human player will figure out what the game is always doing -- so, for example, it is easy
-    //  to figure out that if the computer starts with (say) a 8, then they are probably trying to match a pair.  if they start with a card
-    //  less than 5, they are trying to get to 15.  this extra information can be leveraged to help the player.  So instead we should figure
-    //  out what valid cards could be played and then if then set strategic_weight to be the same for adding up to 15 and for pairs and then
-    //  randomly pick which one to use.
-    //
-
-    if strategic_weight > 0 {
-        if card_to_play.rank == Rank::Unknown {
-            panic!("strategic weight is set but the card_to_play is not not");
-        }
-        return Ok(Some(card_to_play));
-    }
-    //
-    //  if the last card (the highest value) is not a 5, return it
-    //
-    if playable_cards[playable_cards.len() - 1].value != 5 {
-        return Ok(Some(playable_cards[playable_cards.len() - 1]));
-    }
-    //
-    //  if it is a 5, return the next highest one -- we know it is not a pair because if it was, we'd have played it based on what we have above
-    //  we also know that we have at least two cards, because if there was only one, we would have already picked it.
-    Ok(Some(playable_cards[playable_cards.len() - 2]))
+    DefaultStrategy::play(played_cards, available_cards)
 }
 
 #[cfg(test)]
@@ -501,6 +373,134 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_select_crib_cards_by_expected_value() {
+        let hand = [
+            c!(Five, Of::Hearts),
+            c!(Five, Of::Clubs),
+            c!(Five, Of::Spades),
+            c!(Jack, Of::Diamonds),
+            c!(Six, Of::Clubs),
+            c!(Four, Of::Diamonds),
+        ];
+        let crib = select_crib_cards_by_expected_value(&hand, false).unwrap();
+        assert_eq!(crib, vec![c!(Six, Of::Clubs), c!(Four, Of::Diamonds)]);
+
+        let err = select_crib_cards_by_expected_value(&hand[0..4], false).unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::BadHand);
+    }
+
+    #[test]
+    fn test_select_crib_cards_for_five_card_cribbage() {
+        let hand = [
+            c!(Five, Of::Hearts),
+            c!(Five, Of::Clubs),
+            c!(Jack, Of::Diamonds),
+            c!(Eight, Of::Clubs),
+            c!(King, Of::Spades),
+        ];
+        let crib = select_crib_cards_for(&hand, Variant::FiveCard, false).unwrap();
+        assert_eq!(crib.len(), 2);
+        assert!(!crib.contains(&c!(Five, Of::Hearts)) && !crib.contains(&c!(Five, Of::Clubs)));
+        let crib =
+            select_crib_cards_by_expected_value_for(&hand, Variant::FiveCard, false).unwrap();
+        assert_eq!(crib.len(), 2);
+        assert_eq!(
+            select_crib_cards_for(&hand, Variant::TwoPlayer, false)
+                .unwrap_err()
+                .error_kind,
+            CribbageErrorKind::BadHand
+        );
+    }
+
+    #[test]
+    fn test_select_crib_cards_from_five_cards() {
+        let hand = [
+            c!(Five, Of::Hearts),
+            c!(Five, Of::Clubs),
+            c!(Five, Of::Spades),
+            c!(Jack, Of::Diamonds),
+            c!(Eight, Of::Clubs),
+        ];
+        for my_crib in [true, false] {
+            assert_eq!(
+                select_crib_cards(&hand, my_crib).unwrap(),
+                vec![c!(Eight, Of::Clubs)]
+            );
+            assert_eq!(
+                select_crib_cards_by_expected_value(&hand, my_crib).unwrap(),
+                vec![c!(Eight, Of::Clubs)]
+            );
+        }
+    }
+
+    #[test]
+    fn test_evaluate_crib_cards() {
+        let hand = [
+            c!(Four, Of::Hearts),
+            c!(Five, Of::Hearts),
+            c!(Six, Of::Spades),
+            c!(Jack, Of::Hearts),
+            c!(Queen, Of::Hearts),
+            c!(Six, Of::Diamonds),
+        ];
+        let options = evaluate_crib_cards(&hand, true).unwrap();
+        assert_eq!(options.len(), 15);
+        assert_eq!(
+            options[0].discard,
+            select_crib_cards_by_expected_value(&hand, true).unwrap()
+        );
+        for option in &options {
+            assert!(option.min_hand_score as f32 <= option.average_hand_score);
+            assert!(option.average_hand_score <= option.max_hand_score as f32);
+            assert!(!option.best_starters.is_empty());
+            assert!(option.best_starters.iter().all(|c| !hand.contains(c)));
+            let net = option.average_hand_score + option.expected_crib_score;
+            assert!((option.expected_value - net).abs() < 1e-5);
+        }
+        for pair in options.windows(2) {
+            assert!(pair[0].expected_value >= pair[1].expected_value);
+        }
+        assert!(evaluate_crib_cards(&hand[..4], true).is_err());
+
+        // five cards discard one unless the variant says they discard two
+        assert_eq!(evaluate_crib_cards(&hand[..5], true).unwrap().len(), 5);
+        let options = evaluate_crib_cards_for(&hand[..5], Variant::FiveCard, true).unwrap();
+        assert_eq!(options.len(), 10);
+        assert!(options.iter().all(|option| option.discard.len() == 2));
+    }
+
+    #[test]
+    fn test_select_crib_cards_to_win() {
+        let hand = [
+            c!(Five, Of::Hearts),
+            c!(Five, Of::Clubs),
+            c!(Five, Of::Spades),
+            c!(Jack, Of::Hearts),
+            c!(Nine, Of::Clubs),
+            c!(King, Of::Diamonds),
+        ];
+        // at the start of the game the most points is the best chance
+        let crib = select_crib_cards_to_win(&hand, 0, 0, false).unwrap();
+        assert_eq!(crib, vec![c!(Nine, Of::Clubs), c!(King, Of::Diamonds)]);
+
+        // needing 8 with the opponent about to peg out, a different discard
+        // gives a better chance than the one with the best expected value
+        let hand = [
+            c!(Three, Of::Hearts),
+            c!(Three, Of::Clubs),
+            c!(Jack, Of::Hearts),
+            c!(Seven, Of::Spades),
+            c!(Queen, Of::Spades),
+            c!(Ace, Of::Spades),
+        ];
+        let crib = select_crib_cards_to_win(&hand, 113, 117, false).unwrap();
+        assert_eq!(crib.len(), 2);
+        assert_ne!(crib, select_crib_cards_by_expected_value(&hand, false).unwrap());
+
+        assert!(select_crib_cards_to_win(&hand[..5], 0, 0, true).is_err());
+    }
+
     #[test]
     fn test_get_crib_cards_match_expected_length() {
         // prepare test parameters
@@ -0,0 +1,405 @@
+//! `simulation` plays many games between two strategies to find out which
+//! one is stronger.  Games are played in parallel and each game is seeded
+//! from the simulation seed, so a run can be repeated exactly.  Whole
+//! tournament matches can be simulated the same way.
+
+use crate::{
+    board::Loss,
+    cribbage_errors::CribbageError,
+    game::{Game, GamePhase},
+    match_play::{FirstDealer, Match},
+    rules::RuleSet,
+    strategy::Strategy,
+    variant::Variant,
+};
+use rayon::prelude::*;
+use serde::Serialize;
+
+/// the z score for a 95% confidence interval
+const Z_95: f64 = 1.96;
+
+/// The points each strategy scored over all of the games, split up by
+/// where the points came from.  Index 0 is the first strategy passed to
+/// `simulate` and index 1 is the second, whichever seat they sat in.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SimulationReport {
+    pub names: [String; 2],
+    pub games: usize,
+    pub wins: [usize; 2],
+    /// wins where the loser did not reach the skunk line
+    pub skunks: [usize; 2],
+    /// wins where the loser did not reach the double skunk line
+    pub double_skunks: [usize; 2],
+    /// the number of deals in all games, which is the number of hands each
+    /// strategy held
+    pub deals: usize,
+    /// the number of deals where the strategy had the crib
+    pub cribs: [usize; 2],
+    pub hand_points: [u64; 2],
+    pub crib_points: [u64; 2],
+    pub pegging_points: [u64; 2],
+    pub his_heels_points: [u64; 2],
+}
+
+impl SimulationReport {
+    fn new(names: [String; 2]) -> SimulationReport {
+        SimulationReport {
+            names,
+            ..Default::default()
+        }
+    }
+
+    /// adds the tallies of `other` to this report
+    fn merge(mut self, other: SimulationReport) -> SimulationReport {
+        self.games += other.games;
+        self.deals += other.deals;
+        for i in 0..2 {
+            self.wins[i] += other.wins[i];
+            self.skunks[i] += other.skunks[i];
+            self.double_skunks[i] += other.double_skunks[i];
+            self.cribs[i] += other.cribs[i];
+            self.hand_points[i] += other.hand_points[i];
+            self.crib_points[i] += other.crib_points[i];
+            self.pegging_points[i] += other.pegging_points[i];
+            self.his_heels_points[i] += other.his_heels_points[i];
+        }
+        self
+    }
+
+    pub fn win_rate(&self, strategy: usize) -> f64 {
+        ratio(self.wins[strategy] as u64, self.games)
+    }
+
+    /// the 95% confidence interval of the win rate of `strategy`
+    pub fn win_rate_confidence_interval(&self, strategy: usize) -> (f64, f64) {
+        let p = self.win_rate(strategy);
+        let margin = Z_95 * (p * (1.0 - p) / self.games.max(1) as f64).sqrt();
+        ((p - margin).max(0.0), (p + margin).min(1.0))
+    }
+
+    /// the fraction of games that `strategy` won with a skunk
+    pub fn skunk_rate(&self, strategy: usize) -> f64 {
+        ratio(self.skunks[strategy] as u64, self.games)
+    }
+
+    pub fn double_skunk_rate(&self, strategy: usize) -> f64 {
+        ratio(self.double_skunks[strategy] as u64, self.games)
+    }
+
+    pub fn average_hand(&self, strategy: usize) -> f64 {
+        ratio(self.hand_points[strategy], self.deals)
+    }
+
+    /// the average points of the crib, over the deals where `strategy` had it
+    pub fn average_crib(&self, strategy: usize) -> f64 {
+        ratio(self.crib_points[strategy], self.cribs[strategy])
+    }
+
+    /// the average points pegged per deal
+    pub fn average_pegging(&self, strategy: usize) -> f64 {
+        ratio(self.pegging_points[strategy], self.deals)
+    }
+}
+
+impl std::fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} games, {} deals", self.games, self.deals)?;
+        writeln!(
+            f,
+            "{:<16}{:>8}{:>18}{:>8}{:>8}{:>8}{:>8}{:>8}",
+            "strategy", "wins", "95% interval", "skunk", "double", "hand", "crib", "peg"
+        )?;
+        for i in 0..2 {
+            let (low, high) = self.win_rate_confidence_interval(i);
+            writeln!(
+                f,
+                "{:<16}{:>7.2}%{:>8.2}% - {:>5.2}%{:>7.2}%{:>7.2}%{:>8.2}{:>8.2}{:>8.2}",
+                self.names[i],
+                100.0 * self.win_rate(i),
+                100.0 * low,
+                100.0 * high,
+                100.0 * self.skunk_rate(i),
+                100.0 * self.double_skunk_rate(i),
+                self.average_hand(i),
+                self.average_crib(i),
+                self.average_pegging(i),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays `games` games of `first` against `second` on all cores.  The
+/// strategies swap seats every game so neither gets an advantage from the
+/// cut for deal.
+pub fn simulate(
+    first: &dyn Strategy,
+    second: &dyn Strategy,
+    games: usize,
+    seed: u64,
+) -> Result<SimulationReport, CribbageError> {
+    let names = [first.name().to_string(), second.name().to_string()];
+    (0..games)
+        .into_par_iter()
+        .map(|i| play_game(first, second, i, seed.wrapping_add(i as u64)))
+        .try_reduce(
+            || SimulationReport::new(names.clone()),
+            |a, b| Ok(a.merge(b)),
+        )
+}
+
+/// plays one game and returns a report for just that game.  `first` sits in
+/// seat `index % 2`.
+fn play_game(
+    first: &dyn Strategy,
+    second: &dyn Strategy,
+    index: usize,
+    seed: u64,
+) -> Result<SimulationReport, CribbageError> {
+    let seat_of_first = index % 2;
+    let strategies: [&dyn Strategy; 2] = if seat_of_first == 0 {
+        [first, second]
+    } else {
+        [second, first]
+    };
+    // which strategy is sitting in each seat
+    let strategy_in = |seat: usize| if seat == seat_of_first { 0 } else { 1 };
+
+    let mut report = SimulationReport {
+        games: 1,
+        ..Default::default()
+    };
+    let mut game = Game::from_seed(seed);
+    while game.phase() != GamePhase::GameOver {
+        let phase = game.phase();
+        let dealer = game.dealer();
+        let before = game.scores().to_vec();
+        game.step(&strategies)?;
+
+        if phase == GamePhase::Deal {
+            report.deals += 1;
+            report.cribs[strategy_in(dealer)] += 1;
+        }
+        for (seat, score) in game.scores().iter().enumerate() {
+            let points = (score - before[seat]) as u64;
+            let tally = match phase {
+                GamePhase::Starter => &mut report.his_heels_points,
+                GamePhase::Pegging => &mut report.pegging_points,
+                GamePhase::Show => &mut report.hand_points,
+                GamePhase::Crib => &mut report.crib_points,
+                _ => continue,
+            };
+            tally[strategy_in(seat)] += points;
+        }
+    }
+
+    let winner = game.winner().unwrap(); // the game is over
+    report.wins[strategy_in(winner)] = 1;
+    match game.board().loss(1 - winner) {
+        Some(Loss::Skunk) => report.skunks[strategy_in(winner)] = 1,
+        Some(Loss::DoubleSkunk) => {
+            report.skunks[strategy_in(winner)] = 1;
+            report.double_skunks[strategy_in(winner)] = 1;
+        }
+        _ => {}
+    }
+    Ok(report)
+}
+
+/// The results of many matches between two strategies.  As in
+/// `SimulationReport`, index 0 is the first strategy and index 1 the second.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct MatchReport {
+    pub names: [String; 2],
+    pub best_of: u32,
+    pub matches: usize,
+    pub match_wins: [usize; 2],
+    /// the games played in all matches
+    pub games: usize,
+    /// the games won, with a skunk counting as two
+    pub game_wins: [u64; 2],
+    /// the tournament game points
+    pub game_points: [u64; 2],
+}
+
+impl MatchReport {
+    fn merge(mut self, other: MatchReport) -> MatchReport {
+        self.matches += other.matches;
+        self.games += other.games;
+        for i in 0..2 {
+            self.match_wins[i] += other.match_wins[i];
+            self.game_wins[i] += other.game_wins[i];
+            self.game_points[i] += other.game_points[i];
+        }
+        self
+    }
+
+    pub fn match_win_rate(&self, strategy: usize) -> f64 {
+        ratio(self.match_wins[strategy] as u64, self.matches)
+    }
+
+    /// the average game points per match
+    pub fn average_game_points(&self, strategy: usize) -> f64 {
+        ratio(self.game_points[strategy], self.matches)
+    }
+}
+
+impl std::fmt::Display for MatchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} matches of best of {}, {} games",
+            self.matches, self.best_of, self.games
+        )?;
+        writeln!(
+            f,
+            "{:<16}{:>8}{:>8}{:>14}",
+            "strategy", "matches", "games", "game points"
+        )?;
+        for i in 0..2 {
+            writeln!(
+                f,
+                "{:<16}{:>7.2}%{:>8}{:>14.2}",
+                self.names[i],
+                100.0 * self.match_win_rate(i),
+                self.game_wins[i],
+                self.average_game_points(i),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays `matches` two player matches of `best_of` games of `first` against
+/// `second` on all cores, with the loser of each game dealing first in the
+/// next.  The strategies swap seats every match.
+pub fn simulate_matches(
+    first: &dyn Strategy,
+    second: &dyn Strategy,
+    matches: usize,
+    best_of: u32,
+    seed: u64,
+) -> Result<MatchReport, CribbageError> {
+    let names = [first.name().to_string(), second.name().to_string()];
+    let empty = MatchReport {
+        names,
+        best_of,
+        ..Default::default()
+    };
+    (0..matches)
+        .into_par_iter()
+        .map(|i| {
+            // each game of a match adds one to its seed
+            let seed = seed.wrapping_add(i as u64 * best_of as u64);
+            play_match(first, second, i, best_of, seed)
+        })
+        .try_reduce(|| empty.clone(), |a, b| Ok(a.merge(b)))
+}
+
+/// plays one match and returns a report for just that match.  `first` sits
+/// in seat `index % 2`.
+fn play_match(
+    first: &dyn Strategy,
+    second: &dyn Strategy,
+    index: usize,
+    best_of: u32,
+    seed: u64,
+) -> Result<MatchReport, CribbageError> {
+    let seat_of_first = index % 2;
+    let strategies: [&dyn Strategy; 2] = if seat_of_first == 0 {
+        [first, second]
+    } else {
+        [second, first]
+    };
+    let strategy_in = |seat: usize| if seat == seat_of_first { 0 } else { 1 };
+
+    let mut series = Match::new(
+        best_of,
+        Variant::TwoPlayer,
+        RuleSet::default(),
+        FirstDealer::LoserDeals,
+        seed,
+    )?;
+    let winner = series.play_to_end(&strategies)?;
+
+    let mut report = MatchReport {
+        matches: 1,
+        games: series.games().len(),
+        ..Default::default()
+    };
+    report.match_wins[strategy_in(winner)] = 1;
+    for seat in 0..2 {
+        report.game_wins[strategy_in(seat)] = series.wins()[seat] as u64;
+        report.game_points[strategy_in(seat)] = series.game_points()[seat] as u64;
+    }
+    Ok(report)
+}
+
+fn ratio(numerator: u64, denominator: usize) -> f64 {
+    if denominator == 0 {
+        return 0.0;
+    }
+    numerator as f64 / denominator as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::DefaultStrategy;
+
+    #[test]
+    fn simulation_is_repeatable() {
+        let a = simulate(&DefaultStrategy, &DefaultStrategy, 20, 99).unwrap();
+        let b = simulate(&DefaultStrategy, &DefaultStrategy, 20, 99).unwrap();
+        assert_eq!(a.games, 20);
+        assert_eq!(a.wins[0] + a.wins[1], 20);
+        assert_eq!(a.wins, b.wins);
+        assert_eq!(a.hand_points, b.hand_points);
+        assert_eq!(a.pegging_points, b.pegging_points);
+    }
+
+    #[test]
+    fn points_add_up_to_the_final_scores() {
+        let report = play_game(&DefaultStrategy, &DefaultStrategy, 0, 5).unwrap();
+        let mut game = Game::from_seed(5);
+        game.play_to_end(&[&DefaultStrategy, &DefaultStrategy])
+            .unwrap();
+        let total: u64 = game.scores().iter().map(|s| *s as u64).sum();
+        let tallied: u64 = (0..2)
+            .map(|i| {
+                report.hand_points[i]
+                    + report.crib_points[i]
+                    + report.pegging_points[i]
+                    + report.his_heels_points[i]
+            })
+            .sum();
+        assert_eq!(total, tallied);
+        assert_eq!(report.cribs[0] + report.cribs[1], report.deals);
+    }
+
+    #[test]
+    fn matches_are_repeatable() {
+        let a = simulate_matches(&DefaultStrategy, &DefaultStrategy, 6, 3, 4).unwrap();
+        let b = simulate_matches(&DefaultStrategy, &DefaultStrategy, 6, 3, 4).unwrap();
+        assert_eq!(a.matches, 6);
+        assert_eq!(a.match_wins[0] + a.match_wins[1], 6);
+        assert_eq!(a.match_wins, b.match_wins);
+        assert_eq!(a.game_points, b.game_points);
+        // a skunk counts as two games, so a match can end after one game
+        assert!(a.games >= 6 && a.games <= 6 * 3);
+        // every match is won by winning at least two games
+        assert!(a.game_wins[0] + a.game_wins[1] >= 12);
+    }
+
+    #[test]
+    fn confidence_interval() {
+        let report = SimulationReport {
+            games: 10_000,
+            wins: [5_000, 5_000],
+            ..Default::default()
+        };
+        let (low, high) = report.win_rate_confidence_interval(0);
+        assert!((low - 0.4902).abs() < 0.0001);
+        assert!((high - 0.5098).abs() < 0.0001);
+    }
+}
@@ -0,0 +1,622 @@
+//! `strategy` lets the decisions a player makes be swapped out.  A player
+//! only makes two kinds of decisions in cribbage: which cards to put in the
+//! crib and which card to play during pegging.
+//!
+//! `DefaultStrategy` is the logic the web api has always used, which
+//! `select_crib_cards` and `get_next_counted_card` call.  `MonteCarloStrategy`
+//! and `ExactStrategy` look ahead to the end of the pegging round with
+//! `pegging_search`.  `ScoreAwareStrategy` plays for the chance of winning
+//! the game from the score instead of for points.
+
+use crate::{
+    card_set::CardSet,
+    cards::{Card, Rank, Suit},
+    combinator::all_combinations_of_size,
+    counting::score_counting_cards_played,
+    cribbage_errors::{CribbageError, CribbageErrorKind},
+    discard_analysis::{analyze_discards_for, CribEstimate},
+    pegging::PeggingAction,
+    pegging_search::{
+        choose_card_exact, choose_card_monte_carlo, choose_card_to_win, SearchBudget,
+    },
+    rules::{RuleSet, GAME_LENGTH},
+    scoring::score_points,
+    select_cards::{
+        discard_table_value, get_crib_cards, select_crib_cards_by_expected_value_for,
+        select_crib_cards_to_win,
+    },
+    variant::Variant,
+};
+
+/// Everything a player can see when they make a decision
+#[derive(Clone, Debug)]
+pub struct StrategyContext<'a> {
+    /// the seat of the player making the decision
+    pub player: usize,
+    pub dealer: usize,
+    /// the seat of the player's partner in a partnership game
+    pub partner: Option<usize>,
+    /// the variant being played, which says how many cards to discard
+    pub variant: Variant,
+    pub rules: RuleSet,
+    /// the score of each side.  that is each seat, except in partnership
+    /// games where it is each partnership.
+    pub scores: &'a [u32],
+    /// the cards the player is holding right now.  six (or five) cards when
+    /// discarding and the unplayed cards when pegging.
+    pub hand: &'a [Card],
+    /// the cards the player put in the crib, once they have discarded
+    pub discarded: &'a [Card],
+    pub starter: Option<Card>,
+    /// the cards played since the count was last reset
+    pub pile: &'a [Card],
+    /// every play and "Go" so far in this round of pegging
+    pub history: &'a [PeggingAction],
+}
+
+impl<'a> StrategyContext<'a> {
+    /// a context where nothing is known but the cards in `hand` and who owns
+    /// the crib, which is all the web api is told
+    pub fn from_hand(hand: &'a [Card], my_crib: bool) -> StrategyContext<'a> {
+        StrategyContext {
+            player: 0,
+            dealer: if my_crib { 0 } else { 1 },
+            partner: None,
+            variant: Variant::from_cards_dealt(hand.len()).unwrap_or_default(),
+            rules: RuleSet::default(),
+            scores: &[0, 0],
+            hand,
+            discarded: &[],
+            starter: None,
+            pile: &[],
+            history: &[],
+        }
+    }
+
+    /// true if the crib belongs to the player or their partner
+    pub fn is_my_crib(&self) -> bool {
+        self.player == self.dealer || self.partner == Some(self.dealer)
+    }
+
+    /// the cards the player's partner has played so far in this round of
+    /// pegging, in the order they were played
+    pub fn partner_played(&self) -> Vec<Card> {
+        self.history
+            .iter()
+            .filter_map(|action| match action {
+                PeggingAction::Play { player, card } if Some(*player) == self.partner => {
+                    Some(*card)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn count(&self) -> i32 {
+        self.pile.iter().map(|c| c.value).sum()
+    }
+
+    /// every card the player has seen: their hand, their discards, the
+    /// starter and the cards played during pegging
+    pub fn known_cards(&self) -> Vec<Card> {
+        let mut known: Vec<Card> = self.hand.to_vec();
+        known.extend_from_slice(self.discarded);
+        known.extend(self.starter);
+        for action in self.history {
+            if let PeggingAction::Play { card, .. } = action {
+                if !known.contains(card) {
+                    known.push(*card);
+                }
+            }
+        }
+        known
+    }
+}
+
+/// `Strategy` makes the decisions for one player.  Strategies have to be
+/// `Send` and `Sync` so that games can be played on many threads at once.
+pub trait Strategy: Send + Sync {
+    /// a short name used to identify the strategy in reports
+    fn name(&self) -> &str;
+
+    /// Returns the cards from `context.hand` to put in the crib
+    fn choose_discard(&self, context: &StrategyContext) -> Result<Vec<Card>, CribbageError>;
+
+    /// Returns the card from `context.hand` to play, or `None` to say "Go"
+    fn choose_play(&self, context: &StrategyContext) -> Result<Option<Card>, CribbageError>;
+}
+
+/// The strategy that has always been used by the web api
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DefaultStrategy;
+
+impl DefaultStrategy {
+    /// the cards from `six_card_hand` to put in the crib: the discard with
+    /// the best score for the kept cards without a starter, plus or minus
+    /// the value of the crib in the crib tables
+    pub fn discard(
+        six_card_hand: &[Card],
+        variant: Variant,
+        my_crib: bool,
+    ) -> Result<Vec<Card>, CribbageError> {
+        // get all possible hands
+        let mut max_crib = Vec::<Card>::new();
+        let mut max_score: f32 = -1000.0;
+
+        if six_card_hand.len() != variant.cards_dealt() {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadHand,
+                format!(
+                    "a hand should have {} cards to select a crib.  you passed in {}",
+                    variant.cards_dealt(),
+                    six_card_hand.len()
+                ),
+            ));
+        }
+
+        let kept = variant.cards_kept();
+        let potential_hands =
+            all_combinations_of_size(six_card_hand.to_vec(), kept as u32, kept as u32);
+
+        for hand_to_try in potential_hands {
+            if hand_to_try.len() != kept {
+                panic!("while looking for a crib, the hand size should be {}", kept);
+            }
+
+            // get the score for the current hand we are evaluating
+            let mut score: f32 =
+                score_points(CardSet::from(hand_to_try.as_slice()), None, false) as f32;
+            let crib = get_crib_cards(&six_card_hand.to_vec(), &hand_to_try.clone());
+
+            let expected_value = discard_table_value(&crib, my_crib);
+            if my_crib {
+                score += expected_value;
+            } else {
+                score -= expected_value;
+            }
+
+            if score > max_score {
+                max_score = score;
+                max_crib = crib.clone();
+            }
+        }
+
+        Ok(max_crib)
+    }
+
+    /// the card from `available_cards` to play after `played_cards`, or
+    /// `None` to say "Go"
+    ///
+    /// this is where "strategy" is implemented as selecting what cards to give to the crib is straightforward probability whereas counting
+    /// cards is largely a function of anticipating what the other player is going to play -- e.g. if you have a 2 and a 3, you might play the
+    /// 2 hoping that the other player has only cards with value = 10, so that you can play your 3 and get two points for the 15.  But the other
+    /// player knows this is a normal thing to do, so by playing a 2 you imply you also have a 3.  So maybe the right card to play is a 4 so that the
+    /// apponent plays the 3 to get the run (2, 4, 3) and 3 points - and then bam! -- you play an Ace to get 4 points.  and so on.
+    ///
+    /// my strategy for this is to implement it the way I play the game.  by its nature it is a bunch of "if this then that" calls, so this function
+    /// tends to be very long and very experimental.  I have added logic in the past and then tested it by playing millions (literally) of games of
+    /// one algorithm against the other to see if it really works (it takes about 20 seconds to run a million games on a beefy PC...)
+    pub fn play(
+        played_cards: Vec<Card>,
+        mut available_cards: Vec<Card>, // needs to be mut because we .sort() it
+    ) -> Result<Option<Card>, CribbageError> {
+        let current_count: i32 = played_cards.iter().map(|c| c.value).sum::<i32>();
+        //
+        //  if you only have one card left, play it if you can
+        if available_cards.len() == 1 {
+            if current_count + available_cards[0].value <= 31 {
+                return Ok(Some(available_cards[0]));
+            } else {
+                return Ok(None);
+            }
+        }
+
+        available_cards.sort(); // this is a sort by rank
+
+        //
+        //  here is the first strategic decision - this algorithm will take points if they are available.  for example, when
+        //  humans play, they might decide to defer points (say a pair) in order to get the opponent to start a run.  e.g. if cards_played is something like
+        //  a 2, then it would be common for that person to also have a 3.  they played the 2 hoping that a card of value 10 would be played and then they
+        //  could play a 3 - 15 for 2 points!.  This algorithm will always play a 2 to pair the 2 if it is possible.  Another strategy would be to play a 4
+        //  or an Ace (*never* a 3 - because you don't want the count to be 5 because of how many cards are available with value=10).  in this case, the opponent
+        //  would play the 3 and get a run of 3 for 3 points.  But then you could build on the run and get 4 points (assuming you had the right cards)
+        //
+        //  one of the things I want to explore is defeating the "get a 15" strategy above. the other common strategy (which we use below) is to start with a card
+        //  that you have a pair...the hope is the apponent pairs the card, so you can get 3 of a kind (e.g. play a 4, opponent plays a 4, you play your second 4)
+        //  would it be better to not pair the card and give up the two points?  over the course of the game, stopping 3 of a kind might be worth it.
+        //
+        //  this is an area ripe for innovation!
+        //
+
+        //
+        //  Note: I need the card to play for the max score - i tried to do this in a variety of ways using map/fold and I could get the max score, but the card
+        //        that generated the max score was lost in the map.  i gave up and did it via iteration.
+
+        let mut max: i32 = -1;
+        let mut card_to_play = Card::new(Rank::Unknown, Suit::Unknown);
+        let mut playable_cards: Vec<Card> = Vec::new();
+        for potential_card in available_cards.as_slice() {
+            if let Ok(s) = score_counting_cards_played(played_cards.as_slice(), *potential_card) {
+                if max < s.total_score as i32 {
+                    // innovation idea:  if you can play 2 cards and get the same score, which one should you play?
+                    max = s.total_score as i32;
+                    card_to_play = *potential_card;
+                }
+
+                playable_cards.push(*potential_card);
+            }
+        }
+
+        if playable_cards.is_empty() {
+            // this means we have no valid cards to play
+            return Ok(None);
+        }
+
+        //
+        //  if there is only one card that can be played, return it
+        if playable_cards.len() == 1 {
+            return Ok(Some(playable_cards[0]));
+        }
+
+        //
+        //  get the most points - innovate here and pick the *best* one, not necessarily the one with most points!
+        if max > 0 {
+            return Ok(Some(card_to_play));
+        }
+        //
+        //  we can play, but we can't get points.
+
+        //
+        //  find all combinations of 2 cards in the cards I have left...
+        let two_card_combi = all_combinations_of_size(playable_cards.clone(), 2, 2);
+        //
+        //  we only want to interate through the 2 card combinations once, so
+        //  we set a strategic weight to pick between the various scenarios and
+        //  set the card_to_play when we find one
+        //
+        let mut strategic_weight = 0;
+        let mut card_to_play: Card = Card::new(Rank::Unknown, Suit::Unknown);
+
+        for mut cards in two_card_combi {
+            if cards.len() != 2 {
+                panic!("all_combinations_of_min_size returned the wrong size Vec!");
+            }
+            cards.sort(); // this sort might not be needed, but i'm not sure if all_combinations_of_size guarantees to returns sorted if the input was sorted
+            if cards[0].rank == cards[1].rank
+                && current_count + 3 * cards[0].value <= 31
+                && strategic_weight < 10
+                && cards[1].value != 5
+            {
+                //
+                //  this means that we have a pair and if the opponent plays the same card to get a pair, we can play our second to get 6 points
+                //  an innovation to be tested here is to be careful with playing a 5 - which are typically held to try to get to a 15.
+                card_to_play = cards[1];
+                strategic_weight = 10;
+            }
+            //
+            // I have no pairs, try to start a run
+            match (cards[0].rank as i32 - cards[1].rank as i32).abs() {
+                // there are 2 cards that the other person can play that help us - the values would be
+                // cards[0]-1 or cards[0]+1 - we only check one here, and we pick the optimistic one
+                // because if the card is bigger one, we might go over 31.  but we should run a test
+                // to see how much it matters over the course of a game
+                1 if cards[0].value - 1 + cards[0].value + cards[1].value + current_count <= 31
+                    && strategic_weight < 5 =>
+                {
+                    card_to_play = cards[1]; // I like to play the pick one when possible
+                    strategic_weight = 5;
+                }
+                // this means we are in a situation where we have something like a 7 and a 9, but no 8
+                // cards[0].value + 1 is the value of the card we want the opponent to play
+                //
+                // i want to use "31" here because it is the key rule number, but the linter complains about
+                //  the +1, so turn the linter rule off
+                #[allow(clippy::int_plus_one)]
+                2 if cards[0].value + cards[1].value + cards[0].value + 1 <= 31
+                    && strategic_weight < 5 =>
+                {
+                    if cards[1].rank != Rank::Five {
+                        card_to_play = cards[1]; // I like to play the big one when possible
+                    } else {
+                        card_to_play = cards[0];
+                    }
+
+                    strategic_weight = 5;
+                }
+                _ => {
+                    // we don't have cards within 1 rank of each other -- not worth trying to get a run
+                }
+            }
+            //  remember the cards in case we need  to pick a card assuming that the other player only has cards of rank 10 to play left
+            match cards[0].value + cards[1].value + 10 {
+                15 | 21 if strategic_weight < 8 => {
+                    card_to_play = cards[1];
+                    strategic_weight = 8;
+                }
+                _ => {}
+            }
+        }
+
+        //
+        //  if we get here - we have no cards that we can play that give points, no runs we can try to start, and no pairs to leverage.
+        //  so we will try to pick a card assuming that the other player only has cards of rank 10 to play left
+        //
+        //  Another innovation idea to test is that a human player will figure out what the game is always doing -- so, for example, it is easy
+        //  to figure out that if the computer starts with (say) a 8, then they are probably trying to match a pair.  if they start with a card
+        //  less than 5, they are trying to get to 15.  this extra information can be leveraged to help the player.  So instead we should figure
+        //  out what valid cards could be played and then if then set strategic_weight to be the same for adding up to 15 and for pairs and then
+        //  randomly pick which one to use.
+        //
+
+        if strategic_weight > 0 {
+            if card_to_play.rank == Rank::Unknown {
+                panic!("strategic weight is set but the card_to_play is not not");
+            }
+            return Ok(Some(card_to_play));
+        }
+        //
+        //  if the last card (the highest value) is not a 5, return it
+        //
+        if playable_cards[playable_cards.len() - 1].value != 5 {
+            return Ok(Some(playable_cards[playable_cards.len() - 1]));
+        }
+        //
+        //  if it is a 5, return the next highest one -- we know it is not a pair because if it was, we'd have played it based on what we have above
+        //  we also know that we have at least two cards, because if there was only one, we would have already picked it.
+        Ok(Some(playable_cards[playable_cards.len() - 2]))
+    }
+}
+
+impl Strategy for DefaultStrategy {
+    fn name(&self) -> &str {
+        "default"
+    }
+
+    fn choose_discard(&self, context: &StrategyContext) -> Result<Vec<Card>, CribbageError> {
+        DefaultStrategy::discard(context.hand, context.variant, context.is_my_crib())
+    }
+
+    fn choose_play(&self, context: &StrategyContext) -> Result<Option<Card>, CribbageError> {
+        DefaultStrategy::play(context.pile.to_vec(), context.hand.to_vec())
+    }
+}
+
+/// Discards using the expected value of the hand over every starter, and
+/// pegs like `DefaultStrategy`
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ExpectedValueStrategy;
+
+impl Strategy for ExpectedValueStrategy {
+    fn name(&self) -> &str {
+        "expected_value"
+    }
+
+    fn choose_discard(&self, context: &StrategyContext) -> Result<Vec<Card>, CribbageError> {
+        select_crib_cards_by_expected_value_for(context.hand, context.variant, context.is_my_crib())
+    }
+
+    fn choose_play(&self, context: &StrategyContext) -> Result<Option<Card>, CribbageError> {
+        DefaultStrategy.choose_play(context)
+    }
+}
+
+/// Discards like `DefaultStrategy` and pegs with `choose_card_monte_carlo`
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MonteCarloStrategy {
+    pub budget: SearchBudget,
+}
+
+impl Strategy for MonteCarloStrategy {
+    fn name(&self) -> &str {
+        "monte_carlo"
+    }
+
+    fn choose_discard(&self, context: &StrategyContext) -> Result<Vec<Card>, CribbageError> {
+        DefaultStrategy.choose_discard(context)
+    }
+
+    fn choose_play(&self, context: &StrategyContext) -> Result<Option<Card>, CribbageError> {
+        choose_card_monte_carlo(context, self.budget)
+    }
+}
+
+/// Discards like `ExpectedValueStrategy` and pegs with `choose_card_exact`,
+/// which solves the end of each round exactly.  This is the strongest
+/// strategy and the slowest.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ExactStrategy {
+    /// the budget for sampling when there are too many holdings to solve
+    pub budget: SearchBudget,
+}
+
+impl Strategy for ExactStrategy {
+    fn name(&self) -> &str {
+        "exact"
+    }
+
+    fn choose_discard(&self, context: &StrategyContext) -> Result<Vec<Card>, CribbageError> {
+        ExpectedValueStrategy.choose_discard(context)
+    }
+
+    fn choose_play(&self, context: &StrategyContext) -> Result<Option<Card>, CribbageError> {
+        choose_card_exact(context, self.budget)
+    }
+}
+
+/// Discards by the expected value of the hand plus or minus the points of
+/// the discards alone, and pegs like `DefaultStrategy`.  it doesn't use the
+/// crib tables, so it is the opponent they are generated against.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TableFreeStrategy;
+
+impl Strategy for TableFreeStrategy {
+    fn name(&self) -> &str {
+        "table_free"
+    }
+
+    fn choose_discard(&self, context: &StrategyContext) -> Result<Vec<Card>, CribbageError> {
+        let options = analyze_discards_for(
+            context.hand,
+            context.variant,
+            context.is_my_crib(),
+            CribEstimate::DiscardsOnly,
+        )?;
+        Ok(options[0].discard.clone())
+    }
+
+    fn choose_play(&self, context: &StrategyContext) -> Result<Option<Card>, CribbageError> {
+        DefaultStrategy.choose_play(context)
+    }
+}
+
+/// Plays to win a two player game to 121 instead of to score the most
+/// points: it discards with `select_crib_cards_to_win` and pegs with
+/// `choose_card_to_win`, which both weigh each choice by the chance of
+/// winning from the score.  Other variants and game lengths have no win
+/// probabilities, so it discards like `ExpectedValueStrategy` and pegs
+/// like `MonteCarloStrategy` in them.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ScoreAwareStrategy {
+    pub budget: SearchBudget,
+}
+
+impl ScoreAwareStrategy {
+    fn knows_the_odds(context: &StrategyContext) -> bool {
+        context.variant == Variant::TwoPlayer && context.rules.game_length == GAME_LENGTH
+    }
+}
+
+impl Strategy for ScoreAwareStrategy {
+    fn name(&self) -> &str {
+        "score_aware"
+    }
+
+    fn choose_discard(&self, context: &StrategyContext) -> Result<Vec<Card>, CribbageError> {
+        if !ScoreAwareStrategy::knows_the_odds(context) {
+            return ExpectedValueStrategy.choose_discard(context);
+        }
+        select_crib_cards_to_win(
+            context.hand,
+            context.scores[context.player],
+            context.scores[1 - context.player],
+            context.is_my_crib(),
+        )
+    }
+
+    fn choose_play(&self, context: &StrategyContext) -> Result<Option<Card>, CribbageError> {
+        if !ScoreAwareStrategy::knows_the_odds(context) {
+            return choose_card_monte_carlo(context, self.budget);
+        }
+        choose_card_to_win(context, self.budget)
+    }
+}
+
+/// the names that `strategy_by_name` knows about
+pub const STRATEGY_NAMES: &[&str] = &[
+    "default",
+    "expected_value",
+    "monte_carlo",
+    "exact",
+    "table_free",
+    "score_aware",
+];
+
+/// Returns the strategy whose `name()` is `name`
+pub fn strategy_by_name(name: &str) -> Option<Box<dyn Strategy>> {
+    match name {
+        "default" => Some(Box::new(DefaultStrategy)),
+        "expected_value" => Some(Box::new(ExpectedValueStrategy)),
+        "monte_carlo" => Some(Box::new(MonteCarloStrategy::default())),
+        "exact" => Some(Box::new(ExactStrategy::default())),
+        "table_free" => Some(Box::new(TableFreeStrategy)),
+        "score_aware" => Some(Box::new(ScoreAwareStrategy::default())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{Rank::*, Suit as Of};
+    use crate::new_card as c;
+    use crate::select_cards::select_crib_cards;
+
+    #[test]
+    fn known_cards() {
+        let hand = [c!(Five, Of::Hearts), c!(Six, Of::Hearts)];
+        let discarded = [c!(Two, Of::Clubs), c!(Three, Of::Clubs)];
+        let history = [
+            PeggingAction::Play {
+                player: 1,
+                card: c!(King, Of::Spades),
+            },
+            PeggingAction::Play {
+                player: 0,
+                card: c!(Four, Of::Hearts),
+            },
+            PeggingAction::Go { player: 1 },
+        ];
+        let mut context = StrategyContext {
+            player: 0,
+            dealer: 1,
+            partner: None,
+            variant: Variant::TwoPlayer,
+            rules: RuleSet::default(),
+            scores: &[10, 20],
+            hand: &hand,
+            discarded: &discarded,
+            starter: Some(c!(Ace, Of::Diamonds)),
+            pile: &[c!(King, Of::Spades), c!(Four, Of::Hearts)],
+            history: &history,
+        };
+        assert!(!context.is_my_crib());
+        assert_eq!(context.count(), 14);
+        assert_eq!(context.known_cards().len(), 7);
+        assert!(context.partner_played().is_empty());
+
+        // the same plays with seat 1 as the partner in a partnership game
+        context.partner = Some(1);
+        assert!(context.is_my_crib());
+        assert_eq!(context.partner_played(), vec![c!(King, Of::Spades)]);
+    }
+
+    #[test]
+    fn strategies_by_name() {
+        for name in STRATEGY_NAMES {
+            assert_eq!(strategy_by_name(name).unwrap().name(), *name);
+        }
+        assert!(strategy_by_name("nope").is_none());
+    }
+
+    #[test]
+    fn default_strategy_matches_select_cards() {
+        let hand = [
+            c!(Five, Of::Hearts),
+            c!(Five, Of::Clubs),
+            c!(Five, Of::Spades),
+            c!(Jack, Of::Diamonds),
+            c!(Six, Of::Clubs),
+            c!(Four, Of::Diamonds),
+        ];
+        for my_crib in [true, false] {
+            let context = StrategyContext::from_hand(&hand, my_crib);
+            assert_eq!(
+                DefaultStrategy.choose_discard(&context).unwrap(),
+                select_crib_cards(&hand, my_crib).unwrap()
+            );
+        }
+
+        let pile = [c!(Four, Of::Spades), c!(Ten, Of::Diamonds)];
+        let hand = [
+            c!(Ace, Of::Spades),
+            c!(Six, Of::Spades),
+            c!(Jack, Of::Spades),
+        ];
+        let mut context = StrategyContext::from_hand(&hand, false);
+        context.pile = &pile;
+        assert_eq!(
+            DefaultStrategy.choose_play(&context).unwrap(),
+            Some(c!(Ace, Of::Spades))
+        );
+    }
+}
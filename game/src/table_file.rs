@@ -0,0 +1,150 @@
+//! `table_file` reads the text files that precomputed tables are shipped
+//! in.  A file is a version line, lines of a key and its value, and tables
+//! that are a name line followed by a line of numbers for each row.  `#`
+//! starts a comment, and blank lines are skipped.  Errors say which line
+//! they are on.
+
+use crate::cribbage_errors::{CribbageError, CribbageErrorKind};
+use std::{fmt, str::FromStr};
+
+/// The lines of a table file that are left to read
+pub(crate) struct TableLines<'a> {
+    /// what the file holds, such as "the crib tables", for errors
+    what: &'static str,
+    lines: Vec<(usize, &'a str)>,
+    next: usize,
+}
+
+impl<'a> TableLines<'a> {
+    pub(crate) fn new(text: &'a str, what: &'static str) -> TableLines<'a> {
+        TableLines {
+            what,
+            lines: text
+                .lines()
+                .enumerate()
+                .map(|(number, line)| (number + 1, line.split('#').next().unwrap().trim()))
+                .filter(|(_, line)| !line.is_empty())
+                .collect(),
+            next: 0,
+        }
+    }
+
+    /// the next line and its number, where `expected` says what it should be
+    pub(crate) fn next_line(&mut self, expected: &str) -> Result<(usize, &'a str), CribbageError> {
+        let line = self.lines.get(self.next).copied().ok_or_else(|| {
+            CribbageError::new(
+                CribbageErrorKind::ParseError,
+                format!("{} end before {}", self.what, expected),
+            )
+        })?;
+        self.next += 1;
+        Ok(line)
+    }
+
+    /// reads the version line, which has to be `supported`
+    pub(crate) fn version(&mut self, supported: u32) -> Result<u32, CribbageError> {
+        let (number, version) = self.keyed("version")?;
+        match version.parse() {
+            Ok(version) if version == supported => Ok(version),
+            Ok(version) => Err(line_error(
+                number,
+                format!(
+                    "{} are version {}, but version {} is supported",
+                    self.what, version, supported
+                ),
+            )),
+            Err(_) => Err(line_error(number, format!("bad version: {}", version))),
+        }
+    }
+
+    /// reads the line with `key` and parses its value
+    pub(crate) fn value<T: FromStr>(&mut self, key: &str) -> Result<T, CribbageError> {
+        let (number, value) = self.keyed(key)?;
+        value
+            .parse()
+            .map_err(|_| line_error(number, format!("bad {}: {}", key, value)))
+    }
+
+    /// reads a line that has to be `name`, such as the name of a table
+    pub(crate) fn name(&mut self, name: &str) -> Result<(), CribbageError> {
+        let (number, line) = self.next_line(name)?;
+        if line != name {
+            return Err(line_error(number, format!("expected {}", name)));
+        }
+        Ok(())
+    }
+
+    /// reads a row of `length` numbers, or of any length for `None`
+    pub(crate) fn row<T: FromStr>(
+        &mut self,
+        table: &str,
+        length: Option<usize>,
+    ) -> Result<Vec<T>, CribbageError> {
+        let (number, line) = self.next_line(table)?;
+        let values = line
+            .split_whitespace()
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| line_error(number, format!("bad value: {}", value)))
+            })
+            .collect::<Result<Vec<T>, CribbageError>>()?;
+        match length {
+            Some(length) if values.len() != length => Err(line_error(
+                number,
+                format!("a row has {} values, not {}", values.len(), length),
+            )),
+            _ => Ok(values),
+        }
+    }
+
+    /// checks that there is nothing left
+    pub(crate) fn finish(&self) -> Result<(), CribbageError> {
+        match self.lines.get(self.next) {
+            Some((number, _)) => Err(line_error(*number, format!("{} have ended", self.what))),
+            None => Ok(()),
+        }
+    }
+
+    fn keyed(&mut self, key: &str) -> Result<(usize, &'a str), CribbageError> {
+        let (number, line) = self.next_line(key)?;
+        match line.split_once(' ') {
+            Some((k, value)) if k == key => Ok((number, value.trim())),
+            _ => Err(line_error(number, format!("expected {}", key))),
+        }
+    }
+}
+
+/// writes `values` as a row with `decimals` places
+pub(crate) fn write_row<T: Into<f64> + Copy>(
+    f: &mut fmt::Formatter,
+    values: &[T],
+    decimals: usize,
+) -> fmt::Result {
+    let values: Vec<String> = values
+        .iter()
+        .map(|v| format!("{:.*}", decimals, (*v).into()))
+        .collect();
+    writeln!(f, "{}", values.join(" "))
+}
+
+fn line_error(number: usize, message: String) -> CribbageError {
+    CribbageError::new(
+        CribbageErrorKind::ParseError,
+        format!("line {}: {}", number, message),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_rows_say_how_long_they_are() {
+        let mut lines = TableLines::new("t\n1 2 3\n", "the test table");
+        lines.name("t").unwrap();
+        let err = lines.row::<f64>("t", Some(4)).unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::ParseError);
+        assert_eq!(err.message, "line 2: a row has 3 values, not 4");
+    }
+}
@@ -0,0 +1,159 @@
+//! `variant` describes how many people play, how the cards are dealt and
+//! who plays together.  The scoring is the same in every variant and the
+//! crib always has four cards.  Each player keeps four cards for the show,
+//! except in the five card game where they keep three.
+
+use crate::rules::RuleSet;
+use serde::{Deserialize, Serialize};
+
+/// the number of cards in the crib
+pub const CRIB_SIZE: usize = 4;
+
+/// The ways cribbage can be dealt
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Variant {
+    /// six cards each and two discards each
+    #[default]
+    TwoPlayer,
+    /// five cards each and one discard each.  the dealer also deals one card
+    /// straight into the crib.
+    ThreePlayer,
+    /// two partnerships of two, sitting opposite each other: seats 0 and 2
+    /// against seats 1 and 3.  five cards each and one discard each.
+    FourPlayer,
+    /// the original two player game: five cards each and two discards each,
+    /// so hands have three cards.  the pone starts with 3 points and the
+    /// game is usually played to 61.
+    FiveCard,
+}
+
+impl Variant {
+    /// the variant that deals `cards` to each player, for callers that only
+    /// have a hand.  five cards are taken to be three player cribbage, which
+    /// discards the same way as four player cribbage.  the five card game
+    /// also deals five, so callers that might be playing it have to say so
+    /// with the variant instead of using this.
+    pub fn from_cards_dealt(cards: usize) -> Option<Variant> {
+        match cards {
+            6 => Some(Variant::TwoPlayer),
+            5 => Some(Variant::ThreePlayer),
+            _ => None,
+        }
+    }
+
+    /// the usual rules for the variant: the five card game is played to 61
+    /// and the others to 121
+    pub fn default_rules(self) -> RuleSet {
+        match self {
+            Variant::FiveCard => RuleSet::short_game(),
+            _ => RuleSet::standard(),
+        }
+    }
+
+    pub fn players(self) -> usize {
+        match self {
+            Variant::TwoPlayer | Variant::FiveCard => 2,
+            Variant::ThreePlayer => 3,
+            Variant::FourPlayer => 4,
+        }
+    }
+
+    /// the number of sides that keep score.  each player is their own side,
+    /// except in partnership games.
+    pub fn teams(self) -> usize {
+        match self {
+            Variant::FourPlayer => 2,
+            _ => self.players(),
+        }
+    }
+
+    /// the side that the player in `seat` scores for
+    pub fn team_of(self, seat: usize) -> usize {
+        seat % self.teams()
+    }
+
+    /// the partner of the player in `seat`, if they have one
+    pub fn partner_of(self, seat: usize) -> Option<usize> {
+        match self {
+            Variant::FourPlayer => Some((seat + 2) % 4),
+            _ => None,
+        }
+    }
+
+    /// the number of cards dealt to each player
+    pub fn cards_dealt(self) -> usize {
+        match self {
+            Variant::TwoPlayer => 6,
+            Variant::ThreePlayer | Variant::FourPlayer | Variant::FiveCard => 5,
+        }
+    }
+
+    /// the number of cards each player puts in the crib
+    pub fn cards_discarded(self) -> usize {
+        match self {
+            Variant::TwoPlayer | Variant::FiveCard => 2,
+            Variant::ThreePlayer | Variant::FourPlayer => 1,
+        }
+    }
+
+    /// the points the pone gets at the start of the game, for the dealer's
+    /// advantage of the first crib
+    pub fn pone_start_points(self) -> u32 {
+        match self {
+            Variant::FiveCard => 3,
+            _ => 0,
+        }
+    }
+
+    /// the number of cards each player keeps for the show
+    pub fn cards_kept(self) -> usize {
+        self.cards_dealt() - self.cards_discarded()
+    }
+
+    /// the number of cards dealt from the deck straight into the crib
+    pub fn cards_to_crib(self) -> usize {
+        CRIB_SIZE - self.players() * self.cards_discarded()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_crib_has_four_cards() {
+        for variant in [
+            Variant::TwoPlayer,
+            Variant::ThreePlayer,
+            Variant::FourPlayer,
+        ] {
+            assert_eq!(variant.cards_kept(), 4);
+            assert_eq!(variant.pone_start_points(), 0);
+            assert_eq!(
+                variant.players() * variant.cards_discarded() + variant.cards_to_crib(),
+                CRIB_SIZE
+            );
+        }
+        assert_eq!(Variant::ThreePlayer.cards_to_crib(), 1);
+        assert_eq!(Variant::FourPlayer.cards_to_crib(), 0);
+
+        let variant = Variant::FiveCard;
+        assert_eq!(variant.cards_kept(), 3);
+        assert_eq!(variant.cards_to_crib(), 0);
+        assert_eq!(variant.pone_start_points(), 3);
+        assert_eq!(variant.default_rules().game_length, 61);
+    }
+
+    #[test]
+    fn partnerships() {
+        let variant = Variant::FourPlayer;
+        assert_eq!(variant.teams(), 2);
+        assert_eq!(
+            (0..4).map(|s| variant.team_of(s)).collect::<Vec<_>>(),
+            vec![0, 1, 0, 1]
+        );
+        assert_eq!(variant.partner_of(1), Some(3));
+        assert_eq!(Variant::ThreePlayer.team_of(2), 2);
+        assert_eq!(Variant::TwoPlayer.partner_of(0), None);
+    }
+}
@@ -0,0 +1,721 @@
+//! `win_probability` estimates the chance of winning a two player game to
+//! 121 from any score, which is what decisions near the end of a game
+//! should be made on instead of the points they are expected to score.
+//!
+//! `DealDistribution` is how likely each number of points is in one deal:
+//! the points both players peg, the pone's hand, the dealer's hand and the
+//! crib.  It is measured by playing `DefaultStrategy` against itself.  A
+//! deal is then played out in the order points are scored: the pegging,
+//! the pone's hand and then the dealer's hand and crib, so that at 119 to
+//! 119 the pone is more likely to win.  `WinProbabilities` works back from
+//! the end of the game to find the chance the dealer wins from every
+//! position at the start of a deal.
+//!
+//! The probabilities are shipped in `tables/win_probabilities.txt`, made
+//! by the `generate_win_probabilities` tool, along with the distribution
+//! they were worked out from.
+
+use crate::{
+    card_set::CardSet,
+    cards::Card,
+    cribbage_errors::{CribbageError, CribbageErrorKind},
+    game::{Game, GamePhase},
+    rules::{RuleSet, GAME_LENGTH},
+    scoring::score_points,
+    strategy::{DefaultStrategy, Strategy},
+    table_file::{write_row, TableLines},
+    variant::Variant,
+};
+use lazy_static::lazy_static;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// the version of the win probability file format
+pub const WIN_PROBABILITIES_VERSION: u32 = 1;
+
+/// the decimal places of the numbers in the file
+const DECIMALS: usize = 6;
+
+/// the deals played in each simulated game.  the game is made long enough
+/// that nobody wins, so that no deal is cut short.
+const DEALS_PER_GAME: usize = 100;
+
+lazy_static! {
+    static ref STANDARD: WinProbabilities = include_str!("../tables/win_probabilities.txt")
+        .parse()
+        .expect("the shipped win probabilities should parse");
+}
+
+/// How likely each number of points is in one deal of a two player game.
+/// Each distribution is indexed by points and adds up to 1.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DealDistribution {
+    /// the deals it was measured from
+    pub deals: usize,
+    pub seed: u64,
+    /// `pegging[pone][dealer]` is the chance the pone pegs `pone` points
+    /// and the dealer pegs `dealer`, his heels included
+    pub pegging: Vec<Vec<f64>>,
+    pub pone_hand: Vec<f64>,
+    pub dealer_hand: Vec<f64>,
+    pub crib: Vec<f64>,
+}
+
+impl DealDistribution {
+    /// Measures the distribution from `deals` deals of `DefaultStrategy`
+    /// against itself, played on all cores
+    pub fn simulate(deals: usize, seed: u64) -> Result<DealDistribution, CribbageError> {
+        let games = deals.div_ceil(DEALS_PER_GAME);
+        let tallies = (0..games)
+            .into_par_iter()
+            .map(|i| {
+                let wanted = DEALS_PER_GAME.min(deals - i * DEALS_PER_GAME);
+                tally_deals(wanted, seed.wrapping_add(i as u64))
+            })
+            .collect::<Result<Vec<Tally>, CribbageError>>()?;
+
+        let mut pegging: Vec<Vec<u64>> = Vec::new();
+        let (mut pone_hand, mut dealer_hand, mut crib) = (Vec::new(), Vec::new(), Vec::new());
+        for tally in tallies {
+            for (pone, dealer) in tally.pegging {
+                if pegging.len() <= pone {
+                    pegging.resize(pone + 1, Vec::new());
+                }
+                add(&mut pegging[pone], dealer);
+            }
+            tally
+                .pone_hand
+                .into_iter()
+                .for_each(|p| add(&mut pone_hand, p));
+            tally
+                .dealer_hand
+                .into_iter()
+                .for_each(|p| add(&mut dealer_hand, p));
+            tally.crib.into_iter().for_each(|p| add(&mut crib, p));
+        }
+        // every row has a chance for each of the dealer's points
+        let width = pegging.iter().map(Vec::len).max().unwrap_or(0);
+        pegging.iter_mut().for_each(|row| row.resize(width, 0));
+        Ok(DealDistribution {
+            deals,
+            seed,
+            pegging: pegging
+                .iter()
+                .map(|row| row.iter().map(|n| *n as f64 / deals as f64).collect())
+                .collect(),
+            pone_hand: normalized(&pone_hand),
+            dealer_hand: normalized(&dealer_hand),
+            crib: normalized(&crib),
+        })
+    }
+
+    /// the distribution of the dealer's hand and crib together
+    pub fn dealer_count(&self) -> Vec<f64> {
+        convolve(&self.dealer_hand, &self.crib)
+    }
+
+    /// the points the pone and the dealer are expected to score in a deal
+    pub fn expected_points(&self) -> (f64, f64) {
+        let mut pegging = (0.0, 0.0);
+        for (pone, row) in self.pegging.iter().enumerate() {
+            for (dealer, chance) in row.iter().enumerate() {
+                pegging.0 += pone as f64 * chance;
+                pegging.1 += dealer as f64 * chance;
+            }
+        }
+        (
+            pegging.0 + mean(&self.pone_hand),
+            pegging.1 + mean(&self.dealer_count()),
+        )
+    }
+}
+
+/// How good a position is, as the web api reports it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PositionOdds {
+    pub my_score: u32,
+    pub opponent_score: u32,
+    pub my_deal: bool,
+    /// the chance of winning from the start of the deal
+    pub win_probability: f64,
+    /// the points each player still needs to reach 121
+    pub points_needed: u32,
+    pub opponent_points_needed: u32,
+    /// the points each player is expected to score in this deal
+    pub expected_points: f64,
+    pub opponent_expected_points: f64,
+}
+
+/// The chance of winning from every position of a two player game to 121
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WinProbabilities {
+    distribution: DealDistribution,
+    /// `dealer[dealer score][pone score]` is the chance the dealer wins from
+    /// the start of a deal
+    dealer: Vec<Vec<f64>>,
+}
+
+impl WinProbabilities {
+    /// the probabilities in `tables/win_probabilities.txt`
+    pub fn standard() -> &'static WinProbabilities {
+        &STANDARD
+    }
+
+    /// Works out the chance of winning from every position when every deal
+    /// follows `distribution`
+    pub fn new(distribution: DealDistribution) -> WinProbabilities {
+        let n = GAME_LENGTH as usize;
+        let pone_hand = &distribution.pone_hand;
+        let dealer_count = distribution.dealer_count();
+        // a deal where nobody pegs leaves the position as it was, so it is
+        // left out and the other outcomes scaled up to make up for it
+        let no_pegging = distribution
+            .pegging
+            .first()
+            .and_then(|row| row.first())
+            .copied()
+            .unwrap_or(0.0);
+
+        // the chance the dealer wins from the start of the deal, from the
+        // pone's count and from the dealer's count.  every deal pegs at least
+        // one point, so each position only depends on positions with more
+        // points, and those with the same points but later in the deal.
+        let mut dealer = vec![vec![0.0; n]; n];
+        let mut at_pone_count = vec![vec![0.0; n]; n];
+        let mut at_dealer_count = vec![vec![0.0; n]; n];
+        for total in (0..2 * n - 1).rev() {
+            let positions: Vec<(usize, usize)> = (0..n)
+                .filter(|p| total >= *p && total - p < n)
+                .map(|p| (p, total - p))
+                .collect();
+            for (p, q) in positions.iter().copied() {
+                let mut wins = 0.0;
+                for (x, row) in distribution.pegging.iter().enumerate() {
+                    for (y, chance) in row.iter().enumerate() {
+                        if x + y == 0 {
+                            continue;
+                        }
+                        // the pone usually pegs first
+                        wins += chance
+                            * if q + x >= n {
+                                0.0
+                            } else if p + y >= n {
+                                1.0
+                            } else {
+                                at_pone_count[p + y][q + x]
+                            };
+                    }
+                }
+                dealer[p][q] = wins / (1.0 - no_pegging);
+            }
+            for (p, q) in positions.iter().copied() {
+                at_dealer_count[p][q] = dealer_count
+                    .iter()
+                    .enumerate()
+                    .map(|(d, chance)| {
+                        chance
+                            * if p + d >= n {
+                                1.0
+                            } else {
+                                1.0 - dealer[q][p + d]
+                            }
+                    })
+                    .sum();
+            }
+            for (p, q) in positions.iter().copied() {
+                at_pone_count[p][q] = pone_hand
+                    .iter()
+                    .enumerate()
+                    .map(|(h, chance)| {
+                        chance
+                            * if q + h >= n {
+                                0.0
+                            } else {
+                                at_dealer_count[p][q + h]
+                            }
+                    })
+                    .sum();
+            }
+        }
+        WinProbabilities {
+            distribution,
+            dealer,
+        }
+    }
+
+    /// Simulates `deals` deals and works out the probabilities from them
+    pub fn generate(deals: usize, seed: u64) -> Result<WinProbabilities, CribbageError> {
+        Ok(WinProbabilities::new(DealDistribution::simulate(
+            deals, seed,
+        )?))
+    }
+
+    /// Reports how good the position is at the start of a deal with `me`
+    /// points to the opponent's `opponent`
+    pub fn odds(
+        &self,
+        me: u32,
+        opponent: u32,
+        my_deal: bool,
+    ) -> Result<PositionOdds, CribbageError> {
+        if me.max(opponent) > GAME_LENGTH || me.min(opponent) == GAME_LENGTH {
+            return Err(CribbageError::new(
+                CribbageErrorKind::BadCount,
+                format!(
+                    "{} to {} isn't a score in a game to {}",
+                    me, opponent, GAME_LENGTH
+                ),
+            ));
+        }
+        let (pone, dealer) = self.distribution.expected_points();
+        let (mine, theirs) = if my_deal {
+            (dealer, pone)
+        } else {
+            (pone, dealer)
+        };
+        Ok(PositionOdds {
+            my_score: me,
+            opponent_score: opponent,
+            my_deal,
+            win_probability: self.at_deal(me, opponent, my_deal),
+            points_needed: GAME_LENGTH - me,
+            opponent_points_needed: GAME_LENGTH - opponent,
+            expected_points: mine,
+            opponent_expected_points: theirs,
+        })
+    }
+
+    pub fn distribution(&self) -> &DealDistribution {
+        &self.distribution
+    }
+
+    /// the chance of winning at the start of a deal, with `me` points to the
+    /// opponent's `opponent`
+    pub fn at_deal(&self, me: u32, opponent: u32, my_deal: bool) -> f64 {
+        if me >= GAME_LENGTH {
+            1.0
+        } else if opponent >= GAME_LENGTH {
+            0.0
+        } else if my_deal {
+            self.dealer[me as usize][opponent as usize]
+        } else {
+            1.0 - self.dealer[opponent as usize][me as usize]
+        }
+    }
+
+    /// the distributions of the points counted after the pegging by me and
+    /// by the opponent, when my hand follows `my_hand` and the crib is
+    /// expected to score `crib` points
+    pub fn counts(&self, my_deal: bool, my_hand: &[f64], crib: f64) -> (Vec<f64>, Vec<f64>) {
+        let distribution = &self.distribution;
+        let crib = shifted(&distribution.crib, crib - mean(&distribution.crib));
+        if my_deal {
+            (convolve(my_hand, &crib), distribution.pone_hand.clone())
+        } else {
+            (my_hand.to_vec(), convolve(&distribution.dealer_hand, &crib))
+        }
+    }
+
+    /// the chance of winning a deal that is about to be pegged, where the
+    /// hand and crib points of each player follow `my_count` and
+    /// `their_count` instead of the usual distributions
+    pub fn before_pegging(
+        &self,
+        me: u32,
+        opponent: u32,
+        my_deal: bool,
+        my_count: &[f64],
+        their_count: &[f64],
+    ) -> f64 {
+        let mut wins = 0.0;
+        for (pone, row) in self.distribution.pegging.iter().enumerate() {
+            for (dealer, chance) in row.iter().enumerate() {
+                if *chance == 0.0 {
+                    continue;
+                }
+                let (mine, theirs) = if my_deal {
+                    (dealer, pone)
+                } else {
+                    (pone, dealer)
+                };
+                let (me, opponent) = (me + mine as u32, opponent + theirs as u32);
+                let (pone_out, dealer_out) = if my_deal {
+                    (opponent >= GAME_LENGTH, me >= GAME_LENGTH)
+                } else {
+                    (me >= GAME_LENGTH, opponent >= GAME_LENGTH)
+                };
+                // the pone usually pegs first
+                let pone_wins = if pone_out {
+                    1.0
+                } else if dealer_out {
+                    0.0
+                } else if my_deal {
+                    1.0 - self.after_pegging(me, opponent, my_deal, my_count, their_count)
+                } else {
+                    self.after_pegging(me, opponent, my_deal, my_count, their_count)
+                };
+                wins += chance * if my_deal { 1.0 - pone_wins } else { pone_wins };
+            }
+        }
+        wins
+    }
+
+    /// the chance of winning once the pegging is over.  the pone counts
+    /// their hand, then the dealer counts their hand and crib, then the
+    /// next deal starts.
+    pub fn after_pegging(
+        &self,
+        me: u32,
+        opponent: u32,
+        my_deal: bool,
+        my_count: &[f64],
+        their_count: &[f64],
+    ) -> f64 {
+        if me >= GAME_LENGTH {
+            return 1.0;
+        }
+        if opponent >= GAME_LENGTH {
+            return 0.0;
+        }
+        let (pone, dealer, pone_count, dealer_count) = if my_deal {
+            (opponent, me, their_count, my_count)
+        } else {
+            (me, opponent, my_count, their_count)
+        };
+        let mut pone_wins = 0.0;
+        for (h, pone_chance) in pone_count.iter().enumerate() {
+            if *pone_chance == 0.0 {
+                continue;
+            }
+            let pone = pone + h as u32;
+            if pone >= GAME_LENGTH {
+                pone_wins += pone_chance;
+                continue;
+            }
+            for (d, dealer_chance) in dealer_count.iter().enumerate() {
+                // the pone deals next
+                pone_wins +=
+                    pone_chance * dealer_chance * self.at_deal(pone, dealer + d as u32, true);
+            }
+        }
+        if my_deal {
+            1.0 - pone_wins
+        } else {
+            pone_wins
+        }
+    }
+}
+
+/// the distribution of the points `kept` scores with each of `starters`
+pub fn hand_distribution(kept: &[Card], starters: &[Card]) -> Vec<f64> {
+    let kept = CardSet::from(kept);
+    let mut counts = Vec::new();
+    for starter in starters {
+        add(
+            &mut counts,
+            score_points(kept, Some(*starter), false) as usize,
+        );
+    }
+    normalized(&counts)
+}
+
+/// The file the probabilities are shipped in: the version, the deals and
+/// seed of the simulation, each distribution as a name and a row, then a
+/// row of the chance the dealer wins for each score of the dealer
+impl fmt::Display for WinProbabilities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let distribution = &self.distribution;
+        writeln!(
+            f,
+            "# the chance of winning a two player game to {}",
+            GAME_LENGTH
+        )?;
+        writeln!(f, "version {}", WIN_PROBABILITIES_VERSION)?;
+        writeln!(f, "deals {}", distribution.deals)?;
+        writeln!(f, "seed {}", distribution.seed)?;
+        writeln!(f, "# a row for each of the pone's pegging points")?;
+        writeln!(f, "pegging {}", distribution.pegging.len())?;
+        for row in &distribution.pegging {
+            write_row(f, row, DECIMALS)?;
+        }
+        for (name, row) in [
+            ("pone_hand", &distribution.pone_hand),
+            ("dealer_hand", &distribution.dealer_hand),
+            ("crib", &distribution.crib),
+        ] {
+            writeln!(f, "{}", name)?;
+            write_row(f, row, DECIMALS)?;
+        }
+        writeln!(f, "# a row for each of the dealer's scores")?;
+        writeln!(f, "dealer_wins")?;
+        for row in &self.dealer {
+            write_row(f, row, DECIMALS)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for WinProbabilities {
+    type Err = CribbageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = TableLines::new(s, "the win probabilities");
+        lines.version(WIN_PROBABILITIES_VERSION)?;
+        let deals = lines.value("deals")?;
+        let seed = lines.value("seed")?;
+        let rows: usize = lines.value("pegging")?;
+        let pegging = (0..rows)
+            .map(|_| lines.row("pegging", None))
+            .collect::<Result<Vec<Vec<f64>>, CribbageError>>()?;
+        let mut distribution = |name: &str| {
+            lines.name(name)?;
+            lines.row(name, None)
+        };
+        let pone_hand = distribution("pone_hand")?;
+        let dealer_hand = distribution("dealer_hand")?;
+        let crib = distribution("crib")?;
+        lines.name("dealer_wins")?;
+        let n = GAME_LENGTH as usize;
+        let dealer = (0..n)
+            .map(|_| lines.row("dealer_wins", Some(n)))
+            .collect::<Result<Vec<Vec<f64>>, CribbageError>>()?;
+        lines.finish()?;
+
+        Ok(WinProbabilities {
+            distribution: DealDistribution {
+                deals,
+                seed,
+                pegging,
+                pone_hand,
+                dealer_hand,
+                crib,
+            },
+            dealer,
+        })
+    }
+}
+
+/// the points of each part of the deals of one game
+#[derive(Default)]
+struct Tally {
+    pegging: Vec<(usize, usize)>,
+    pone_hand: Vec<usize>,
+    dealer_hand: Vec<usize>,
+    crib: Vec<usize>,
+}
+
+/// plays `deals` deals of a game too long to win and tallies their points
+fn tally_deals(deals: usize, seed: u64) -> Result<Tally, CribbageError> {
+    let rules = RuleSet {
+        game_length: u32::MAX,
+        ..RuleSet::standard()
+    };
+    let mut game = Game::with_variant(seed, Variant::TwoPlayer, rules)?;
+    let strategies: [&dyn Strategy; 2] = [&DefaultStrategy, &DefaultStrategy];
+    let mut tally = Tally::default();
+    let mut before = vec![0; 2];
+    while tally.crib.len() < deals {
+        let phase = game.phase();
+        if phase == GamePhase::Deal {
+            before = game.scores().to_vec();
+        }
+        let (dealer, pone) = (game.dealer(), game.pone());
+        game.step(&strategies)?;
+        let points = |player: usize| (game.scores()[player] - before[player]) as usize;
+        match (phase, game.phase()) {
+            (GamePhase::Pegging, GamePhase::Show) => {
+                tally.pegging.push((points(pone), points(dealer)))
+            }
+            (GamePhase::Show, GamePhase::Show) => tally.pone_hand.push(points(pone)),
+            (GamePhase::Show, GamePhase::Crib) => tally.dealer_hand.push(points(dealer)),
+            (GamePhase::Crib, _) => tally.crib.push(points(dealer)),
+            _ => continue,
+        }
+        before = game.scores().to_vec();
+    }
+    Ok(tally)
+}
+
+/// counts one more of `points`
+fn add(counts: &mut Vec<u64>, points: usize) {
+    if counts.len() <= points {
+        counts.resize(points + 1, 0);
+    }
+    counts[points] += 1;
+}
+
+fn normalized(counts: &[u64]) -> Vec<f64> {
+    let total: u64 = counts.iter().sum();
+    counts.iter().map(|n| *n as f64 / total as f64).collect()
+}
+
+/// the distribution of the sum of two independent numbers of points
+pub fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut sum = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            sum[i + j] += x * y;
+        }
+    }
+    sum
+}
+
+/// `distribution` moved up by `points`, or down if it is negative.  a part of
+/// a point splits each chance between the points either side, so the mean
+/// moves by exactly `points` unless it would go below 0.
+pub fn shifted(distribution: &[f64], points: f64) -> Vec<f64> {
+    let whole = points.floor();
+    let part = points - whole;
+    let mut moved = vec![0.0; (distribution.len() as f64 + whole.max(0.0)) as usize + 1];
+    for (i, chance) in distribution.iter().enumerate() {
+        let to = (i as f64 + whole).max(0.0) as usize;
+        moved[to] += chance * (1.0 - part);
+        moved[(i as f64 + whole + 1.0).max(0.0) as usize] += chance * part;
+    }
+    moved
+}
+
+/// the mean of a distribution of points
+pub fn mean(distribution: &[f64]) -> f64 {
+    distribution
+        .iter()
+        .enumerate()
+        .map(|(points, chance)| points as f64 * chance)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distributions_add_up() {
+        let distribution = WinProbabilities::standard().distribution();
+        let pegging: f64 = distribution.pegging.iter().flatten().sum();
+        // the file rounds each chance to 6 places
+        assert!((pegging - 1.0).abs() < 1e-4);
+        assert_eq!(distribution.pegging[0][0], 0.0);
+        for d in [
+            &distribution.pone_hand,
+            &distribution.dealer_hand,
+            &distribution.crib,
+        ] {
+            assert!((d.iter().sum::<f64>() - 1.0).abs() < 1e-4);
+        }
+        // hands average a little over 4 points, cribs a little under
+        assert!(mean(&distribution.pone_hand) > 4.0 && mean(&distribution.pone_hand) < 9.0);
+        assert!(mean(&distribution.crib) > 3.0 && mean(&distribution.crib) < 6.0);
+        assert!(
+            (mean(&distribution.dealer_count())
+                - mean(&distribution.dealer_hand)
+                - mean(&distribution.crib))
+            .abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn probabilities_make_sense() {
+        let win = WinProbabilities::standard();
+        // the dealer has the edge at the start
+        let start = win.at_deal(0, 0, true);
+        assert!(start > 0.5 && start < 0.65, "{}", start);
+        assert_eq!(win.at_deal(0, 0, false), 1.0 - start);
+        // more points are always better
+        for me in (0..120).step_by(7) {
+            for opponent in (0..120).step_by(11) {
+                for my_deal in [true, false] {
+                    let p = win.at_deal(me, opponent, my_deal);
+                    assert!((0.0..=1.0).contains(&p));
+                    assert!(win.at_deal(me + 1, opponent, my_deal) >= p - 1e-5);
+                }
+            }
+        }
+        // at 119 each the pone pegs and counts first
+        assert!(win.at_deal(119, 119, false) > 0.55);
+        assert_eq!(win.at_deal(121, 100, false), 1.0);
+        assert_eq!(win.at_deal(100, 121, true), 0.0);
+    }
+
+    #[test]
+    fn counts_follow_the_distribution() {
+        let win = WinProbabilities::standard();
+        let distribution = win.distribution();
+        let dealer_count = distribution.dealer_count();
+        for (me, opponent) in [(0, 0), (90, 100), (115, 118)] {
+            for my_deal in [true, false] {
+                let (mine, theirs) = if my_deal {
+                    (&dealer_count, &distribution.pone_hand)
+                } else {
+                    (&distribution.pone_hand, &dealer_count)
+                };
+                let p = win.before_pegging(me, opponent, my_deal, mine, theirs);
+                assert!((p - win.at_deal(me, opponent, my_deal)).abs() < 1e-5);
+            }
+        }
+        // needing 2 to win, a sure 2 points in hand wins after the pegging
+        assert_eq!(
+            win.after_pegging(119, 119, false, &[0.0, 0.0, 1.0], &dealer_count),
+            1.0
+        );
+    }
+
+    #[test]
+    fn shipped_probabilities_match_their_distribution() {
+        let shipped = WinProbabilities::standard();
+        assert!(shipped.distribution().deals >= 100_000);
+        let fresh = WinProbabilities::new(shipped.distribution().clone());
+        for (shipped, fresh) in shipped.dealer.iter().zip(&fresh.dealer) {
+            for (s, f) in shipped.iter().zip(fresh) {
+                assert!((s - f).abs() < 1e-4, "{} {}", s, f);
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_the_text_form() {
+        let shipped = WinProbabilities::standard();
+        let text = shipped.to_string();
+        assert_eq!(&text.parse::<WinProbabilities>().unwrap(), shipped);
+
+        let err = text
+            .replace("version 1", "version 2")
+            .parse::<WinProbabilities>()
+            .unwrap_err();
+        assert_eq!(err.error_kind, CribbageErrorKind::ParseError);
+        assert!(err.message.starts_with("line 2:"), "{}", err.message);
+        let short = text.lines().take(40).collect::<Vec<_>>().join("\n");
+        assert!(short.parse::<WinProbabilities>().is_err());
+    }
+
+    #[test]
+    fn reports_the_odds() {
+        let win = WinProbabilities::standard();
+        let odds = win.odds(98, 104, true).unwrap();
+        assert_eq!(odds.win_probability, win.at_deal(98, 104, true));
+        assert_eq!((odds.points_needed, odds.opponent_points_needed), (23, 17));
+        // the dealer has the crib, so expects more points
+        assert!(odds.expected_points > odds.opponent_expected_points);
+        assert!(odds.opponent_expected_points > 8.0);
+
+        assert_eq!(win.odds(121, 100, false).unwrap().win_probability, 1.0);
+        assert!(win.odds(122, 100, false).is_err());
+        assert!(win.odds(121, 121, false).is_err());
+    }
+
+    #[test]
+    fn shifts_and_convolves() {
+        let d = [0.5, 0.5];
+        assert_eq!(convolve(&d, &d), vec![0.25, 0.5, 0.25]);
+        let up = shifted(&d, 1.25);
+        assert!((mean(&up) - (mean(&d) + 1.25)).abs() < 1e-12);
+        assert!((up.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        let down = shifted(&[0.0, 0.0, 1.0], -0.5);
+        assert!((mean(&down) - 1.5).abs() < 1e-12);
+        assert_eq!(shifted(&[1.0], -3.0)[0], 1.0);
+    }
+}